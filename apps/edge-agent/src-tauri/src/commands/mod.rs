@@ -3,12 +3,30 @@
 //! These commands are called from the frontend via Tauri's invoke API.
 
 use crate::ollama::OllamaClient;
-use crate::storage::{Claim, Commitment, Evidence};
+use crate::storage::audit::{self, AuditVerification};
+use crate::storage::vault::{self, DataKey, KdfParams, VaultStatus};
+use crate::storage::{
+    AuditEntry, AuditRecord, CachedProof, Claim, ClaimChange, Commitment, CommitmentLeaf,
+    Database, DbPool, Evidence, EvidenceDependents, KeyStatus, LifecycleStage, RecoverySeedRecord,
+    Revocation, SignedAuditHead, StoredKeypair,
+};
+use crate::zk::preflight::{self, PredicateFailure};
+use crate::zk::{
+    CarbonFootprintInputs, CarbonFootprintLifecycleInputs, CertValidInputs, CircuitInputs,
+    EnergyDensityRangeInputs, LifecycleStageWitness, ProverBackend, RecycledContentInputs,
+    SubstanceNotInListInputs,
+};
 use crate::AppState;
-use chrono::Utc;
+use chrono::{DateTime, Utc};
 use commitments::{hash_claim, MerkleTree};
-use crypto::KeyPair;
+use crypto::{
+    data_integrity, DerivationPath, DidDocument, Domain, EncryptedKeystore, ExposeSecret, KeyPair,
+    KeystoreParams, Mnemonic, PublicKey, RotationCertificate, SecretString, Signable, SignedMessage,
+    Signer,
+};
 use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::sync::Arc;
 use tauri::State;
 use uuid::Uuid;
 
@@ -21,6 +39,9 @@ pub struct CommandResponse<T> {
     pub success: bool,
     pub data: Option<T>,
     pub error: Option<String>,
+    /// Structured failure details (e.g. why a predicate is not satisfied)
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub details: Option<serde_json::Value>,
 }
 
 impl<T> CommandResponse<T> {
@@ -29,6 +50,7 @@ impl<T> CommandResponse<T> {
             success: true,
             data: Some(data),
             error: None,
+            details: None,
         }
     }
 
@@ -37,6 +59,28 @@ impl<T> CommandResponse<T> {
             success: false,
             data: None,
             error: Some(error.to_string()),
+            details: None,
+        }
+    }
+
+    /// Rejects a proof request because the commitment no longer reproduces
+    /// its signed root.
+    pub fn integrity_error(failure: &PredicateFailure) -> Self {
+        CommandResponse {
+            success: false,
+            data: None,
+            error: Some(format!("Commitment integrity error: {}", failure)),
+            details: serde_json::to_value(failure).ok(),
+        }
+    }
+
+    /// Rejects a proof request whose predicate cannot be satisfied.
+    pub fn unsatisfied(failure: &PredicateFailure) -> Self {
+        CommandResponse {
+            success: false,
+            data: None,
+            error: Some(format!("Predicate not satisfied: {}", failure)),
+            details: serde_json::to_value(failure).ok(),
         }
     }
 }
@@ -107,12 +151,20 @@ pub async fn ingest_document(
         valid_from: None,
         valid_until: None,
         created_at: Utc::now(),
+        shredded_at: None,
     };
 
-    // Store in database
-    let db = state.db.lock().map_err(|e| e.to_string())?;
-    db.insert_evidence(&evidence, Some(&content))
-        .map_err(|e| e.to_string())?;
+    // Seal the original document under its own key before taking the writer
+    let sealed = match Database::seal_evidence(&state.vault, &evidence, Some(&content)) {
+        Ok(sealed) => sealed,
+        Err(e) => return Ok(CommandResponse::err(&e.to_string())),
+    };
+    let db = state.db.write().map_err(|e| e.to_string())?;
+    audited(&state, &db, || {
+        db.insert_sealed_evidence(&sealed)?;
+        Ok(((), Some(AuditRecord::new("ingest_document", "evidence", &evidence.id).after(&evidence)?)))
+    })
+    .map_err(|e| e.to_string())?;
 
     Ok(CommandResponse::ok(evidence))
 }
@@ -121,7 +173,7 @@ pub async fn ingest_document(
 pub async fn list_evidence(
     state: State<'_, AppState>,
 ) -> Result<CommandResponse<Vec<Evidence>>, String> {
-    let db = state.db.lock().map_err(|e| e.to_string())?;
+    let db = state.db.read().map_err(|e| e.to_string())?;
     match db.list_evidence() {
         Ok(evidence) => Ok(CommandResponse::ok(evidence)),
         Err(e) => Ok(CommandResponse::err(&e.to_string())),
@@ -133,7 +185,7 @@ pub async fn get_evidence(
     id: String,
     state: State<'_, AppState>,
 ) -> Result<CommandResponse<Option<Evidence>>, String> {
-    let db = state.db.lock().map_err(|e| e.to_string())?;
+    let db = state.db.read().map_err(|e| e.to_string())?;
     match db.get_evidence(&id) {
         Ok(evidence) => Ok(CommandResponse::ok(evidence)),
         Err(e) => Ok(CommandResponse::err(&e.to_string())),
@@ -145,13 +197,108 @@ pub async fn delete_evidence(
     id: String,
     state: State<'_, AppState>,
 ) -> Result<CommandResponse<bool>, String> {
-    let db = state.db.lock().map_err(|e| e.to_string())?;
-    match db.delete_evidence(&id) {
+    let db = state.db.write().map_err(|e| e.to_string())?;
+    let before = match db.get_evidence(&id) {
+        Ok(Some(evidence)) => evidence,
+        Ok(None) => return Ok(CommandResponse::ok(false)),
+        Err(e) => return Ok(CommandResponse::err(&e.to_string())),
+    };
+    let deleted = audited(&state, &db, || {
+        let deleted = db.delete_evidence(&id)?;
+        Ok((deleted, Some(AuditRecord::new("delete_evidence", "evidence", &id).before(&before)?)))
+    });
+    match deleted {
         Ok(deleted) => Ok(CommandResponse::ok(deleted)),
         Err(e) => Ok(CommandResponse::err(&e.to_string())),
     }
 }
 
+#[tauri::command]
+pub async fn get_evidence_dependents(
+    id: String,
+    state: State<'_, AppState>,
+) -> Result<CommandResponse<EvidenceDependents>, String> {
+    let db = state.db.read().map_err(|e| e.to_string())?;
+    match db.get_evidence_dependents(&id) {
+        Ok(dependents) => Ok(CommandResponse::ok(dependents)),
+        Err(e) => Ok(CommandResponse::err(&e.to_string())),
+    }
+}
+
+/// Destroys the evidence content but keeps its record and content hash
+#[tauri::command]
+pub async fn shred_evidence(
+    id: String,
+    state: State<'_, AppState>,
+) -> Result<CommandResponse<bool>, String> {
+    let db = state.db.write().map_err(|e| e.to_string())?;
+    let before = match db.get_evidence(&id) {
+        Ok(Some(evidence)) => evidence,
+        Ok(None) => return Ok(CommandResponse::err("Evidence not found")),
+        Err(e) => return Ok(CommandResponse::err(&e.to_string())),
+    };
+    let shredded = audited(&state, &db, || {
+        if !db.shred_evidence(&id)? {
+            return Ok((false, None));
+        }
+        let after = db.get_evidence(&id)?;
+        let record = AuditRecord::new("shred_evidence", "evidence", &id).before(&before)?.after(&after)?;
+        Ok((true, Some(record)))
+    });
+    match shredded {
+        Ok(shredded) => Ok(CommandResponse::ok(shredded)),
+        Err(e) => Ok(CommandResponse::err(&e.to_string())),
+    }
+}
+
+/// Shreds evidence past its retention period; returns the IDs shredded
+#[tauri::command]
+pub async fn apply_retention_policies(
+    state: State<'_, AppState>,
+) -> Result<CommandResponse<Vec<String>>, String> {
+    match apply_retention(&state.db, Utc::now()) {
+        Ok(shredded) => Ok(CommandResponse::ok(shredded)),
+        Err(e) => Ok(CommandResponse::err(&e.to_string())),
+    }
+}
+
+/// Retention periods in days by evidence type (`evidence_retention_days`
+/// setting); types without an entry are kept indefinitely
+fn retention_policies(db: &Database) -> anyhow::Result<BTreeMap<String, u32>> {
+    match db.get_setting("evidence_retention_days")? {
+        Some(json) => serde_json::from_str(&json)
+            .map_err(|e| anyhow::anyhow!("Invalid evidence_retention_days setting: {}", e)),
+        None => Ok(BTreeMap::new()),
+    }
+}
+
+/// Crypto-shreds evidence ingested longer ago than its type's retention
+/// period. Expired evidence is found on a reader; the writer is taken only
+/// to shred it. Runs without the vault, so entries carry no state hashes.
+pub fn apply_retention(pool: &DbPool, now: chrono::DateTime<Utc>) -> anyhow::Result<Vec<String>> {
+    let expired = {
+        let db = pool.read()?;
+        let mut expired = Vec::new();
+        for (evidence_type, days) in retention_policies(&db)? {
+            let cutoff = now - chrono::Duration::days(i64::from(days));
+            expired.extend(db.expired_evidence(&evidence_type, cutoff)?);
+        }
+        expired
+    };
+    if expired.is_empty() {
+        return Ok(expired);
+    }
+
+    let db = pool.write()?;
+    db.atomic(|| {
+        let shredded = db.shred_evidence_ids(&expired)?;
+        for id in &shredded {
+            db.append_audit(&AuditRecord::new("apply_retention", "evidence", id))?;
+        }
+        Ok(shredded)
+    })
+}
+
 // ============================================================================
 // Claim commands
 // ============================================================================
@@ -169,7 +316,7 @@ pub async fn extract_claims(
 ) -> Result<CommandResponse<Vec<Claim>>, String> {
     // Get evidence
     let evidence = {
-        let db = state.db.lock().map_err(|e| e.to_string())?;
+        let db = state.db.read().map_err(|e| e.to_string())?;
         match db.get_evidence(&input.evidence_id) {
             Ok(Some(e)) => e,
             Ok(None) => return Ok(CommandResponse::err("Evidence not found")),
@@ -184,7 +331,7 @@ pub async fn extract_claims(
 
     // Call Ollama for extraction (settings override env default)
     let (ollama_url, ollama_model) = {
-        let db = state.db.lock().map_err(|e| e.to_string())?;
+        let db = state.db.read().map_err(|e| e.to_string())?;
         let url = db.get_setting("ollama_url").ok().flatten().unwrap_or_else(|| state.ollama_base.clone());
         let model = db.get_setting("ollama_model").ok().flatten();
         (url, model)
@@ -222,11 +369,17 @@ pub async fn extract_claims(
 
     // Store claims
     {
-        let db = state.db.lock().map_err(|e| e.to_string())?;
-        for claim in &claims {
-            if let Err(e) = db.insert_claim(claim) {
-                return Ok(CommandResponse::err(&e.to_string()));
+        let db = state.db.write().map_err(|e| e.to_string())?;
+        let stored = audited(&state, &db, || {
+            let mut records = Vec::new();
+            for claim in &claims {
+                db.insert_claim(claim)?;
+                records.push(AuditRecord::new("extract_claims", "claim", &claim.id).after(claim)?);
             }
+            Ok(((), records))
+        });
+        if let Err(e) = stored {
+            return Ok(CommandResponse::err(&e.to_string()));
         }
     }
 
@@ -238,7 +391,7 @@ pub async fn list_claims(
     product_id: Option<String>,
     state: State<'_, AppState>,
 ) -> Result<CommandResponse<Vec<Claim>>, String> {
-    let db = state.db.lock().map_err(|e| e.to_string())?;
+    let db = state.db.read().map_err(|e| e.to_string())?;
     match db.list_claims(product_id.as_deref()) {
         Ok(claims) => Ok(CommandResponse::ok(claims)),
         Err(e) => Ok(CommandResponse::err(&e.to_string())),
@@ -250,7 +403,7 @@ pub async fn get_claim(
     id: String,
     state: State<'_, AppState>,
 ) -> Result<CommandResponse<Option<Claim>>, String> {
-    let db = state.db.lock().map_err(|e| e.to_string())?;
+    let db = state.db.read().map_err(|e| e.to_string())?;
     match db.get_claim(&id) {
         Ok(claim) => Ok(CommandResponse::ok(claim)),
         Err(e) => Ok(CommandResponse::err(&e.to_string())),
@@ -262,8 +415,32 @@ pub async fn update_claim(
     claim: Claim,
     state: State<'_, AppState>,
 ) -> Result<CommandResponse<bool>, String> {
-    let db = state.db.lock().map_err(|e| e.to_string())?;
-    match db.update_claim(&claim) {
+    let db = state.db.write().map_err(|e| e.to_string())?;
+    let before = match db.get_claim(&claim.id) {
+        Ok(Some(before)) => before,
+        Ok(None) => return Ok(CommandResponse::ok(false)),
+        Err(e) => return Ok(CommandResponse::err(&e.to_string())),
+    };
+    update_claim_audited(&state, &db, "update_claim", &before, &claim)
+}
+
+/// Stores an edited claim and logs it with its before and after states
+fn update_claim_audited(
+    state: &AppState,
+    db: &Database,
+    command: &str,
+    before: &Claim,
+    claim: &Claim,
+) -> Result<CommandResponse<bool>, String> {
+    let updated = audited(state, db, || {
+        if !db.update_claim(claim)? {
+            return Ok((false, None));
+        }
+        let after = db.get_claim(&claim.id)?;
+        let record = AuditRecord::new(command, "claim", &claim.id).before(before)?.after(&after)?;
+        Ok((true, Some(record)))
+    });
+    match updated {
         Ok(updated) => Ok(CommandResponse::ok(updated)),
         Err(e) => Ok(CommandResponse::err(&e.to_string())),
     }
@@ -274,35 +451,130 @@ pub async fn delete_claim(
     id: String,
     state: State<'_, AppState>,
 ) -> Result<CommandResponse<bool>, String> {
-    let db = state.db.lock().map_err(|e| e.to_string())?;
-    match db.delete_claim(&id) {
-        Ok(deleted) => Ok(CommandResponse::ok(deleted)),
+    let db = state.db.write().map_err(|e| e.to_string())?;
+    let before = match db.get_claim(&id) {
+        Ok(Some(before)) => before,
+        Ok(None) => return Ok(CommandResponse::ok(false)),
+        Err(e) => return Ok(CommandResponse::err(&e.to_string())),
+    };
+    let retired = audited(&state, &db, || {
+        if !db.retire_claim(&id)? {
+            return Ok((false, None));
+        }
+        Ok((true, Some(AuditRecord::new("delete_claim", "claim", &id).before(&before)?)))
+    });
+    match retired {
+        Ok(retired) => Ok(CommandResponse::ok(retired)),
         Err(e) => Ok(CommandResponse::err(&e.to_string())),
     }
 }
 
+/// One entry in a claim's history, with the fields changed since the
+/// previous version and the commitments that froze this version.
+#[derive(Debug, Serialize)]
+pub struct ClaimHistoryEntry {
+    pub version: u32,
+    pub change: ClaimChange,
+    pub claim: Claim,
+    pub recorded_at: chrono::DateTime<Utc>,
+    pub committed_in: Vec<String>,
+    pub diff: Vec<FieldChange>,
+}
+
+#[derive(Debug, Serialize, PartialEq)]
+pub struct FieldChange {
+    pub field: String,
+    pub before: Option<serde_json::Value>,
+    pub after: Option<serde_json::Value>,
+}
+
+#[tauri::command]
+pub async fn claim_history(
+    id: String,
+    state: State<'_, AppState>,
+) -> Result<CommandResponse<Vec<ClaimHistoryEntry>>, String> {
+    let db = state.db.read().map_err(|e| e.to_string())?;
+    claim_history_with(&db, &id)
+}
+
+fn claim_history_with(
+    db: &Database,
+    id: &str,
+) -> Result<CommandResponse<Vec<ClaimHistoryEntry>>, String> {
+    let versions = match db.get_claim_history(id) {
+        Ok(versions) if versions.is_empty() => return Ok(CommandResponse::err("Claim not found")),
+        Ok(versions) => versions,
+        Err(e) => return Ok(CommandResponse::err(&e.to_string())),
+    };
+
+    let mut entries = Vec::with_capacity(versions.len());
+    let mut previous: Option<&Claim> = None;
+    for version in &versions {
+        let committed_in = db
+            .commitments_for_claim_version(id, version.version)
+            .map_err(|e| e.to_string())?;
+        let diff = match previous {
+            Some(before) => diff_claims(before, &version.claim)?,
+            None => Vec::new(),
+        };
+        entries.push(ClaimHistoryEntry {
+            version: version.version,
+            change: version.change,
+            claim: version.claim.clone(),
+            recorded_at: version.recorded_at,
+            committed_in,
+            diff,
+        });
+        previous = Some(&version.claim);
+    }
+
+    Ok(CommandResponse::ok(entries))
+}
+
+/// Field-level diff of two claim versions; bookkeeping fields are skipped.
+fn diff_claims(before: &Claim, after: &Claim) -> Result<Vec<FieldChange>, String> {
+    const IGNORED: [&str; 3] = ["id", "created_at", "updated_at"];
+
+    let before = serde_json::to_value(before).map_err(|e| e.to_string())?;
+    let after = serde_json::to_value(after).map_err(|e| e.to_string())?;
+    let (before, after) = match (before.as_object(), after.as_object()) {
+        (Some(before), Some(after)) => (before, after),
+        _ => return Ok(Vec::new()),
+    };
+
+    let fields: std::collections::BTreeSet<&String> = before.keys().chain(after.keys()).collect();
+    Ok(fields
+        .into_iter()
+        .filter(|field| !IGNORED.contains(&field.as_str()))
+        .filter(|field| before.get(*field) != after.get(*field))
+        .map(|field| FieldChange {
+            field: field.clone(),
+            before: before.get(field).cloned(),
+            after: after.get(field).cloned(),
+        })
+        .collect())
+}
+
 #[tauri::command]
 pub async fn verify_claim(
     id: String,
     verified: bool,
     state: State<'_, AppState>,
 ) -> Result<CommandResponse<bool>, String> {
-    let db = state.db.lock().map_err(|e| e.to_string())?;
+    let db = state.db.write().map_err(|e| e.to_string())?;
 
     // Get existing claim
-    let mut claim = match db.get_claim(&id) {
+    let before = match db.get_claim(&id) {
         Ok(Some(c)) => c,
         Ok(None) => return Ok(CommandResponse::err("Claim not found")),
         Err(e) => return Ok(CommandResponse::err(&e.to_string())),
     };
 
+    let mut claim = before.clone();
     claim.verified = verified;
     claim.updated_at = Utc::now();
 
-    match db.update_claim(&claim) {
-        Ok(updated) => Ok(CommandResponse::ok(updated)),
-        Err(e) => Ok(CommandResponse::err(&e.to_string())),
-    }
+    update_claim_audited(&state, &db, "verify_claim", &before, &claim)
 }
 
 // ============================================================================
@@ -319,69 +591,108 @@ pub struct CreateCommitmentInput {
 pub async fn create_commitment(
     input: CreateCommitmentInput,
     state: State<'_, AppState>,
+) -> Result<CommandResponse<Commitment>, String> {
+    create_commitment_with(&state, input)
+}
+
+fn create_commitment_with(
+    state: &AppState,
+    input: CreateCommitmentInput,
 ) -> Result<CommandResponse<Commitment>, String> {
     if input.claim_ids.is_empty() {
         return Ok(CommandResponse::err("No claims specified"));
     }
+    let key = match state.vault.key() {
+        Ok(key) => key,
+        Err(e) => return Ok(CommandResponse::err(&e.to_string())),
+    };
 
-    let db = state.db.lock().map_err(|e| e.to_string())?;
+    // Load the signer and claims on a reader; the writer is taken only for
+    // the final transaction
+    let (signer, new_key, loaded) = {
+        let db = state.db.read().map_err(|e| e.to_string())?;
+
+        // Get the signer (or create a keypair if none exists)
+        let (signer, new_key): (Arc<dyn Signer>, _) = match active_signer(state, &db) {
+            Ok(Some(signer)) => (signer, None),
+            Ok(None) => {
+                let (kp, stored) = new_keypair(&key, &db).map_err(|e| e.to_string())?;
+                (Arc::new(kp), Some(stored))
+            }
+            Err(e) => return Ok(CommandResponse::err(&format!("{:#}", e))),
+        };
 
-    // Get keypair (or create one if none exists)
-    let keypair = match db.get_active_keypair() {
-        Ok(Some(kp)) => {
-            let secret = crate::storage::decode_secret_key(&kp.secret_key_encrypted)
-                .map_err(|e| e.to_string())?;
-            KeyPair::from_bytes(&secret).map_err(|e| e.to_string())?
-        }
-        Ok(None) => {
-            // Generate new keypair
-            let kp = KeyPair::generate();
-            let stored = crate::storage::StoredKeypair {
-                id: Uuid::new_v4().to_string(),
-                public_key: kp.public_key().key.clone(),
-                secret_key_encrypted: crate::storage::encode_secret_key(&kp.secret_bytes())
-                    .map_err(|e| e.to_string())?,
-                created_at: Utc::now(),
-                is_active: true,
+        let mut loaded: Vec<(Claim, u32)> = Vec::new();
+        for claim_id in &input.claim_ids {
+            if loaded.iter().any(|(claim, _)| claim.id == *claim_id) {
+                return Ok(CommandResponse::err(&format!("Claim {} listed more than once", claim_id)));
+            }
+            let claim = match db.get_claim(claim_id) {
+                Ok(Some(c)) => c,
+                Ok(None) => return Ok(CommandResponse::err(&format!("Claim {} not found", claim_id))),
+                Err(e) => return Ok(CommandResponse::err(&e.to_string())),
             };
-            db.insert_keypair(&stored).map_err(|e| e.to_string())?;
-            kp
+            let version = match db.get_claim_status(claim_id) {
+                Ok(Some(status)) if status.retired_at.is_some() => {
+                    return Ok(CommandResponse::err(&format!("Claim {} is retired", claim_id)));
+                }
+                Ok(Some(status)) => status.version,
+                Ok(None) => return Ok(CommandResponse::err(&format!("Claim {} not found", claim_id))),
+                Err(e) => return Ok(CommandResponse::err(&e.to_string())),
+            };
+            loaded.push((claim, version));
         }
-        Err(e) => return Ok(CommandResponse::err(&e.to_string())),
+        (signer, new_key, loaded)
     };
 
-    // Load claims and compute hashes
-    let mut claim_hashes = Vec::new();
-    for claim_id in &input.claim_ids {
-        let claim = match db.get_claim(claim_id) {
-            Ok(Some(c)) => c,
-            Ok(None) => return Ok(CommandResponse::err(&format!("Claim {} not found", claim_id))),
-            Err(e) => return Ok(CommandResponse::err(&e.to_string())),
-        };
-
-        // Hash the claim
+    let mut leaves = Vec::new();
+    for (claim, version) in loaded {
+        // Hash the claim and freeze its canonical encoding
         let hash = compute_claim_hash(&claim)?;
-        claim_hashes.push(hash);
+        let canonical = commitments::canonicalize(&claim).map_err(|e| e.to_string())?;
+        leaves.push((hash, claim.id, canonical, version));
     }
 
+    // Canonical leaf order: the same claim set always yields the same root
+    leaves.sort();
+    let claim_hashes: Vec<[u8; 32]> = leaves.iter().map(|(hash, _, _, _)| *hash).collect();
+    let claim_ids: Vec<String> = leaves.iter().map(|(_, id, _, _)| id.clone()).collect();
+
     // Build Merkle tree
     let tree = MerkleTree::build(claim_hashes).map_err(|e| e.to_string())?;
     let root = commitments::to_hex(&tree.root());
 
     // Sign the root
-    let signature = keypair.sign_hex(tree.root().as_slice());
+    let signature = match signer.sign_hex_in(Domain::COMMITMENT, tree.root().as_slice()) {
+        Ok(signature) => signature,
+        Err(e) => return Ok(CommandResponse::err(&format!("Signing failed: {}", e))),
+    };
 
     let now = Utc::now();
     let valid_until = input
         .valid_days
         .map(|days| now + chrono::Duration::days(days));
 
+    let commitment_id = Uuid::new_v4().to_string();
+    let snapshot: Vec<CommitmentLeaf> = leaves
+        .into_iter()
+        .enumerate()
+        .map(|(position, (hash, claim_id, canonical_claim, claim_version))| CommitmentLeaf {
+            commitment_id: commitment_id.clone(),
+            position,
+            claim_id,
+            claim_version,
+            leaf_hash: commitments::to_hex(&hash),
+            canonical_claim,
+        })
+        .collect();
+
     let commitment = Commitment {
-        id: Uuid::new_v4().to_string(),
+        id: commitment_id,
         root,
-        claim_count: input.claim_ids.len(),
-        claim_ids: input.claim_ids,
-        public_key: keypair.public_key().key,
+        claim_count: claim_ids.len(),
+        claim_ids,
+        public_key: signer.public_key().to_hex(),
         signature,
         valid_from: Some(now),
         valid_until,
@@ -389,9 +700,36 @@ pub async fn create_commitment(
         revoked_at: None,
         revoked_reason: None,
         created_at: now,
+        revocation: None,
     };
 
-    db.insert_commitment(&commitment).map_err(|e| e.to_string())?;
+    let db = state.db.write().map_err(|e| e.to_string())?;
+    let stored = audited(state, &db, || {
+        // Claims edited or retired since they were read no longer match the
+        // snapshot; a keypair another first commitment created must not be
+        // replaced
+        for leaf in &snapshot {
+            match db.get_claim_status(&leaf.claim_id)? {
+                Some(status) if status.retired_at.is_none() && status.version == leaf.claim_version => {}
+                _ => return Ok((false, Vec::new())),
+            }
+        }
+        let mut records = Vec::new();
+        if let Some(stored) = &new_key {
+            if db.get_active_keypair()?.is_some() {
+                return Ok((false, Vec::new()));
+            }
+            db.insert_keypair(stored)?;
+            records.push(AuditRecord::new("create_commitment", "keypair", &stored.id).after(&stored.public_key)?);
+        }
+        db.insert_commitment_snapshot(&commitment, &snapshot)?;
+        records.push(AuditRecord::new("create_commitment", "commitment", &commitment.id).after(&commitment)?);
+        Ok((true, records))
+    })
+    .map_err(|e| e.to_string())?;
+    if !stored {
+        return Ok(CommandResponse::err("Claims or keys changed while the commitment was created; try again"));
+    }
 
     Ok(CommandResponse::ok(commitment))
 }
@@ -400,7 +738,7 @@ pub async fn create_commitment(
 pub async fn list_commitments(
     state: State<'_, AppState>,
 ) -> Result<CommandResponse<Vec<Commitment>>, String> {
-    let db = state.db.lock().map_err(|e| e.to_string())?;
+    let db = state.db.read().map_err(|e| e.to_string())?;
     match db.list_commitments() {
         Ok(commitments) => Ok(CommandResponse::ok(commitments)),
         Err(e) => Ok(CommandResponse::err(&e.to_string())),
@@ -412,7 +750,7 @@ pub async fn get_commitment(
     id: String,
     state: State<'_, AppState>,
 ) -> Result<CommandResponse<Option<Commitment>>, String> {
-    let db = state.db.lock().map_err(|e| e.to_string())?;
+    let db = state.db.read().map_err(|e| e.to_string())?;
     match db.get_commitment(&id) {
         Ok(commitment) => Ok(CommandResponse::ok(commitment)),
         Err(e) => Ok(CommandResponse::err(&e.to_string())),
@@ -425,8 +763,43 @@ pub async fn revoke_commitment(
     reason: String,
     state: State<'_, AppState>,
 ) -> Result<CommandResponse<bool>, String> {
-    let db = state.db.lock().map_err(|e| e.to_string())?;
-    match db.revoke_commitment(&id, &reason) {
+    let db = state.db.write().map_err(|e| e.to_string())?;
+    revoke_commitment_with(&state, &db, &id, &reason)
+}
+
+fn revoke_commitment_with(
+    state: &AppState,
+    db: &Database,
+    id: &str,
+    reason: &str,
+) -> Result<CommandResponse<bool>, String> {
+    let before = match db.get_commitment(id) {
+        Ok(Some(commitment)) => commitment,
+        Ok(None) => return Ok(CommandResponse::ok(false)),
+        Err(e) => return Ok(CommandResponse::err(&e.to_string())),
+    };
+
+    // Sign the revocation so brands can tell the supplier withdrew it
+    let revocation = Revocation {
+        commitment_id: id.to_string(),
+        root: before.root.clone(),
+        reason: reason.to_string(),
+        revoked_at: Utc::now(),
+    };
+    let signed = match active_signer(state, db).map_err(|e| format!("{:#}", e))? {
+        Some(signer) => match SignedMessage::sign(revocation.clone(), signer.as_ref()) {
+            Ok(signed) => Some(signed),
+            Err(e) => return Ok(CommandResponse::err(&format!("Signing failed: {}", e))),
+        },
+        None => None,
+    };
+    let revoked = audited(state, db, || {
+        let revoked = db.revoke_commitment(id, reason, revocation.revoked_at, signed.as_ref())?;
+        let after = db.get_commitment(id)?;
+        let record = AuditRecord::new("revoke_commitment", "commitment", id).before(&before)?.after(&after)?;
+        Ok((revoked, Some(record)))
+    });
+    match revoked {
         Ok(revoked) => Ok(CommandResponse::ok(revoked)),
         Err(e) => Ok(CommandResponse::err(&e.to_string())),
     }
@@ -440,8 +813,27 @@ pub async fn revoke_commitment(
 pub struct GenerateProofInput {
     pub commitment_id: String,
     pub predicate_id: String,
-    pub claim_index: usize,
+    /// Claim to open, by ID
+    #[serde(default, alias = "claimId")]
+    pub claim_id: Option<String>,
+    /// Claim to open, resolved to the only claim of this type in the commitment
+    /// (defaults to the predicate's claim type)
+    #[serde(default, alias = "claimType")]
+    pub claim_type: Option<String>,
+    /// Position in `commitment.claim_ids`; kept for older callers
+    #[serde(default, alias = "claimIndex")]
+    pub claim_index: Option<usize>,
     pub threshold: Option<u32>,
+    /// Lower bound for range predicates (e.g. ENERGY_DENSITY_RANGE_V1)
+    #[serde(default, alias = "minThreshold")]
+    pub min_threshold: Option<u32>,
+    /// Upper bound for range predicates
+    #[serde(default, alias = "maxThreshold")]
+    pub max_threshold: Option<u32>,
+    /// Claim ID of lifecycle stages for CARBON_FOOTPRINT_LIFECYCLE_V1; stages
+    /// left out are resolved by claim type
+    #[serde(default, alias = "stageClaims")]
+    pub stage_claims: Option<BTreeMap<LifecycleStage, String>>,
     pub timestamp: Option<u64>,
     pub extra: Option<serde_json::Value>,
     #[serde(alias = "product_binding", alias = "productBinding")]
@@ -461,6 +853,10 @@ pub struct PredicateId {
 #[serde(rename_all = "camelCase")]
 pub struct PublicInputs {
     pub threshold: Option<u32>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub min_threshold: Option<u32>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub max_threshold: Option<u32>,
     pub commitment_root: String,
     pub product_binding: String,
     pub requester_binding: String,
@@ -485,6 +881,27 @@ pub struct ProofPackage {
     pub nonce: String,
     pub generated_at: i64,
     pub context: ProofContext,
+    /// Key that signed the package; `None` if the supplier has no active key
+    pub supplier_public_key: Option<String>,
+    /// Signature over the package's signing payload in
+    /// [`Domain::PROOF_PACKAGE`]
+    pub supplier_signature: Option<String>,
+}
+
+impl Signable for ProofPackage {
+    const DOMAIN: Domain = Domain::PROOF_PACKAGE;
+
+    /// The package's canonical JSON without the signature
+    fn signing_payload(&self) -> crypto::Result<Vec<u8>> {
+        let mut value = serde_json::to_value(self)?;
+        if let Some(fields) = value.as_object_mut() {
+            fields.remove("supplierSignature");
+        }
+        // Canonicalizing a JSON value can only fail in serde_json
+        let canonical = commitments::canonicalize(&value)
+            .map_err(<serde_json::Error as serde::ser::Error>::custom)?;
+        Ok(canonical.into_bytes())
+    }
 }
 
 fn is_hex_32(value: &str) -> bool {
@@ -597,6 +1014,8 @@ fn substance_id_from_str(value: &str) -> [u8; 32] {
 const DOMAIN_SUBSTANCE_PRODUCT: [u8; 4] = *b"SUBP";
 const DOMAIN_SUBSTANCE_FORBIDDEN: [u8; 4] = *b"SUBF";
 const CARBON_FOOTPRINT_SCALE: u32 = 100;
+/// Default lifetime of a cached proof when no `proof_cache_ttl_secs` setting exists
+const DEFAULT_PROOF_CACHE_TTL_SECS: i64 = 24 * 60 * 60;
 
 fn hash_claim_type(claim_type: &str) -> [u8; 32] {
     commitments::hash_bytes(claim_type.as_bytes())
@@ -657,13 +1076,13 @@ fn hash_substance_list(
 
 fn compute_claim_hash(claim: &Claim) -> Result<[u8; 32], String> {
     match claim.claim_type.as_str() {
-        "recycled_content" => {
+        "recycled_content" | "energy_density" => {
             let actual_value = parse_u32_value(&claim.value)?;
             let claim_type_hash = hash_claim_type(&claim.claim_type);
             let unit_hash = hash_unit(&claim.unit);
             Ok(hash_claim_value(claim_type_hash, actual_value as u64, unit_hash))
         }
-        "carbon_footprint" => {
+        claim_type if claim_type == "carbon_footprint" || LifecycleStage::from_claim_type(claim_type).is_some() => {
             let actual_value = parse_scaled_u32_value(&claim.value, CARBON_FOOTPRINT_SCALE)?;
             let claim_type_hash = hash_claim_type(&claim.claim_type);
             let unit_hash = hash_unit(&claim.unit);
@@ -719,7 +1138,18 @@ pub async fn generate_proof(
     input: GenerateProofInput,
     state: State<'_, AppState>,
 ) -> Result<CommandResponse<ProofPackage>, String> {
-    let db = state.db.lock().map_err(|e| e.to_string())?;
+    generate_proof_with(&state, input, crate::zk::backend_from_env)
+}
+
+/// Proof generation with an explicit prover selection. Cached proofs are
+/// keyed by the backend too, so one backend's output is never served when
+/// another is selected.
+fn generate_proof_with(
+    state: &AppState,
+    input: GenerateProofInput,
+    select_backend: fn() -> anyhow::Result<Box<dyn ProverBackend>>,
+) -> Result<CommandResponse<ProofPackage>, String> {
+    let db = state.db.read().map_err(|e| e.to_string())?;
 
     // Get commitment
     let commitment = match db.get_commitment(&input.commitment_id) {
@@ -739,18 +1169,6 @@ pub async fn generate_proof(
         }
     }
 
-    // Load claim for the proof
-    if input.claim_index >= commitment.claim_ids.len() {
-        return Ok(CommandResponse::err("Invalid claim index"));
-    }
-
-    let claim_id = &commitment.claim_ids[input.claim_index];
-    let claim = match db.get_claim(claim_id) {
-        Ok(Some(c)) => c,
-        Ok(None) => return Ok(CommandResponse::err("Claim not found")),
-        Err(e) => return Ok(CommandResponse::err(&e.to_string())),
-    };
-
     // Generate nonce (hex, 16 bytes)
     let nonce = hex::encode(uuid::Uuid::new_v4().as_bytes());
 
@@ -761,105 +1179,168 @@ pub async fn generate_proof(
     let predicate_id = parse_predicate_id(&input.predicate_id)?;
     let supplier_id = db.get_setting("supplier_id").ok().flatten();
 
-    // Build Merkle proof for the selected claim
-    let mut claim_hashes = Vec::new();
-    for id in &commitment.claim_ids {
-        let c = match db.get_claim(id) {
-            Ok(Some(claim)) => claim,
-            Ok(None) => return Ok(CommandResponse::err("Claim not found in commitment")),
-            Err(e) => return Ok(CommandResponse::err(&e.to_string())),
-        };
-        let hash = compute_claim_hash(&c)?;
-        claim_hashes.push(hash);
-    }
+    // Rebuild the tree from the frozen snapshot; later claim edits or
+    // deletions do not affect what was committed
+    let leaves = db.get_commitment_leaves(&commitment.id).map_err(|e| e.to_string())?;
+    let (claims, claim_hashes) = if leaves.is_empty() {
+        match load_live_leaves(&db, &commitment) {
+            Ok(loaded) => loaded,
+            Err(failure) => return Ok(CommandResponse::integrity_error(&failure)),
+        }
+    } else {
+        match load_frozen_leaves(&leaves) {
+            Ok(loaded) => loaded,
+            Err(failure) => return Ok(CommandResponse::integrity_error(&failure)),
+        }
+    };
 
+    // Deeper trees than the circuits take would only fail inside nargo
+    if let Err(failure) = preflight::check_tree_depth(claim_hashes.len()) {
+        return Ok(CommandResponse::unsatisfied(&failure));
+    }
     let tree = MerkleTree::build(claim_hashes).map_err(|e| e.to_string())?;
-    let proof = tree.prove(input.claim_index);
 
-    // Predicate-specific proof generation (Noir CLI)
+    if let Err(failure) = preflight::check_commitment_root(&hex_to_bytes32(&commitment.root)?, &tree.root()) {
+        // Without a frozen snapshot, name the claim that changed since the
+        // commitment from its version history
+        let failure = if leaves.is_empty() {
+            find_diverged_live_leaf(&db, &commitment, &claims).unwrap_or(failure)
+        } else {
+            failure
+        };
+        return Ok(CommandResponse::integrity_error(&failure));
+    }
+
+    // Predicate-specific witness assembly
     let mut timestamp_override: Option<u64> = None;
     let mut extra_override: Option<serde_json::Value> = input.extra.clone();
 
-    let proof_hex = match (predicate_id.name.as_str(), predicate_id.version.as_str()) {
+    let commitment_root = hex_to_bytes32(&commitment.root)?;
+    let product_binding_bytes = hex_to_bytes32(&product_binding)?;
+    let requester_binding_bytes = hex_to_bytes32(&requester_binding)?;
+    let canonical_predicate = format!("{}_{}", predicate_id.name, predicate_id.version);
+
+    let circuit_inputs = match (predicate_id.name.as_str(), predicate_id.version.as_str()) {
         ("RECYCLED_CONTENT_GTE", "V1") => {
+            let (claim, proof) = match select_claim(&input, &claims, "recycled_content", &canonical_predicate) {
+                Ok(index) => (&claims[index], tree.prove(index)),
+                Err(e) => return Ok(CommandResponse::err(&e)),
+            };
             let threshold = input.threshold.ok_or_else(|| "Threshold required for RECYCLED_CONTENT_GTE_V1")?;
             let actual_value = parse_u32_value(&claim.value)?;
-            if claim.claim_type != "recycled_content" {
-                return Ok(CommandResponse::err("Claim type mismatch for RECYCLED_CONTENT_GTE_V1"));
+            if let Err(failure) = preflight::check_recycled_content_gte(actual_value, threshold) {
+                return Ok(CommandResponse::unsatisfied(&failure));
             }
 
-            let commitment_root = hex_to_bytes32(&commitment.root)?;
-            let product_binding_bytes = hex_to_bytes32(&product_binding)?;
-            let requester_binding_bytes = hex_to_bytes32(&requester_binding)?;
-
-            let claim_type_hash = hash_claim_type(&claim.claim_type);
-            let unit_hash = hash_unit(&claim.unit);
-
-            let config = crate::zk::NoirCliConfig::from_env()
-                .map_err(|e| format!("Noir CLI config error: {}", e))?;
-
-            let tree_depth = proof.path.len() as u32;
-
-            crate::zk::prove_recycled_content_gte(
-                &config,
-                crate::zk::RecycledContentInputs {
-                    threshold,
-                    commitment_root,
-                    product_binding: product_binding_bytes,
-                    requester_binding: requester_binding_bytes,
-                    actual_value,
-                    claim_type_hash,
-                    unit_hash,
-                    claim_hash: proof.leaf,
-                    merkle_path: proof.path,
-                    merkle_indices: proof.indices,
-                    tree_depth,
-                },
-            )
-            .map_err(|e| format!("Proof generation failed: {}", e))?
+            CircuitInputs::RecycledContentGte(RecycledContentInputs {
+                threshold,
+                commitment_root,
+                product_binding: product_binding_bytes,
+                requester_binding: requester_binding_bytes,
+                actual_value,
+                claim_type_hash: hash_claim_type(&claim.claim_type),
+                unit_hash: hash_unit(&claim.unit),
+                claim_hash: proof.leaf,
+                tree_depth: proof.path.len() as u32,
+                merkle_path: proof.path,
+                merkle_indices: proof.indices,
+            })
         }
         ("CARBON_FOOTPRINT_LTE", "V1") => {
+            let (claim, proof) = match select_claim(&input, &claims, "carbon_footprint", &canonical_predicate) {
+                Ok(index) => (&claims[index], tree.prove(index)),
+                Err(e) => return Ok(CommandResponse::err(&e)),
+            };
             let threshold = input.threshold.ok_or_else(|| "Threshold required for CARBON_FOOTPRINT_LTE_V1")?;
             let actual_value = parse_scaled_u32_value(&claim.value, CARBON_FOOTPRINT_SCALE)?;
-            if claim.claim_type != "carbon_footprint" {
-                return Ok(CommandResponse::err("Claim type mismatch for CARBON_FOOTPRINT_LTE_V1"));
+            if let Err(failure) = preflight::check_carbon_footprint_lte(actual_value, threshold) {
+                return Ok(CommandResponse::unsatisfied(&failure));
             }
 
-            let commitment_root = hex_to_bytes32(&commitment.root)?;
-            let product_binding_bytes = hex_to_bytes32(&product_binding)?;
-            let requester_binding_bytes = hex_to_bytes32(&requester_binding)?;
+            CircuitInputs::CarbonFootprintLte(CarbonFootprintInputs {
+                threshold,
+                commitment_root,
+                product_binding: product_binding_bytes,
+                requester_binding: requester_binding_bytes,
+                actual_value,
+                claim_type_hash: hash_claim_type(&claim.claim_type),
+                unit_hash: hash_unit(&claim.unit),
+                claim_hash: proof.leaf,
+                tree_depth: proof.path.len() as u32,
+                merkle_path: proof.path,
+                merkle_indices: proof.indices,
+            })
+        }
+        ("CARBON_FOOTPRINT_LIFECYCLE", "V1") => {
+            let threshold = input.threshold.ok_or_else(|| "Threshold required for CARBON_FOOTPRINT_LIFECYCLE_V1")?;
+            let mut stages = Vec::new();
+            for stage in LifecycleStage::ALL {
+                let claim_id = input.stage_claims.as_ref()
+                    .and_then(|stages| stages.get(&stage))
+                    .map(String::as_str);
+                let index = match find_claim(&claims, claim_id, stage.claim_type(), &canonical_predicate) {
+                    Ok(index) => index,
+                    Err(e) => return Ok(CommandResponse::err(&e)),
+                };
+                let stage_claim = &claims[index];
+
+                let stage_proof = tree.prove(index);
+                stages.push(LifecycleStageWitness {
+                    value: parse_scaled_u32_value(&stage_claim.value, CARBON_FOOTPRINT_SCALE)? as u64,
+                    claim_type_hash: hash_claim_type(&stage_claim.claim_type),
+                    unit_hash: hash_unit(&stage_claim.unit),
+                    claim_hash: stage_proof.leaf,
+                    tree_depth: stage_proof.path.len() as u32,
+                    merkle_path: stage_proof.path,
+                    merkle_indices: stage_proof.indices,
+                });
+            }
 
-            let claim_type_hash = hash_claim_type(&claim.claim_type);
-            let unit_hash = hash_unit(&claim.unit);
+            let stage_values: Vec<u64> = stages.iter().map(|s| s.value).collect();
+            if let Err(failure) = preflight::check_carbon_footprint_lifecycle(&stage_values, threshold as u64) {
+                return Ok(CommandResponse::unsatisfied(&failure));
+            }
 
-            let config = crate::zk::NoirCliConfig::from_env()
-                .map_err(|e| format!("Noir CLI config error: {}", e))?;
-
-            let tree_depth = proof.path.len() as u32;
-
-            crate::zk::prove_carbon_footprint_lte(
-                &config,
-                crate::zk::CarbonFootprintInputs {
-                    threshold,
-                    commitment_root,
-                    product_binding: product_binding_bytes,
-                    requester_binding: requester_binding_bytes,
-                    actual_value,
-                    claim_type_hash,
-                    unit_hash,
-                    claim_hash: proof.leaf,
-                    merkle_path: proof.path,
-                    merkle_indices: proof.indices,
-                    tree_depth,
-                },
-            )
-            .map_err(|e| format!("Proof generation failed: {}", e))?
+            CircuitInputs::CarbonFootprintLifecycle(CarbonFootprintLifecycleInputs {
+                threshold: threshold as u64,
+                commitment_root,
+                product_binding: product_binding_bytes,
+                requester_binding: requester_binding_bytes,
+                stages,
+            })
         }
-        ("CERT_VALID", "V1") => {
-            if claim.claim_type != "certification" {
-                return Ok(CommandResponse::err("Claim type mismatch for CERT_VALID_V1"));
+        ("ENERGY_DENSITY_RANGE", "V1") => {
+            let (claim, proof) = match select_claim(&input, &claims, "energy_density", &canonical_predicate) {
+                Ok(index) => (&claims[index], tree.prove(index)),
+                Err(e) => return Ok(CommandResponse::err(&e)),
+            };
+            let min_threshold = input.min_threshold.ok_or_else(|| "min_threshold required for ENERGY_DENSITY_RANGE_V1")?;
+            let max_threshold = input.max_threshold.ok_or_else(|| "max_threshold required for ENERGY_DENSITY_RANGE_V1")?;
+            let actual_value = parse_u32_value(&claim.value)?;
+            if let Err(failure) = preflight::check_energy_density_range(actual_value, min_threshold, max_threshold) {
+                return Ok(CommandResponse::unsatisfied(&failure));
             }
 
+            CircuitInputs::EnergyDensityRange(EnergyDensityRangeInputs {
+                min_threshold,
+                max_threshold,
+                commitment_root,
+                product_binding: product_binding_bytes,
+                requester_binding: requester_binding_bytes,
+                actual_value,
+                claim_type_hash: hash_claim_type(&claim.claim_type),
+                unit_hash: hash_unit(&claim.unit),
+                claim_hash: proof.leaf,
+                tree_depth: proof.path.len() as u32,
+                merkle_path: proof.path,
+                merkle_indices: proof.indices,
+            })
+        }
+        ("CERT_VALID", "V1") => {
+            let (claim, proof) = match select_claim(&input, &claims, "certification", &canonical_predicate) {
+                Ok(index) => (&claims[index], tree.prove(index)),
+                Err(e) => return Ok(CommandResponse::err(&e)),
+            };
             let (valid_from, valid_until) = extract_cert_window(&claim.value)?;
             let check_timestamp = input.timestamp
                 .or_else(|| {
@@ -869,41 +1350,29 @@ pub async fn generate_proof(
                 })
                 .unwrap_or_else(|| Utc::now().timestamp() as u64);
             timestamp_override = Some(check_timestamp);
+            if let Err(failure) = preflight::check_cert_valid(valid_from, valid_until, check_timestamp) {
+                return Ok(CommandResponse::unsatisfied(&failure));
+            }
 
-            let commitment_root = hex_to_bytes32(&commitment.root)?;
-            let product_binding_bytes = hex_to_bytes32(&product_binding)?;
-            let requester_binding_bytes = hex_to_bytes32(&requester_binding)?;
-
-            let claim_type_hash = hash_claim_type(&claim.claim_type);
-
-            let config = crate::zk::NoirCliConfig::from_env()
-                .map_err(|e| format!("Noir CLI config error: {}", e))?;
-
-            let tree_depth = proof.path.len() as u32;
-
-            crate::zk::prove_cert_valid(
-                &config,
-                crate::zk::CertValidInputs {
-                    check_timestamp,
-                    commitment_root,
-                    product_binding: product_binding_bytes,
-                    requester_binding: requester_binding_bytes,
-                    valid_from,
-                    valid_until,
-                    claim_type_hash,
-                    claim_hash: proof.leaf,
-                    merkle_path: proof.path,
-                    merkle_indices: proof.indices,
-                    tree_depth,
-                },
-            )
-            .map_err(|e| format!("Proof generation failed: {}", e))?
+            CircuitInputs::CertValid(CertValidInputs {
+                check_timestamp,
+                commitment_root,
+                product_binding: product_binding_bytes,
+                requester_binding: requester_binding_bytes,
+                valid_from,
+                valid_until,
+                claim_type_hash: hash_claim_type(&claim.claim_type),
+                claim_hash: proof.leaf,
+                tree_depth: proof.path.len() as u32,
+                merkle_path: proof.path,
+                merkle_indices: proof.indices,
+            })
         }
         ("SUBSTANCE_NOT_IN_LIST", "V1") => {
-            if claim.claim_type != "substance_content" {
-                return Ok(CommandResponse::err("Claim type mismatch for SUBSTANCE_NOT_IN_LIST_V1"));
-            }
-
+            let (claim, proof) = match select_claim(&input, &claims, "substance_content", &canonical_predicate) {
+                Ok(index) => (&claims[index], tree.prove(index)),
+                Err(e) => return Ok(CommandResponse::err(&e)),
+            };
             let claim_obj = claim.value.as_object().ok_or_else(|| "Substance claim must be object".to_string())?;
             let product_substances = claim_obj.get("substances")
                 .ok_or_else(|| "Missing substances list".to_string())
@@ -918,41 +1387,20 @@ pub async fn generate_proof(
             let product_substances_bytes: Vec<[u8; 32]> = product_substances.iter().map(|s| substance_id_from_str(s)).collect();
             let forbidden_substances_bytes: Vec<[u8; 32]> = forbidden_substances_list.iter().map(|s| substance_id_from_str(s)).collect();
 
+            let named_substances: Vec<([u8; 32], &str)> = product_substances_bytes.iter()
+                .copied()
+                .zip(product_substances.iter().map(String::as_str))
+                .collect();
+            if let Err(failure) = preflight::check_substance_not_in_list(&named_substances, &forbidden_substances_bytes) {
+                return Ok(CommandResponse::unsatisfied(&failure));
+            }
+
             let num_substances = product_substances_bytes.len() as u32;
             let num_forbidden = forbidden_substances_bytes.len() as u32;
 
             let claim_type_hash = hash_claim_type(&claim.claim_type);
             let forbidden_list_hash = hash_substance_list(DOMAIN_SUBSTANCE_FORBIDDEN, claim_type_hash, &forbidden_substances_bytes, num_forbidden);
 
-            let commitment_root = hex_to_bytes32(&commitment.root)?;
-            let product_binding_bytes = hex_to_bytes32(&product_binding)?;
-            let requester_binding_bytes = hex_to_bytes32(&requester_binding)?;
-
-            let config = crate::zk::NoirCliConfig::from_env()
-                .map_err(|e| format!("Noir CLI config error: {}", e))?;
-
-            let tree_depth = proof.path.len() as u32;
-
-            let proof_hex = crate::zk::prove_substance_not_in_list(
-                &config,
-                crate::zk::SubstanceNotInListInputs {
-                    forbidden_list_hash,
-                    commitment_root,
-                    product_binding: product_binding_bytes,
-                    requester_binding: requester_binding_bytes,
-                    product_substances: product_substances_bytes,
-                    num_substances,
-                    forbidden_substances: forbidden_substances_bytes,
-                    num_forbidden,
-                    claim_type_hash,
-                    claim_hash: proof.leaf,
-                    merkle_path: proof.path,
-                    merkle_indices: proof.indices,
-                    tree_depth,
-                },
-            )
-            .map_err(|e| format!("Proof generation failed: {}", e))?;
-
             let mut extra = input.extra.clone().unwrap_or_else(|| serde_json::json!({}));
             if let Some(obj) = extra.as_object_mut() {
                 obj.insert(
@@ -962,23 +1410,80 @@ pub async fn generate_proof(
             }
             // Persist extra update for packaging
             extra_override = Some(extra);
+
+            CircuitInputs::SubstanceNotInList(SubstanceNotInListInputs {
+                forbidden_list_hash,
+                commitment_root,
+                product_binding: product_binding_bytes,
+                requester_binding: requester_binding_bytes,
+                product_substances: product_substances_bytes,
+                num_substances,
+                forbidden_substances: forbidden_substances_bytes,
+                num_forbidden,
+                claim_type_hash,
+                claim_hash: proof.leaf,
+                tree_depth: proof.path.len() as u32,
+                merkle_path: proof.path,
+                merkle_indices: proof.indices,
+            })
         }
         _ => {
             return Ok(CommandResponse::err("Predicate not supported by prover yet"));
         }
     };
 
-    let proof_package = ProofPackage {
+    let public_inputs = PublicInputs {
+        threshold: input.threshold,
+        min_threshold: input.min_threshold,
+        max_threshold: input.max_threshold,
+        commitment_root: commitment.root.clone(),
+        product_binding: product_binding.clone(),
+        requester_binding: requester_binding.clone(),
+        timestamp: timestamp_override.or(input.timestamp),
+        extra: extra_override,
+    };
+
+    // Reuse a cached proof for an identical statement from the same backend;
+    // the package still gets a fresh nonce and generation time below.
+    let backend = select_backend().map_err(|e| format!("{:#}", e))?;
+    let cache_key = proof_cache_key(backend.name(), &predicate_id, &public_inputs)?;
+    let ttl = db.get_setting("proof_cache_ttl_secs").ok().flatten()
+        .and_then(|v| v.parse::<i64>().ok())
+        .unwrap_or(DEFAULT_PROOF_CACHE_TTL_SECS);
+    let cached = db.get_cached_proof(&cache_key, Utc::now()).map_err(|e| e.to_string())?;
+    // Release the connection before proving so other commands are not blocked
+    drop(db);
+
+    let proof_hex = match cached {
+        Some(entry) => entry.proof,
+        None => {
+            let proof_hex = backend.prove(circuit_inputs)
+                .map_err(|e| format!("Proof generation failed ({}): {}", backend.name(), e))?;
+
+            let now = Utc::now();
+            let mut expires_at = now + chrono::Duration::seconds(ttl);
+            if let Some(valid_until) = commitment.valid_until {
+                expires_at = expires_at.min(valid_until);
+            }
+            let entry = CachedProof {
+                cache_key,
+                commitment_id: commitment.id.clone(),
+                predicate_id: canonical_predicate,
+                public_inputs: serde_json::to_value(&public_inputs).map_err(|e| e.to_string())?,
+                proof: proof_hex.clone(),
+                created_at: now,
+                expires_at,
+            };
+            state.db.write().map_err(|e| e.to_string())?
+                .insert_cached_proof(&entry).map_err(|e| e.to_string())?;
+            proof_hex
+        }
+    };
+
+    let mut proof_package = ProofPackage {
         predicate_id,
         proof: proof_hex,
-        public_inputs: PublicInputs {
-            threshold: input.threshold,
-            commitment_root: commitment.root,
-            product_binding: product_binding.clone(),
-            requester_binding: requester_binding.clone(),
-            timestamp: timestamp_override.or(input.timestamp),
-            extra: extra_override,
-        },
+        public_inputs,
         nonce,
         generated_at: Utc::now().timestamp_millis(),
         context: ProofContext {
@@ -986,11 +1491,175 @@ pub async fn generate_proof(
             requester_id: Some(input.requester_id),
             product_id: Some(input.product_id),
         },
+        supplier_public_key: None,
+        supplier_signature: None,
+    };
+
+    // Sign the package so a verifier can tell it came from the supplier
+    let signer = {
+        let db = state.db.read().map_err(|e| e.to_string())?;
+        active_signer(state, &db).map_err(|e| format!("{:#}", e))?
     };
+    if let Some(signer) = signer {
+        proof_package.supplier_public_key = Some(signer.public_key().to_hex());
+        let payload = proof_package.signing_payload().map_err(|e| e.to_string())?;
+        match signer.sign_hex_in(ProofPackage::DOMAIN, &payload) {
+            Ok(signature) => proof_package.supplier_signature = Some(signature),
+            Err(e) => return Ok(CommandResponse::err(&format!("Signing failed: {}", e))),
+        }
+    }
 
     Ok(CommandResponse::ok(proof_package))
 }
 
+type CommittedLeaves = (Vec<Claim>, Vec<[u8; 32]>);
+
+/// Decodes the frozen claims and checks each against its stored leaf hash.
+fn load_frozen_leaves(leaves: &[CommitmentLeaf]) -> Result<CommittedLeaves, PredicateFailure> {
+    let mut claims = Vec::new();
+    let mut claim_hashes = Vec::new();
+    for leaf in leaves {
+        let diverged = |actual: String| PredicateFailure::LeafMismatch {
+            claim_id: leaf.claim_id.clone(),
+            position: leaf.position,
+            expected: leaf.leaf_hash.clone(),
+            actual,
+        };
+        let claim: Claim = serde_json::from_str(&leaf.canonical_claim)
+            .map_err(|e| diverged(format!("undecodable snapshot ({})", e)))?;
+        let hash = compute_claim_hash(&claim).map_err(diverged)?;
+        if commitments::to_hex(&hash) != leaf.leaf_hash || claim.id != leaf.claim_id {
+            return Err(diverged(commitments::to_hex(&hash)));
+        }
+        claim_hashes.push(hash);
+        claims.push(claim);
+    }
+    Ok((claims, claim_hashes))
+}
+
+/// Loads live claims for commitments created before leaves were frozen.
+fn load_live_leaves(db: &Database, commitment: &Commitment) -> Result<CommittedLeaves, PredicateFailure> {
+    let mut claims = Vec::new();
+    let mut claim_hashes = Vec::new();
+    for (position, id) in commitment.claim_ids.iter().enumerate() {
+        let missing = |actual: String| PredicateFailure::LeafMismatch {
+            claim_id: id.clone(),
+            position,
+            expected: "committed claim".to_string(),
+            actual,
+        };
+        let claim = match db.get_claim(id) {
+            Ok(Some(claim)) => claim,
+            Ok(None) => return Err(missing("claim deleted".to_string())),
+            Err(e) => return Err(missing(e.to_string())),
+        };
+        claim_hashes.push(compute_claim_hash(&claim).map_err(missing)?);
+        claims.push(claim);
+    }
+    Ok((claims, claim_hashes))
+}
+
+/// Compares each live claim against the version it had when the commitment
+/// was created and reports the first one that differs.
+fn find_diverged_live_leaf(db: &Database, commitment: &Commitment, claims: &[Claim]) -> Option<PredicateFailure> {
+    for (position, claim) in claims.iter().enumerate() {
+        let history = db.get_claim_history(&claim.id).ok()?;
+        // History started at migration for older claims, so fall back to
+        // the earliest recorded version
+        let committed = history
+            .iter()
+            .rev()
+            .find(|version| version.recorded_at <= commitment.created_at)
+            .or_else(|| history.first())?;
+        let expected = compute_claim_hash(&committed.claim).ok()?;
+        let actual = compute_claim_hash(claim).ok()?;
+        if expected != actual {
+            return Some(PredicateFailure::LeafMismatch {
+                claim_id: claim.id.clone(),
+                position,
+                expected: format!("version {} ({})", committed.version, commitments::to_hex(&expected)),
+                actual: commitments::to_hex(&actual),
+            });
+        }
+    }
+    None
+}
+
+/// Resolves the single claim a predicate opens: by `claim_id`, by the legacy
+/// `claim_index`, or as the only claim of the requested type. The claim must
+/// have the predicate's claim type.
+fn select_claim(
+    input: &GenerateProofInput,
+    claims: &[Claim],
+    expected_type: &str,
+    predicate: &str,
+) -> Result<usize, String> {
+    if input.claim_id.is_none() {
+        if let Some(index) = input.claim_index {
+            let claim = claims.get(index).ok_or_else(|| "Invalid claim index".to_string())?;
+            check_claim_type(claim, expected_type, predicate)?;
+            return Ok(index);
+        }
+    }
+
+    let search_type = input.claim_type.as_deref().unwrap_or(expected_type);
+    let index = find_claim(claims, input.claim_id.as_deref(), search_type, predicate)?;
+    check_claim_type(&claims[index], expected_type, predicate)?;
+    Ok(index)
+}
+
+/// Finds a committed claim by ID, or the only claim of `claim_type` when no
+/// ID is given.
+fn find_claim(claims: &[Claim], claim_id: Option<&str>, claim_type: &str, predicate: &str) -> Result<usize, String> {
+    let index = match claim_id {
+        Some(id) => claims.iter()
+            .position(|c| c.id == id)
+            .ok_or_else(|| format!("Claim {} is not part of the commitment", id))?,
+        None => {
+            let matches: Vec<usize> = claims.iter()
+                .enumerate()
+                .filter(|(_, c)| c.claim_type == claim_type)
+                .map(|(i, _)| i)
+                .collect();
+            match matches.as_slice() {
+                [index] => *index,
+                [] => return Err(format!("Commitment has no {} claim", claim_type)),
+                _ => {
+                    return Err(format!(
+                        "Commitment has {} {} claims; specify claim_id",
+                        matches.len(),
+                        claim_type
+                    ))
+                }
+            }
+        }
+    };
+    check_claim_type(&claims[index], claim_type, predicate)?;
+    Ok(index)
+}
+
+fn check_claim_type(claim: &Claim, expected_type: &str, predicate: &str) -> Result<(), String> {
+    if claim.claim_type != expected_type {
+        return Err(format!(
+            "Claim type mismatch for {}: expected {}, found {}",
+            predicate, expected_type, claim.claim_type
+        ));
+    }
+    Ok(())
+}
+
+/// Cache key over the backend and the full public statement: commitment
+/// root, predicate, canonical public inputs and product/requester bindings.
+fn proof_cache_key(backend: &str, predicate_id: &PredicateId, public_inputs: &PublicInputs) -> Result<String, String> {
+    let statement = serde_json::json!({
+        "backend": backend,
+        "predicate": predicate_id,
+        "publicInputs": public_inputs,
+    });
+    let canonical = commitments::canonicalize(&statement).map_err(|e| e.to_string())?;
+    Ok(commitments::to_hex(&commitments::hash_bytes(canonical.as_bytes())))
+}
+
 // ============================================================================
 // Key management commands
 // ============================================================================
@@ -1000,51 +1669,921 @@ pub struct KeypairInfo {
     pub id: String,
     pub public_key: String,
     pub created_at: String,
+    pub status: KeyStatus,
+    pub retired_at: Option<String>,
+    pub retired_reason: Option<String>,
+    pub derivation_path: Option<String>,
 }
 
-#[tauri::command]
-pub async fn get_keypair(
-    state: State<'_, AppState>,
-) -> Result<CommandResponse<Option<KeypairInfo>>, String> {
-    let db = state.db.lock().map_err(|e| e.to_string())?;
-    match db.get_active_keypair() {
-        Ok(Some(kp)) => Ok(CommandResponse::ok(Some(KeypairInfo {
+impl From<StoredKeypair> for KeypairInfo {
+    fn from(kp: StoredKeypair) -> Self {
+        KeypairInfo {
+            status: kp.status(),
             id: kp.id,
             public_key: kp.public_key,
             created_at: kp.created_at.to_rfc3339(),
-        }))),
-        Ok(None) => Ok(CommandResponse::ok(None)),
-        Err(e) => Ok(CommandResponse::err(&e.to_string())),
+            retired_at: kp.retired_at.map(|d| d.to_rfc3339()),
+            retired_reason: kp.retired_reason,
+            derivation_path: kp.derivation_path,
+        }
     }
 }
 
-#[tauri::command]
-pub async fn generate_new_keypair(
-    state: State<'_, AppState>,
-) -> Result<CommandResponse<KeypairInfo>, String> {
-    let db = state.db.lock().map_err(|e| e.to_string())?;
+/// A new active key and the certificate linking it to the key it replaced
+#[derive(Debug, Serialize)]
+pub struct KeyRotation {
+    pub keypair: KeypairInfo,
+    pub certificate: Option<RotationCertificate>,
+}
 
-    let kp = KeyPair::generate();
-    let now = Utc::now();
+/// Rotation certificates from the supplier's first key to the active one
+#[derive(Debug, Serialize)]
+pub struct KeyChainExport {
+    pub root_public_key: String,
+    pub current_public_key: String,
+    pub certificates: Vec<RotationCertificate>,
+}
 
-    let stored = crate::storage::StoredKeypair {
+/// Seals a freshly generated keypair as the active one
+fn seal_keypair(key: &DataKey, kp: &KeyPair, derivation_path: Option<String>) -> anyhow::Result<StoredKeypair> {
+    Ok(StoredKeypair {
         id: Uuid::new_v4().to_string(),
-        public_key: kp.public_key().key.clone(),
-        secret_key_encrypted: crate::storage::encode_secret_key(&kp.secret_bytes())
-            .map_err(|e| e.to_string())?,
-        created_at: now,
+        public_key: kp.public_key().to_hex(),
+        secret_key_encrypted: key.encrypt_keypair(kp)?,
+        created_at: Utc::now(),
         is_active: true,
-    };
+        retired_at: None,
+        retired_reason: None,
+        compromised: false,
+        derivation_path,
+    })
+}
 
-    db.insert_keypair(&stored).map_err(|e| e.to_string())?;
+/// The next signing key: derived at the next unused index on the default
+/// account once a recovery phrase exists, random otherwise
+fn new_keypair(key: &DataKey, db: &Database) -> anyhow::Result<(KeyPair, StoredKeypair)> {
+    let record = match db.get_recovery_seed()? {
+        Some(record) => record,
+        None => {
+            let kp = KeyPair::generate();
+            let stored = seal_keypair(key, &kp, None)?;
+            return Ok((kp, stored));
+        }
+    };
+    let next_index = db
+        .list_keypairs()?
+        .iter()
+        .filter_map(|kp| kp.derivation_path.as_deref()?.parse::<DerivationPath>().ok())
+        .filter_map(|path| match path.indices() {
+            [RECOVERY_ACCOUNT, index] => Some(index + 1),
+            _ => None,
+        })
+        .max()
+        .unwrap_or(0);
+    let path = DerivationPath::new(vec![RECOVERY_ACCOUNT, next_index])?;
+    let kp = KeyPair::derive(key.decrypt_secret(&record.sealed_seed)?.expose_secret(), &path)?;
+    let stored = seal_keypair(key, &kp, Some(path.to_string()))?;
+    Ok((kp, stored))
+}
+
+/// The configured external signer, else the vault's active keypair; `None`
+/// if neither exists
+fn active_signer(state: &AppState, db: &Database) -> anyhow::Result<Option<Arc<dyn Signer>>> {
+    if let Some(signer) = &state.signer {
+        return Ok(Some(Arc::clone(signer)));
+    }
+    match db.get_active_keypair()? {
+        Some(stored) => {
+            let keypair = state.vault.key()?.decrypt_keypair(&stored.secret_key_encrypted)?;
+            Ok(Some(Arc::new(keypair)))
+        }
+        None => Ok(None),
+    }
+}
+
+/// Replaces the active key with a new one. The outgoing key signs a rotation
+/// certificate for its successor through the active signer; with no active
+/// key, the new one starts a fresh chain. An external signer keeps signing
+/// with its own key, so its key is rotated in the KMS or HSM instead.
+fn rotate_keypair_with(
+    state: &AppState,
+    db: &Database,
+    command: &str,
+    reason: &str,
+) -> Result<KeyRotation, String> {
+    if state.signer.is_some() {
+        return Err("Signing uses the configured external signer; rotate its key in the KMS or HSM".to_string());
+    }
+    let key = state.vault.key().map_err(|e| e.to_string())?;
+    let (next, stored) = new_keypair(&key, db).map_err(|e| e.to_string())?;
+
+    let previous = db.get_active_keypair().map_err(|e| e.to_string())?;
+    let outgoing = active_signer(state, db).map_err(|e| e.to_string())?;
+    let certificate = audited(state, db, || {
+        let mut record = AuditRecord::new(command, "keypair", &stored.id);
+        let certificate = match (&previous, &outgoing) {
+            (Some(previous), Some(outgoing)) => {
+                let issued_at = stored.created_at.timestamp() as u64;
+                let certificate = RotationCertificate::issue(outgoing.as_ref(), &next, issued_at)?;
+                db.rotate_keypair(&previous.id, &stored, &certificate, reason)?;
+                record = record.before(&previous.public_key)?;
+                Some(certificate)
+            }
+            _ => {
+                db.insert_keypair(&stored)?;
+                None
+            }
+        };
+        Ok((certificate, Some(record.after(&stored.public_key)?)))
+    })
+    .map_err(|e| e.to_string())?;
+
+    Ok(KeyRotation {
+        keypair: stored.into(),
+        certificate,
+    })
+}
+
+#[tauri::command]
+pub async fn get_keypair(
+    state: State<'_, AppState>,
+) -> Result<CommandResponse<Option<KeypairInfo>>, String> {
+    let db = state.db.read().map_err(|e| e.to_string())?;
+    match db.get_active_keypair() {
+        Ok(kp) => Ok(CommandResponse::ok(kp.map(KeypairInfo::from))),
+        Err(e) => Ok(CommandResponse::err(&e.to_string())),
+    }
+}
+
+/// Every keypair this agent has held, oldest first
+#[tauri::command]
+pub async fn list_keypairs(
+    state: State<'_, AppState>,
+) -> Result<CommandResponse<Vec<KeypairInfo>>, String> {
+    let db = state.db.read().map_err(|e| e.to_string())?;
+    match db.list_keypairs() {
+        Ok(keypairs) => Ok(CommandResponse::ok(keypairs.into_iter().map(KeypairInfo::from).collect())),
+        Err(e) => Ok(CommandResponse::err(&e.to_string())),
+    }
+}
+
+#[tauri::command]
+pub async fn generate_new_keypair(
+    state: State<'_, AppState>,
+) -> Result<CommandResponse<KeypairInfo>, String> {
+    if let Err(e) = state.vault.key() {
+        return Ok(CommandResponse::err(&e.to_string()));
+    }
+    let db = state.db.write().map_err(|e| e.to_string())?;
+    let rotation = rotate_keypair_with(&state, &db, "generate_new_keypair", "replaced")?;
+    Ok(CommandResponse::ok(rotation.keypair))
+}
+
+/// Replaces the active key, linking the two with a rotation certificate
+#[tauri::command]
+pub async fn rotate_keypair(
+    reason: Option<String>,
+    state: State<'_, AppState>,
+) -> Result<CommandResponse<KeyRotation>, String> {
+    if let Err(e) = state.vault.key() {
+        return Ok(CommandResponse::err(&e.to_string()));
+    }
+    let db = state.db.write().map_err(|e| e.to_string())?;
+    match db.get_active_keypair() {
+        Ok(Some(_)) => {}
+        Ok(None) => return Ok(CommandResponse::err("No active keypair to rotate")),
+        Err(e) => return Ok(CommandResponse::err(&e.to_string())),
+    }
+    let reason = reason.unwrap_or_else(|| "rotated".to_string());
+    Ok(CommandResponse::ok(rotate_keypair_with(&state, &db, "rotate_keypair", &reason)?))
+}
+
+/// Retires an inactive key. The active key is retired by rotating away from
+/// it, so its successor stays linked.
+#[tauri::command]
+pub async fn retire_keypair(
+    id: String,
+    reason: String,
+    state: State<'_, AppState>,
+) -> Result<CommandResponse<KeypairInfo>, String> {
+    let db = state.db.write().map_err(|e| e.to_string())?;
+    set_keypair_retired(&state, &db, &id, &reason, false)
+}
+
+/// Marks a key as compromised and stops it signing. A compromised active key
+/// is not rotated: it cannot vouch for a successor, so the next key starts a
+/// new chain that brands must trust afresh.
+#[tauri::command]
+pub async fn mark_keypair_compromised(
+    id: String,
+    reason: String,
+    state: State<'_, AppState>,
+) -> Result<CommandResponse<KeypairInfo>, String> {
+    let db = state.db.write().map_err(|e| e.to_string())?;
+    set_keypair_retired(&state, &db, &id, &reason, true)
+}
+
+fn set_keypair_retired(
+    state: &AppState,
+    db: &Database,
+    id: &str,
+    reason: &str,
+    compromised: bool,
+) -> Result<CommandResponse<KeypairInfo>, String> {
+    let before = match db.get_keypair(id) {
+        Ok(Some(kp)) => kp,
+        Ok(None) => return Ok(CommandResponse::err(&format!("Keypair {} not found", id))),
+        Err(e) => return Ok(CommandResponse::err(&e.to_string())),
+    };
+    if before.compromised {
+        return Ok(CommandResponse::err(&format!("Keypair {} is already compromised", id)));
+    }
+    if before.is_active && !compromised {
+        return Ok(CommandResponse::err("Rotate to a new keypair before retiring the active one"));
+    }
+    let command = if compromised { "mark_keypair_compromised" } else { "retire_keypair" };
+    let after = audited(state, db, || {
+        db.retire_keypair(id, reason, compromised)?;
+        let after = db
+            .get_keypair(id)?
+            .ok_or_else(|| anyhow::anyhow!("Keypair {} not found", id))?;
+        let record = AuditRecord::new(command, "keypair", id).before(&before.status())?.after(&after.status())?;
+        Ok((after, Some(record)))
+    });
+    match after {
+        Ok(after) => Ok(CommandResponse::ok(after.into())),
+        Err(e) => Ok(CommandResponse::err(&e.to_string())),
+    }
+}
+
+/// Rotation certificates from the first key in the active key's chain, for
+/// brands to check with `crypto::verify_key_chain`
+#[tauri::command]
+pub async fn export_key_chain(
+    state: State<'_, AppState>,
+) -> Result<CommandResponse<KeyChainExport>, String> {
+    let db = state.db.read().map_err(|e| e.to_string())?;
+    let active = match db.get_active_keypair() {
+        Ok(Some(kp)) => kp,
+        Ok(None) => return Ok(CommandResponse::err("No active keypair")),
+        Err(e) => return Ok(CommandResponse::err(&e.to_string())),
+    };
+    let certificates = match db.key_chain(&active.public_key) {
+        Ok(certificates) => certificates,
+        Err(e) => return Ok(CommandResponse::err(&e.to_string())),
+    };
+    let root_public_key = certificates
+        .first()
+        .map(|c| c.previous_key.clone())
+        .unwrap_or_else(|| active.public_key.clone());
+
+    let verified = PublicKey::from_hex(&root_public_key)
+        .and_then(|root| crypto::verify_key_chain(&root, &certificates));
+    if let Err(e) = verified {
+        return Ok(CommandResponse::err(&e.to_string()));
+    }
+
+    Ok(CommandResponse::ok(KeyChainExport {
+        root_public_key,
+        current_public_key: active.public_key,
+        certificates,
+    }))
+}
+
+/// Current key backup format version
+const KEY_BACKUP_VERSION: u32 = 1;
+
+/// Every signing key, each in a password-protected keystore, with the
+/// rotation certificates linking them
+#[derive(Debug, Serialize, Deserialize)]
+pub struct KeyBackup {
+    pub version: u32,
+    pub created_at: DateTime<Utc>,
+    pub keys: Vec<BackedUpKey>,
+    pub rotations: Vec<RotationCertificate>,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct BackedUpKey {
+    pub keystore: EncryptedKeystore,
+    pub created_at: DateTime<Utc>,
+    pub is_active: bool,
+    pub retired_at: Option<DateTime<Utc>>,
+    pub retired_reason: Option<String>,
+    pub compromised: bool,
+    #[serde(default)]
+    pub derivation_path: Option<String>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct KeyRestore {
+    pub restored: Vec<KeypairInfo>,
+    /// Keys in the backup this agent already holds
+    pub skipped: usize,
+}
+
+fn backup_keys_with(
+    state: &AppState,
+    db: &Database,
+    password: &str,
+    params: KeystoreParams,
+) -> anyhow::Result<KeyBackup> {
+    vault::check_passphrase(password)?;
+    let key = state.vault.key()?;
+    let mut keys = Vec::new();
+    for stored in db.list_keypairs()? {
+        let keypair = key.decrypt_keypair(&stored.secret_key_encrypted)?;
+        keys.push(BackedUpKey {
+            keystore: EncryptedKeystore::seal(&keypair, password, params)?,
+            created_at: stored.created_at,
+            is_active: stored.is_active,
+            retired_at: stored.retired_at,
+            retired_reason: stored.retired_reason,
+            compromised: stored.compromised,
+            derivation_path: stored.derivation_path,
+        });
+    }
+    if keys.is_empty() {
+        anyhow::bail!("There are no keys to back up");
+    }
+    Ok(KeyBackup {
+        version: KEY_BACKUP_VERSION,
+        created_at: Utc::now(),
+        keys,
+        rotations: db.list_key_rotations()?,
+    })
+}
+
+/// Restores keys this agent does not hold yet. The backup's active key only
+/// becomes active here if no other key is; rotations are restored once both
+/// of their keys are present.
+fn restore_keys_with(state: &AppState, db: &Database, backup: &KeyBackup, password: &str) -> anyhow::Result<KeyRestore> {
+    if backup.version != KEY_BACKUP_VERSION {
+        anyhow::bail!("Unsupported key backup version {}", backup.version);
+    }
+    for certificate in &backup.rotations {
+        if !certificate.verify()? {
+            anyhow::bail!("Backup contains an invalid rotation certificate");
+        }
+    }
+    let key = state.vault.key()?;
+    // Open every keystore before writing anything, so a wrong password
+    // restores nothing
+    let keypairs = backup
+        .keys
+        .iter()
+        .map(|backed_up| backed_up.keystore.open(password))
+        .collect::<Result<Vec<_>, _>>()?;
+
+    let held: Vec<String> = db.list_keypairs()?.into_iter().map(|kp| kp.public_key).collect();
+    let mut has_active = db.get_active_keypair()?.is_some();
+    let mut restored = Vec::new();
+    for (backed_up, keypair) in backup.keys.iter().zip(&keypairs) {
+        let public_key = keypair.public_key().to_hex();
+        if held.contains(&public_key) {
+            continue;
+        }
+        let is_active = backed_up.is_active && !backed_up.compromised && !has_active;
+        has_active |= is_active;
+        restored.push(StoredKeypair {
+            id: Uuid::new_v4().to_string(),
+            public_key,
+            secret_key_encrypted: key.encrypt_keypair(keypair)?,
+            created_at: backed_up.created_at,
+            is_active,
+            retired_at: backed_up.retired_at,
+            retired_reason: backed_up.retired_reason.clone(),
+            compromised: backed_up.compromised,
+            derivation_path: backed_up.derivation_path.clone(),
+        });
+    }
+    audited(state, db, || {
+        db.restore_keypairs(&restored, &backup.rotations)?;
+        let records = restored
+            .iter()
+            .map(|stored| AuditRecord::new("restore_keys", "keypair", &stored.id).after(&stored.public_key))
+            .collect::<anyhow::Result<Vec<_>>>()?;
+        Ok(((), records))
+    })?;
+    Ok(KeyRestore {
+        skipped: backup.keys.len() - restored.len(),
+        restored: restored.into_iter().map(KeypairInfo::from).collect(),
+    })
+}
+
+/// Exports every signing key as a password-protected backup (JSON)
+#[tauri::command]
+pub async fn backup_keys(
+    password: String,
+    state: State<'_, AppState>,
+) -> Result<CommandResponse<String>, String> {
+    let db = state.db.read().map_err(|e| e.to_string())?;
+    let backup = match backup_keys_with(&state, &db, &password, KeystoreParams::default()) {
+        Ok(backup) => backup,
+        Err(e) => return Ok(CommandResponse::err(&format!("{:#}", e))),
+    };
+    match serde_json::to_string_pretty(&backup) {
+        Ok(json) => Ok(CommandResponse::ok(json)),
+        Err(e) => Ok(CommandResponse::err(&e.to_string())),
+    }
+}
+
+/// Restores signing keys from a backup made with `backup_keys`
+#[tauri::command]
+pub async fn restore_keys(
+    backup: String,
+    password: String,
+    state: State<'_, AppState>,
+) -> Result<CommandResponse<KeyRestore>, String> {
+    let backup: KeyBackup = match serde_json::from_str(&backup) {
+        Ok(backup) => backup,
+        Err(e) => return Ok(CommandResponse::err(&format!("Invalid key backup: {}", e))),
+    };
+    let db = state.db.write().map_err(|e| e.to_string())?;
+    match restore_keys_with(&state, &db, &backup, &password) {
+        Ok(restore) => Ok(CommandResponse::ok(restore)),
+        Err(e) => Ok(CommandResponse::err(&format!("{:#}", e))),
+    }
+}
+
+// ============================================================================
+// Recovery phrase commands
+// ============================================================================
+
+/// Account (first path index) of signing keys derived from the recovery
+/// phrase; keys are `m/0'/0'`, `m/0'/1'`, ... in the order they were created
+const RECOVERY_ACCOUNT: u32 = 0;
+
+const SEED_FINGERPRINT_DOMAIN: &[u8] = b"zkdpp-edge-agent recovery seed fingerprint v1";
+
+/// Shown once when the phrase is created; not `Debug`, so the phrase
+/// stays out of logs
+#[derive(Serialize)]
+pub struct RecoveryPhrase {
+    pub phrase: SecretString,
+    pub fingerprint: String,
+}
+
+#[derive(Debug, Serialize)]
+pub struct KeyRecovery {
+    /// Existing derived keys whose sealed secret was rebuilt
+    pub rebuilt: usize,
+    /// Keys added from the requested paths
+    pub recovered: Vec<KeypairInfo>,
+}
+
+fn seed_fingerprint(seed: &[u8]) -> String {
+    let mut data = SEED_FINGERPRINT_DOMAIN.to_vec();
+    data.extend_from_slice(seed);
+    let fingerprint = commitments::to_hex(&commitments::hash_bytes(&data))[..16].to_string();
+    zeroize::Zeroize::zeroize(&mut data);
+    fingerprint
+}
+
+fn create_recovery_phrase_with(state: &AppState, db: &Database) -> anyhow::Result<RecoveryPhrase> {
+    let key = state.vault.key()?;
+    if db.get_recovery_seed()?.is_some() {
+        anyhow::bail!("A recovery phrase already exists");
+    }
+    let mnemonic = Mnemonic::generate();
+    let seed = mnemonic.to_seed("");
+    let record = RecoverySeedRecord {
+        sealed_seed: key.encrypt_secret(seed.expose_secret())?,
+        fingerprint: seed_fingerprint(seed.expose_secret()),
+        created_at: Utc::now(),
+    };
+    audited(state, db, || {
+        db.insert_recovery_seed(&record)?;
+        Ok(((), Some(AuditRecord::new("create_recovery_phrase", "recovery_seed", &record.fingerprint))))
+    })?;
+    Ok(RecoveryPhrase {
+        phrase: mnemonic.phrase(),
+        fingerprint: record.fingerprint,
+    })
+}
+
+/// Rebuilds keys from the recovery phrase: every stored key with a
+/// derivation path is re-derived and resealed, and keys at `paths` this
+/// agent does not hold are added. The last added key becomes active if no
+/// key is; the others are added as retired. Rotation certificates cannot be
+/// derived and come from a key backup or the published chain.
+fn recover_keys_with(state: &AppState, db: &Database, phrase: &str, paths: &[String]) -> anyhow::Result<KeyRecovery> {
+    let key = state.vault.key()?;
+    let seed = Mnemonic::from_phrase(phrase)?.to_seed("");
+    let fingerprint = seed_fingerprint(seed.expose_secret());
+    let stored_seed = db.get_recovery_seed()?;
+    if let Some(record) = &stored_seed {
+        if record.fingerprint != fingerprint {
+            anyhow::bail!("This recovery phrase does not belong to this Edge Agent");
+        }
+    }
+
+    let held = db.list_keypairs()?;
+    let mut rebuilt = Vec::new();
+    for stored in &held {
+        let path = match &stored.derivation_path {
+            Some(path) => path.parse::<DerivationPath>()?,
+            None => continue,
+        };
+        let kp = KeyPair::derive(seed.expose_secret(), &path)?;
+        if kp.public_key().to_hex() != stored.public_key {
+            anyhow::bail!("Key {} at {} does not match the recovery phrase", stored.id, path);
+        }
+        rebuilt.push((stored.id.clone(), key.encrypt_keypair(&kp)?));
+    }
+
+    let mut recovered = Vec::new();
+    for path in paths {
+        let path = path.parse::<DerivationPath>()?;
+        let kp = KeyPair::derive(seed.expose_secret(), &path)?;
+        let public_key = kp.public_key().to_hex();
+        if held.iter().any(|stored| stored.public_key == public_key)
+            || recovered.iter().any(|stored: &StoredKeypair| stored.public_key == public_key)
+        {
+            continue;
+        }
+        let mut stored = seal_keypair(&key, &kp, Some(path.to_string()))?;
+        stored.is_active = false;
+        stored.retired_at = Some(stored.created_at);
+        stored.retired_reason = Some("recovered".to_string());
+        recovered.push(stored);
+    }
+    // Skipped paths do not count: the last key actually added takes over
+    if !held.iter().any(|kp| kp.is_active) {
+        if let Some(last) = recovered.last_mut() {
+            last.is_active = true;
+            last.retired_at = None;
+            last.retired_reason = None;
+        }
+    }
+
+    audited(state, db, || {
+        for (id, sealed) in &rebuilt {
+            db.update_keypair_secret(id, sealed)?;
+        }
+        if stored_seed.is_none() {
+            db.insert_recovery_seed(&RecoverySeedRecord {
+                sealed_seed: key.encrypt_secret(seed.expose_secret())?,
+                fingerprint: fingerprint.clone(),
+                created_at: Utc::now(),
+            })?;
+        }
+        db.restore_keypairs(&recovered, &[])?;
+        let records = recovered
+            .iter()
+            .map(|stored| AuditRecord::new("recover_keys", "keypair", &stored.id).after(&stored.public_key))
+            .collect::<anyhow::Result<Vec<_>>>()?;
+        Ok(((), records))
+    })?;
+
+    Ok(KeyRecovery {
+        rebuilt: rebuilt.len(),
+        recovered: recovered.into_iter().map(KeypairInfo::from).collect(),
+    })
+}
+
+/// Creates the recovery phrase new signing keys are derived from. The phrase
+/// is returned once and not stored; only the seed is kept, sealed.
+#[tauri::command]
+pub async fn create_recovery_phrase(
+    state: State<'_, AppState>,
+) -> Result<CommandResponse<RecoveryPhrase>, String> {
+    let db = state.db.write().map_err(|e| e.to_string())?;
+    match create_recovery_phrase_with(&state, &db) {
+        Ok(phrase) => Ok(CommandResponse::ok(phrase)),
+        Err(e) => Ok(CommandResponse::err(&format!("{:#}", e))),
+    }
+}
+
+#[tauri::command]
+pub async fn recover_keys(
+    phrase: String,
+    paths: Vec<String>,
+    state: State<'_, AppState>,
+) -> Result<CommandResponse<KeyRecovery>, String> {
+    let phrase = SecretString::new(phrase);
+    let db = state.db.write().map_err(|e| e.to_string())?;
+    match recover_keys_with(&state, &db, phrase.expose_secret(), &paths) {
+        Ok(recovery) => Ok(CommandResponse::ok(recovery)),
+        Err(e) => Ok(CommandResponse::err(&format!("{:#}", e))),
+    }
+}
+
+// ============================================================================
+// Vault commands
+// ============================================================================
+
+#[tauri::command]
+pub async fn vault_status(
+    state: State<'_, AppState>,
+) -> Result<CommandResponse<VaultStatus>, String> {
+    let db = state.db.read().map_err(|e| e.to_string())?;
+    match state.vault.status(&db) {
+        Ok(status) => Ok(CommandResponse::ok(status)),
+        Err(e) => Ok(CommandResponse::err(&e.to_string())),
+    }
+}
+
+#[tauri::command]
+pub async fn setup_vault(
+    passphrase: String,
+    state: State<'_, AppState>,
+) -> Result<CommandResponse<bool>, String> {
+    let db = state.db.write().map_err(|e| e.to_string())?;
+    let setup = audited(&state, &db, || {
+        state.vault.initialize(&db, &passphrase, KdfParams::default())?;
+        Ok(((), Some(AuditRecord::new("setup_vault", "vault", "vault"))))
+    });
+    match setup {
+        Ok(()) => Ok(CommandResponse::ok(true)),
+        Err(e) => {
+            // The vault record was rolled back; don't keep its key unlocked
+            state.vault.lock().map_err(|e| e.to_string())?;
+            Ok(CommandResponse::err(&format!("{:#}", e)))
+        }
+    }
+}
+
+#[tauri::command]
+pub async fn unlock_vault(
+    passphrase: String,
+    state: State<'_, AppState>,
+) -> Result<CommandResponse<bool>, String> {
+    let db = state.db.write().map_err(|e| e.to_string())?;
+    match state.vault.unlock(&db, &passphrase) {
+        Ok(()) => Ok(CommandResponse::ok(true)),
+        Err(e) => Ok(CommandResponse::err(&format!("{:#}", e))),
+    }
+}
+
+#[tauri::command]
+pub async fn lock_vault(
+    state: State<'_, AppState>,
+) -> Result<CommandResponse<bool>, String> {
+    match state.vault.lock() {
+        Ok(()) => Ok(CommandResponse::ok(true)),
+        Err(e) => Ok(CommandResponse::err(&e.to_string())),
+    }
+}
+
+#[tauri::command]
+pub async fn change_vault_passphrase(
+    current: String,
+    new: String,
+    state: State<'_, AppState>,
+) -> Result<CommandResponse<bool>, String> {
+    let db = state.db.write().map_err(|e| e.to_string())?;
+    let change = audited(&state, &db, || {
+        state.vault.change_passphrase(&db, &current, &new)?;
+        Ok(((), Some(AuditRecord::new("change_vault_passphrase", "vault", "vault"))))
+    });
+    match change {
+        Ok(()) => Ok(CommandResponse::ok(true)),
+        Err(e) => Ok(CommandResponse::err(&e.to_string())),
+    }
+}
+
+// ============================================================================
+// Audit log commands
+// ============================================================================
+
+/// A chain head is signed once this many entries follow the last signed one
+const AUDIT_SIGN_EVERY_ENTRIES: u64 = 100;
+/// ...or once the last signature is this old
+const AUDIT_SIGN_EVERY_HOURS: i64 = 1;
+
+/// Runs `mutation` and appends the audit records it returns in the same
+/// transaction, so a change is never saved without its entry. The head is
+/// signed afterwards when due.
+fn audited<T, R>(state: &AppState, db: &Database, mutation: impl FnOnce() -> anyhow::Result<(T, R)>) -> anyhow::Result<T>
+where
+    R: IntoIterator<Item = AuditRecord>,
+{
+    let value = db.atomic(|| {
+        let (value, records) = mutation()?;
+        for record in records {
+            db.append_audit(&record)?;
+        }
+        Ok(value)
+    })?;
+    sign_audit_head_if_due(state, db);
+    Ok(value)
+}
+
+/// Signs the head once enough entries or time have passed since the last
+/// signature. A locked vault, a missing keypair or an unreachable signer
+/// only postpones the signature; the next mutation tries again.
+fn sign_audit_head_if_due(state: &AppState, db: &Database) {
+    let seq = match db.audit_head() {
+        Ok(Some((seq, _))) => seq,
+        _ => return,
+    };
+    let due = match db.latest_signed_audit_head() {
+        Ok(Some(last)) => {
+            seq >= last.seq + AUDIT_SIGN_EVERY_ENTRIES
+                || Utc::now() - last.signed_at >= chrono::Duration::hours(AUDIT_SIGN_EVERY_HOURS)
+        }
+        Ok(None) => true,
+        Err(_) => return,
+    };
+    if due {
+        let _ = sign_audit_head_with(state, db);
+    }
+}
+
+/// Signs the current chain head with the active signer
+fn sign_audit_head_with(state: &AppState, db: &Database) -> anyhow::Result<Option<SignedAuditHead>> {
+    let (seq, entry_hash) = match db.audit_head()? {
+        Some(head) => head,
+        None => return Ok(None),
+    };
+    if let Some(last) = db.latest_signed_audit_head()? {
+        if last.seq == seq {
+            return Ok(Some(last));
+        }
+    }
+    let signer = match active_signer(state, db)? {
+        Some(signer) => signer,
+        None => return Ok(None),
+    };
+
+    let head = SignedAuditHead {
+        seq,
+        signature: signer.sign_hex_in(Domain::AUDIT_HEAD, &audit::head_payload(seq, &entry_hash))?,
+        entry_hash,
+        public_key: signer.public_key().to_hex(),
+        signed_at: Utc::now(),
+    };
+    db.insert_signed_audit_head(&head)?;
+    Ok(Some(head))
+}
+
+#[tauri::command]
+pub async fn sign_audit_head(
+    state: State<'_, AppState>,
+) -> Result<CommandResponse<Option<SignedAuditHead>>, String> {
+    let db = state.db.write().map_err(|e| e.to_string())?;
+    match sign_audit_head_with(&state, &db) {
+        Ok(head) => Ok(CommandResponse::ok(head)),
+        Err(e) => Ok(CommandResponse::err(&e.to_string())),
+    }
+}
+
+#[tauri::command]
+pub async fn verify_audit_log(
+    state: State<'_, AppState>,
+) -> Result<CommandResponse<AuditVerification>, String> {
+    let db = state.db.read().map_err(|e| e.to_string())?;
+    let entries = db.list_audit_entries().map_err(|e| e.to_string())?;
+    let heads = db.list_signed_audit_heads().map_err(|e| e.to_string())?;
+    let trusted = audit_trusted_keys(&state, &db).map_err(|e| e.to_string())?;
+    Ok(CommandResponse::ok(audit::verify_chain(&entries, &heads, &trusted)))
+}
+
+/// Keys whose signature on a chain head counts: every key this agent holds,
+/// retired ones included since they signed earlier heads, and the external
+/// signer's if one is configured
+fn audit_trusted_keys(state: &AppState, db: &Database) -> anyhow::Result<Vec<PublicKey>> {
+    let mut trusted = db
+        .list_keypairs()?
+        .iter()
+        .map(|stored| PublicKey::from_hex(&stored.public_key))
+        .collect::<crypto::Result<Vec<_>>>()?;
+    if let Some(signer) = &state.signer {
+        trusted.push(signer.public_key());
+    }
+    Ok(trusted)
+}
+
+/// Everything an auditor needs to re-check the log independently
+#[derive(Debug, Serialize)]
+pub struct AuditExport {
+    pub exported_at: chrono::DateTime<Utc>,
+    pub entries: Vec<AuditEntry>,
+    pub signed_heads: Vec<SignedAuditHead>,
+    /// Hex public keys the signed heads were checked against
+    pub trusted_keys: Vec<String>,
+    pub verification: AuditVerification,
+}
 
-    Ok(CommandResponse::ok(KeypairInfo {
-        id: stored.id,
-        public_key: stored.public_key,
-        created_at: now.to_rfc3339(),
+#[tauri::command]
+pub async fn export_audit_log(
+    state: State<'_, AppState>,
+) -> Result<CommandResponse<AuditExport>, String> {
+    let db = state.db.read().map_err(|e| e.to_string())?;
+    let entries = db.list_audit_entries().map_err(|e| e.to_string())?;
+    let signed_heads = db.list_signed_audit_heads().map_err(|e| e.to_string())?;
+    let trusted = audit_trusted_keys(&state, &db).map_err(|e| e.to_string())?;
+    let verification = audit::verify_chain(&entries, &signed_heads, &trusted);
+    Ok(CommandResponse::ok(AuditExport {
+        exported_at: Utc::now(),
+        entries,
+        signed_heads,
+        trusted_keys: trusted.iter().map(PublicKey::to_hex).collect(),
+        verification,
     }))
 }
 
+// ============================================================================
+// Identity commands
+// ============================================================================
+
+const CREDENTIALS_CONTEXT: &str = "https://www.w3.org/ns/credentials/v2";
+
+/// The supplier's DID and the verification method its key signs as: the
+/// configured `did:web` if there is one, else the key's own `did:key`
+fn issuer_identity(db: &Database, public_key: &PublicKey) -> anyhow::Result<(String, String)> {
+    match db.get_setting("supplier_did")? {
+        Some(did) => Ok((did.clone(), format!("{}#{}", did, public_key.to_multibase()))),
+        None => Ok((public_key.to_did_key(), public_key.did_key_verification_method())),
+    }
+}
+
+/// The DID document to publish at the `did:web` URL, listing the active key
+#[tauri::command]
+pub async fn export_did_document(
+    state: State<'_, AppState>,
+) -> Result<CommandResponse<DidDocument>, String> {
+    let db = state.db.read().map_err(|e| e.to_string())?;
+    export_did_document_with(&state, &db)
+}
+
+fn export_did_document_with(state: &AppState, db: &Database) -> Result<CommandResponse<DidDocument>, String> {
+    let did = match db.get_setting("supplier_did") {
+        Ok(Some(did)) => did,
+        Ok(None) => return Ok(CommandResponse::err("No supplier DID configured")),
+        Err(e) => return Ok(CommandResponse::err(&e.to_string())),
+    };
+    match active_signer(state, db).map_err(|e| format!("{:#}", e))? {
+        Some(signer) => Ok(CommandResponse::ok(DidDocument::for_key(&did, &signer.public_key()))),
+        None => Ok(CommandResponse::err("No active keypair")),
+    }
+}
+
+/// A commitment as a W3C verifiable credential secured with an
+/// `eddsa-jcs-2022` proof
+#[tauri::command]
+pub async fn issue_commitment_credential(
+    id: String,
+    state: State<'_, AppState>,
+) -> Result<CommandResponse<serde_json::Value>, String> {
+    let db = state.db.read().map_err(|e| e.to_string())?;
+    issue_commitment_credential_with(&state, &db, &id)
+}
+
+fn issue_commitment_credential_with(
+    state: &AppState,
+    db: &Database,
+    id: &str,
+) -> Result<CommandResponse<serde_json::Value>, String> {
+    let commitment = match db.get_commitment(id) {
+        Ok(Some(commitment)) => commitment,
+        Ok(None) => return Ok(CommandResponse::err(&format!("Commitment {} not found", id))),
+        Err(e) => return Ok(CommandResponse::err(&e.to_string())),
+    };
+    if commitment.revoked {
+        return Ok(CommandResponse::err("Commitment has been revoked"));
+    }
+    let signer = match active_signer(state, db).map_err(|e| format!("{:#}", e))? {
+        Some(signer) => signer,
+        None => return Ok(CommandResponse::err("No active keypair")),
+    };
+    let (issuer, verification_method) =
+        issuer_identity(db, &signer.public_key()).map_err(|e| e.to_string())?;
+
+    let mut subject = serde_json::json!({
+        "id": issuer,
+        "commitmentRoot": commitment.root,
+        "claimCount": commitment.claim_count,
+    });
+    if let Some(supplier_id) = db.get_setting("supplier_id").ok().flatten() {
+        subject["supplierId"] = serde_json::json!(supplier_id);
+    }
+    let mut credential = serde_json::json!({
+        "@context": [CREDENTIALS_CONTEXT],
+        "id": format!("urn:uuid:{}", commitment.id),
+        "type": ["VerifiableCredential", "SupplierCommitmentCredential"],
+        "issuer": issuer,
+        "validFrom": commitment.valid_from.unwrap_or(commitment.created_at).to_rfc3339(),
+        "credentialSubject": subject,
+    });
+    if let Some(valid_until) = commitment.valid_until {
+        credential["validUntil"] = serde_json::json!(valid_until.to_rfc3339());
+    }
+
+    let created = Utc::now().to_rfc3339_opts(chrono::SecondsFormat::Secs, true);
+    match data_integrity::add_proof(
+        &credential,
+        signer.as_ref(),
+        &verification_method,
+        data_integrity::ASSERTION_METHOD,
+        &created,
+    ) {
+        Ok(secured) => Ok(CommandResponse::ok(secured)),
+        Err(e) => Ok(CommandResponse::err(&format!("Signing failed: {}", e))),
+    }
+}
+
 // ============================================================================
 // Settings commands
 // ============================================================================
@@ -1053,21 +2592,30 @@ pub async fn generate_new_keypair(
 pub struct AppSettings {
     pub supplier_id: Option<String>,
     pub supplier_name: Option<String>,
+    /// `did:web` the supplier publishes its DID document under
+    #[serde(default)]
+    pub supplier_did: Option<String>,
     pub ollama_url: Option<String>,
     pub ollama_model: Option<String>,
+    pub vault_auto_lock_secs: Option<u64>,
+    pub evidence_retention_days: Option<BTreeMap<String, u32>>,
 }
 
 #[tauri::command]
 pub async fn get_settings(
     state: State<'_, AppState>,
 ) -> Result<CommandResponse<AppSettings>, String> {
-    let db = state.db.lock().map_err(|e| e.to_string())?;
+    let db = state.db.read().map_err(|e| e.to_string())?;
 
     let settings = AppSettings {
         supplier_id: db.get_setting("supplier_id").ok().flatten(),
         supplier_name: db.get_setting("supplier_name").ok().flatten(),
+        supplier_did: db.get_setting("supplier_did").ok().flatten(),
         ollama_url: db.get_setting("ollama_url").ok().flatten(),
         ollama_model: db.get_setting("ollama_model").ok().flatten(),
+        vault_auto_lock_secs: db.get_setting("vault_auto_lock_secs").ok().flatten()
+            .and_then(|v| v.parse().ok()),
+        evidence_retention_days: retention_policies(&db).ok(),
     };
 
     Ok(CommandResponse::ok(settings))
@@ -1078,20 +2626,746 @@ pub async fn update_settings(
     settings: AppSettings,
     state: State<'_, AppState>,
 ) -> Result<CommandResponse<bool>, String> {
-    let db = state.db.lock().map_err(|e| e.to_string())?;
+    let db = state.db.write().map_err(|e| e.to_string())?;
 
-    if let Some(v) = settings.supplier_id {
-        db.set_setting("supplier_id", &v).map_err(|e| e.to_string())?;
+    if let Some(Err(e)) = settings.supplier_did.as_deref().map(crypto::did::did_web_url) {
+        return Ok(CommandResponse::err(&e.to_string()));
     }
-    if let Some(v) = settings.supplier_name {
-        db.set_setting("supplier_name", &v).map_err(|e| e.to_string())?;
+    audited(&state, &db, || {
+        if let Some(v) = &settings.supplier_id {
+            db.set_setting("supplier_id", v)?;
+        }
+        if let Some(v) = &settings.supplier_name {
+            db.set_setting("supplier_name", v)?;
+        }
+        if let Some(v) = &settings.supplier_did {
+            db.set_setting("supplier_did", v)?;
+        }
+        if let Some(v) = &settings.ollama_url {
+            db.set_setting("ollama_url", v)?;
+        }
+        if let Some(v) = &settings.ollama_model {
+            db.set_setting("ollama_model", v)?;
+        }
+        if let Some(v) = settings.vault_auto_lock_secs {
+            db.set_setting("vault_auto_lock_secs", &v.to_string())?;
+        }
+        if let Some(v) = &settings.evidence_retention_days {
+            db.set_setting("evidence_retention_days", &serde_json::to_string(v)?)?;
+        }
+        Ok(((), Some(AuditRecord::new("update_settings", "settings", "settings").after(&settings)?)))
+    })
+    .map_err(|e| e.to_string())?;
+    if let Some(v) = settings.vault_auto_lock_secs {
+        state.vault.set_auto_lock(std::time::Duration::from_secs(v)).map_err(|e| e.to_string())?;
     }
-    if let Some(v) = settings.ollama_url {
-        db.set_setting("ollama_url", &v).map_err(|e| e.to_string())?;
+
+    Ok(CommandResponse::ok(true))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::storage::vault::TEST_KDF_PARAMS;
+    use crate::storage::{DbPool, Vault};
+
+    pub(super) fn test_state() -> AppState {
+        let vault = std::sync::Arc::new(Vault::new(std::time::Duration::from_secs(600)));
+        let state = AppState {
+            db: std::sync::Arc::new(DbPool::in_memory(vault.clone()).unwrap()),
+            vault,
+            ollama_base: "http://localhost:11434".to_string(),
+            signer: None,
+        };
+        state
+            .vault
+            .initialize(&state.db.write().unwrap(), "correct horse battery staple", TEST_KDF_PARAMS)
+            .unwrap();
+        state
     }
-    if let Some(v) = settings.ollama_model {
-        db.set_setting("ollama_model", &v).map_err(|e| e.to_string())?;
+
+    pub(super) fn insert_claim(state: &AppState, claim_type: &str, value: serde_json::Value, unit: &str) -> String {
+        let now = Utc::now();
+        let claim = Claim {
+            id: Uuid::new_v4().to_string(),
+            claim_type: claim_type.to_string(),
+            value,
+            unit: unit.to_string(),
+            product_id: "battery-001".to_string(),
+            evidence_ids: vec![],
+            confidence: Some(0.9),
+            verified: true,
+            metadata: serde_json::json!({}),
+            created_at: now,
+            updated_at: now,
+        };
+        state.db.write().unwrap().insert_claim(&claim).unwrap();
+        claim.id
     }
 
-    Ok(CommandResponse::ok(true))
+    pub(super) fn commit(state: &AppState, claim_ids: Vec<String>) -> Commitment {
+        let response = create_commitment_with(state, CreateCommitmentInput { claim_ids, valid_days: Some(30) }).unwrap();
+        assert!(response.success, "{:?}", response.error);
+        response.data.unwrap()
+    }
+
+    pub(super) fn proof_input(commitment_id: &str, predicate_id: &str, threshold: Option<u32>) -> GenerateProofInput {
+        GenerateProofInput {
+            commitment_id: commitment_id.to_string(),
+            predicate_id: predicate_id.to_string(),
+            claim_id: None,
+            claim_type: None,
+            claim_index: None,
+            threshold,
+            min_threshold: None,
+            max_threshold: None,
+            stage_claims: None,
+            timestamp: None,
+            extra: None,
+            product_id: "battery-001".to_string(),
+            requester_id: "brand-042".to_string(),
+        }
+    }
+
+    /// For requests that must be refused before anything is proven
+    fn unreachable_backend() -> anyhow::Result<Box<dyn ProverBackend>> {
+        struct Unreachable;
+        impl ProverBackend for Unreachable {
+            fn name(&self) -> &'static str {
+                "unreachable"
+            }
+            fn prove(&self, _inputs: CircuitInputs) -> anyhow::Result<String> {
+                panic!("the prover should not be reached")
+            }
+        }
+        Ok(Box::new(Unreachable))
+    }
+
+    /// A state signing through a signing daemon holding `held`; returns the
+    /// daemon's socket for cleanup
+    #[cfg(unix)]
+    pub(super) fn remote_signer_state(held: KeyPair) -> (AppState, std::path::PathBuf) {
+        use crypto::signer::remote::{serve, RemoteSigner};
+        use std::os::unix::net::UnixListener;
+
+        let socket = std::env::temp_dir().join(format!("zkdpp-edge-signer-{}.sock", Uuid::new_v4()));
+        let listener = UnixListener::bind(&socket).unwrap();
+        std::thread::spawn(move || serve(listener, std::collections::HashMap::from([("supplier".to_string(), held)])));
+
+        let mut state = test_state();
+        state.signer = Some(Arc::new(RemoteSigner::connect(&socket, "supplier").unwrap()));
+        (state, socket)
+    }
+
+    #[test]
+    fn test_commitment_root_independent_of_claim_order() {
+        let state = test_state();
+        let a = insert_claim(&state, "recycled_content", serde_json::json!(25), "percent");
+        let b = insert_claim(&state, "carbon_footprint", serde_json::json!(12.5), "kg_co2e");
+        let c = insert_claim(&state, "energy_density", serde_json::json!(265), "Wh/kg");
+
+        let first = commit(&state, vec![a.clone(), b.clone(), c.clone()]);
+        let second = commit(&state, vec![c.clone(), a.clone(), b.clone()]);
+        assert_eq!(first.root, second.root);
+        assert_eq!(first.claim_ids, second.claim_ids);
+
+        let duplicate = create_commitment_with(&state, CreateCommitmentInput { claim_ids: vec![a.clone(), a], valid_days: None }).unwrap();
+        assert!(!duplicate.success);
+    }
+
+    #[test]
+    fn test_tampered_snapshot_names_claim() {
+        let mut claim = Claim {
+            id: "claim-1".to_string(),
+            claim_type: "recycled_content".to_string(),
+            value: serde_json::json!(25),
+            unit: "percent".to_string(),
+            product_id: "battery-001".to_string(),
+            evidence_ids: vec![],
+            confidence: None,
+            verified: true,
+            metadata: serde_json::json!({}),
+            created_at: Utc::now(),
+            updated_at: Utc::now(),
+        };
+        let leaf_hash = commitments::to_hex(&compute_claim_hash(&claim).unwrap());
+        claim.value = serde_json::json!(35);
+        let leaf = CommitmentLeaf {
+            commitment_id: "commitment-1".to_string(),
+            position: 0,
+            claim_id: claim.id.clone(),
+            claim_version: 1,
+            leaf_hash,
+            canonical_claim: commitments::canonicalize(&claim).unwrap(),
+        };
+
+        match load_frozen_leaves(&[leaf]) {
+            Err(PredicateFailure::LeafMismatch { claim_id, position, .. }) => {
+                assert_eq!(claim_id, "claim-1");
+                assert_eq!(position, 0);
+            }
+            other => panic!("expected leaf mismatch, got {:?}", other.map(|(c, _)| c.len())),
+        }
+    }
+
+    #[test]
+    fn test_claim_history_locks_committed_versions() {
+        let state = test_state();
+        let recycled = insert_claim(&state, "recycled_content", serde_json::json!(25), "percent");
+        let commitment = commit(&state, vec![recycled.clone()]);
+
+        {
+            let db = state.db.write().unwrap();
+            let mut edited = db.get_claim(&recycled).unwrap().unwrap();
+            edited.value = serde_json::json!(30);
+            assert!(db.update_claim(&edited).unwrap());
+            assert!(db.retire_claim(&recycled).unwrap());
+            assert!(db.update_claim(&edited).is_err());
+            assert!(db.list_claims(None).unwrap().is_empty());
+        }
+
+        let history = claim_history_with(&state.db.read().unwrap(), &recycled).unwrap().data.unwrap();
+        let changes: Vec<ClaimChange> = history.iter().map(|entry| entry.change).collect();
+        assert_eq!(changes, vec![ClaimChange::Created, ClaimChange::Updated, ClaimChange::Retired]);
+        assert_eq!(history[0].committed_in, vec![commitment.id.clone()]);
+        assert!(history[1].committed_in.is_empty());
+        assert_eq!(history[1].diff, vec![FieldChange {
+            field: "value".to_string(),
+            before: Some(serde_json::json!(25)),
+            after: Some(serde_json::json!(30)),
+        }]);
+
+        let retired = create_commitment_with(&state, CreateCommitmentInput { claim_ids: vec![recycled.clone()], valid_days: None }).unwrap();
+        assert_eq!(retired.error, Some(format!("Claim {} is retired", recycled)));
+    }
+
+    #[test]
+    fn test_unsatisfied_predicate_reports_reason() {
+        let state = test_state();
+        let recycled = insert_claim(&state, "recycled_content", serde_json::json!(18), "percent");
+        let commitment = commit(&state, vec![recycled]);
+
+        let response = generate_proof_with(&state, proof_input(&commitment.id, "RECYCLED_CONTENT_GTE_V1", Some(20)), unreachable_backend).unwrap();
+        assert!(!response.success);
+        let details = response.details.unwrap();
+        assert_eq!(details["reason"], "below_threshold");
+        assert_eq!(details["margin"], 2);
+    }
+
+    #[test]
+    fn test_legacy_commitment_names_diverged_claim() {
+        let state = test_state();
+        let recycled = insert_claim(&state, "recycled_content", serde_json::json!(25), "percent");
+        let carbon = insert_claim(&state, "carbon_footprint", serde_json::json!(12.5), "kg_co2e");
+        let frozen = commit(&state, vec![recycled.clone(), carbon.clone()]);
+
+        // Commitments from before leaves were frozen have no snapshot
+        let legacy = Commitment { id: Uuid::new_v4().to_string(), ..frozen };
+        {
+            let db = state.db.write().unwrap();
+            db.insert_commitment(&legacy).unwrap();
+            let mut edited = db.get_claim(&carbon).unwrap().unwrap();
+            edited.value = serde_json::json!(14.0);
+            assert!(db.update_claim(&edited).unwrap());
+        }
+
+        let response = generate_proof_with(&state, proof_input(&legacy.id, "RECYCLED_CONTENT_GTE_V1", Some(20)), unreachable_backend).unwrap();
+        assert!(!response.success);
+        let details = response.details.unwrap();
+        assert_eq!(details["reason"], "leaf_mismatch");
+        assert_eq!(details["claim_id"], carbon.as_str());
+        let position = legacy.claim_ids.iter().position(|id| *id == carbon).unwrap();
+        assert_eq!(details["position"], position);
+        assert!(details["expected"].as_str().unwrap().starts_with("version 1 "));
+    }
+
+    #[test]
+    fn test_oversized_commitment_fails_preflight() {
+        let state = test_state();
+        let claims = (0..preflight::MAX_COMMITTED_CLAIMS + 1)
+            .map(|i| insert_claim(&state, "recycled_content", serde_json::json!(20 + i % 10), "percent"))
+            .collect();
+        let commitment = commit(&state, claims);
+
+        let response = generate_proof_with(&state, proof_input(&commitment.id, "RECYCLED_CONTENT_GTE_V1", Some(20)), unreachable_backend).unwrap();
+        assert!(!response.success);
+        let details = response.details.unwrap();
+        assert_eq!(details["reason"], "too_many_entries");
+        assert_eq!(details["max"], 256);
+    }
+
+    #[test]
+    fn test_revoked_commitment_refuses_proof() {
+        let state = test_state();
+        let recycled = insert_claim(&state, "recycled_content", serde_json::json!(25), "percent");
+        let commitment = commit(&state, vec![recycled]);
+        state.db.write().unwrap().revoke_commitment(&commitment.id, "superseded", Utc::now(), None).unwrap();
+
+        let response = generate_proof_with(&state, proof_input(&commitment.id, "RECYCLED_CONTENT_GTE_V1", Some(20)), unreachable_backend).unwrap();
+        assert!(!response.success);
+        assert_eq!(response.error.as_deref(), Some("Commitment has been revoked"));
+    }
+
+    #[test]
+    fn test_retention_shreds_expired_evidence() {
+        let state = test_state();
+        let now = Utc::now();
+        let db = state.db.write().unwrap();
+        for (id, evidence_type, age_days) in [("old-lab", "lab_report", 400), ("new-lab", "lab_report", 10), ("old-cert", "certificate", 400)] {
+            let evidence = Evidence {
+                id: id.to_string(),
+                evidence_type: evidence_type.to_string(),
+                original_filename: None,
+                mime_type: None,
+                content_hash: "ab".repeat(32),
+                extracted_text: Some("Cobalt 25%".to_string()),
+                issuer_name: None,
+                issuer_type: None,
+                valid_from: None,
+                valid_until: None,
+                created_at: now - chrono::Duration::days(age_days),
+                shredded_at: None,
+            };
+            db.insert_evidence(&evidence, Some(b"report")).unwrap();
+        }
+        db.set_setting("evidence_retention_days", r#"{"lab_report": 365}"#).unwrap();
+        drop(db);
+
+        assert_eq!(apply_retention(&state.db, now).unwrap(), vec!["old-lab".to_string()]);
+        assert!(apply_retention(&state.db, now).unwrap().is_empty());
+
+        let db = state.db.read().unwrap();
+
+        let shredded = db.get_evidence("old-lab").unwrap().unwrap();
+        assert!(shredded.shredded_at.is_some());
+        assert!(shredded.extracted_text.is_none());
+        assert_eq!(shredded.content_hash, "ab".repeat(32));
+        assert!(db.get_evidence_content("old-lab").unwrap().is_none());
+        assert_eq!(db.get_evidence_content("old-cert").unwrap().unwrap(), b"report");
+        assert!(db.get_evidence("new-lab").unwrap().unwrap().shredded_at.is_none());
+    }
+
+    #[test]
+    fn test_mutations_are_audited_and_signed() {
+        let state = test_state();
+        let recycled = insert_claim(&state, "recycled_content", serde_json::json!(25), "percent");
+        let commitment = commit(&state, vec![recycled]);
+        {
+            let db = state.db.write().unwrap();
+            assert!(revoke_commitment_with(&state, &db, &commitment.id, "superseded").unwrap().data.unwrap());
+        }
+
+        let db = state.db.read().unwrap();
+        let entries = db.list_audit_entries().unwrap();
+        let commands: Vec<&str> = entries.iter().map(|entry| entry.command.as_str()).collect();
+        assert_eq!(commands, vec!["create_commitment", "create_commitment", "revoke_commitment"]);
+        let revoked = &entries[2];
+        assert_eq!(revoked.entity_id, commitment.id);
+        let revocation = db.get_commitment(&commitment.id).unwrap().unwrap().revocation.unwrap();
+        assert_eq!(revocation.payload.root, commitment.root);
+        assert_eq!(revocation.payload.reason, "superseded");
+        revocation.verify_from(&PublicKey::from_hex(&commitment.public_key).unwrap()).unwrap();
+        assert_eq!(revoked.before_hash, Some(audit::state_hash(&commitment).unwrap()));
+        assert_ne!(revoked.before_hash, revoked.after_hash);
+
+        let heads = db.list_signed_audit_heads().unwrap();
+        assert_eq!(heads.len(), 1);
+        assert_eq!(heads[0].public_key, commitment.public_key);
+        let verification = audit::verify_chain(&entries, &heads, &audit_trusted_keys(&state, &db).unwrap());
+        assert!(verification.valid, "{:?}", verification.error);
+        assert_eq!(verification.head_hash, revoked.entry_hash);
+    }
+
+    #[test]
+    fn test_commitment_credential_verifies() {
+        use crypto::data_integrity::{verify_proof, ASSERTION_METHOD};
+        use crypto::DidResolver;
+
+        let state = test_state();
+        let recycled = insert_claim(&state, "recycled_content", serde_json::json!(25), "percent");
+        let commitment = commit(&state, vec![recycled]);
+        let public_key = PublicKey::from_hex(&commitment.public_key).unwrap();
+        let db = state.db.write().unwrap();
+
+        // Without a did:web the key's did:key is the issuer
+        let credential = issue_commitment_credential_with(&state, &db, &commitment.id).unwrap().data.unwrap();
+        assert_eq!(credential["issuer"], public_key.to_did_key());
+        assert_eq!(credential["credentialSubject"]["commitmentRoot"], commitment.root);
+        let (_, key) = verify_proof(&credential, &DidResolver::new(), ASSERTION_METHOD).unwrap();
+        assert_eq!(key, public_key);
+        assert!(!export_did_document_with(&state, &db).unwrap().success);
+
+        db.set_setting("supplier_did", "did:web:acme.example").unwrap();
+        let document = export_did_document_with(&state, &db).unwrap().data.unwrap();
+        let credential = issue_commitment_credential_with(&state, &db, &commitment.id).unwrap().data.unwrap();
+        assert_eq!(credential["issuer"], "did:web:acme.example");
+        let mut resolver = DidResolver::new();
+        assert!(verify_proof(&credential, &resolver, ASSERTION_METHOD).is_err());
+        resolver.add_document(document);
+        let (proof, key) = verify_proof(&credential, &resolver, ASSERTION_METHOD).unwrap();
+        assert_eq!(key, public_key);
+        assert!(proof.verification_method.starts_with("did:web:acme.example#z6Mk"));
+
+        let mut altered = credential.clone();
+        altered["credentialSubject"]["claimCount"] = serde_json::json!(2);
+        assert!(verify_proof(&altered, &resolver, ASSERTION_METHOD).is_err());
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_external_signer_signs_without_vault_keys() {
+        let held = KeyPair::generate();
+        let public_key = held.public_key();
+        let (state, socket) = remote_signer_state(held);
+        let recycled = insert_claim(&state, "recycled_content", serde_json::json!(25), "percent");
+        let commitment = commit(&state, vec![recycled]);
+        assert_eq!(commitment.public_key, public_key.to_hex());
+        let root = commitments::from_hex(&commitment.root).unwrap();
+        assert!(public_key.verify_hex_in(Domain::COMMITMENT, &root, &commitment.signature).unwrap());
+        assert!(!public_key.verify_hex_in(Domain::PROOF_PACKAGE, &root, &commitment.signature).unwrap());
+        assert!(state.db.read().unwrap().list_keypairs().unwrap().is_empty());
+        {
+            // The external key keeps signing, so the agent won't hand over to a vault key
+            let db = state.db.write().unwrap();
+            assert!(rotate_keypair_with(&state, &db, "rotate_keypair", "scheduled").is_err());
+            assert!(db.list_keypairs().unwrap().is_empty());
+        }
+
+        let _ = std::fs::remove_file(&socket);
+    }
+}
+
+#[cfg(test)]
+mod key_tests {
+    use super::tests::test_state;
+    use super::*;
+
+    #[test]
+    fn test_rotation_links_keys() {
+        let state = test_state();
+        let db = state.db.write().unwrap();
+        let first = rotate_keypair_with(&state, &db, "generate_new_keypair", "replaced").unwrap().keypair;
+        let rotation = rotate_keypair_with(&state, &db, "rotate_keypair", "scheduled").unwrap();
+        let certificate = rotation.certificate.unwrap();
+        assert_eq!(certificate.previous_key, first.public_key);
+        let third = rotate_keypair_with(&state, &db, "rotate_keypair", "scheduled").unwrap();
+
+        // Brands trusting the first key reach the current one
+        let chain = db.key_chain(&third.keypair.public_key).unwrap();
+        let root = PublicKey::from_hex(&first.public_key).unwrap();
+        let keys = crypto::verify_key_chain(&root, &chain).unwrap();
+        assert_eq!(keys.last().unwrap().to_hex(), third.keypair.public_key);
+
+        let keypairs = db.list_keypairs().unwrap();
+        let statuses: Vec<KeyStatus> = keypairs.iter().map(|kp| kp.status()).collect();
+        assert_eq!(statuses, vec![KeyStatus::Retired, KeyStatus::Retired, KeyStatus::Active]);
+        assert_eq!(keypairs[0].retired_reason.as_deref(), Some("scheduled"));
+
+        let active = third.keypair.id.clone();
+        assert!(!set_keypair_retired(&state, &db, &active, "done", false).unwrap().success);
+        let compromised = set_keypair_retired(&state, &db, &active, "laptop stolen", true).unwrap().data.unwrap();
+        assert_eq!(compromised.status, KeyStatus::Compromised);
+        assert!(db.get_active_keypair().unwrap().is_none());
+        assert!(!set_keypair_retired(&state, &db, &active, "again", true).unwrap().success);
+
+        // The next key starts a chain of its own
+        let fresh = rotate_keypair_with(&state, &db, "generate_new_keypair", "replaced").unwrap();
+        assert!(fresh.certificate.is_none());
+        assert!(db.key_chain(&fresh.keypair.public_key).unwrap().is_empty());
+    }
+
+    #[test]
+    fn test_keys_restored_from_backup() {
+        let params = KeystoreParams { m_cost: 8, t_cost: 1, p_cost: 1 };
+        let lost = test_state();
+        let backup = {
+            let db = lost.db.write().unwrap();
+            rotate_keypair_with(&lost, &db, "generate_new_keypair", "replaced").unwrap();
+            rotate_keypair_with(&lost, &db, "rotate_keypair", "scheduled").unwrap();
+            assert!(backup_keys_with(&lost, &db, "short", params).is_err());
+            backup_keys_with(&lost, &db, "backup passphrase", params).unwrap()
+        };
+        let json = serde_json::to_string(&backup).unwrap();
+        let active = lost.db.read().unwrap().get_active_keypair().unwrap().unwrap();
+        assert!(!json.contains(&active.secret_key_encrypted));
+
+        let replacement = test_state();
+        let db = replacement.db.write().unwrap();
+        let backup: KeyBackup = serde_json::from_str(&json).unwrap();
+        assert!(restore_keys_with(&replacement, &db, &backup, "wrong passphrase").is_err());
+        assert!(db.list_keypairs().unwrap().is_empty());
+
+        let restore = restore_keys_with(&replacement, &db, &backup, "backup passphrase").unwrap();
+        assert_eq!((restore.restored.len(), restore.skipped), (2, 0));
+        let restored = db.get_active_keypair().unwrap().unwrap();
+        assert_eq!(restored.public_key, active.public_key);
+        assert_eq!(db.key_chain(&restored.public_key).unwrap().len(), 1);
+
+        // The restored key signs as the supplier again
+        let signer = active_signer(&replacement, &db).unwrap().unwrap();
+        assert_eq!(signer.public_key().to_hex(), active.public_key);
+
+        let again = restore_keys_with(&replacement, &db, &backup, "backup passphrase").unwrap();
+        assert_eq!((again.restored.len(), again.skipped), (0, 2));
+    }
+
+    #[test]
+    fn test_keys_recovered_from_phrase() {
+        let lost = test_state();
+        let (phrase, held) = {
+            let db = lost.db.write().unwrap();
+            let phrase = create_recovery_phrase_with(&lost, &db).unwrap();
+            assert!(create_recovery_phrase_with(&lost, &db).is_err());
+            rotate_keypair_with(&lost, &db, "generate_new_keypair", "replaced").unwrap();
+            rotate_keypair_with(&lost, &db, "rotate_keypair", "scheduled").unwrap();
+            let held = db.list_keypairs().unwrap();
+            let paths: Vec<_> = held.iter().map(|kp| kp.derivation_path.clone().unwrap()).collect();
+            assert_eq!(paths, vec!["m/0'/0'", "m/0'/1'"]);
+            (phrase, held)
+        };
+        let paths: Vec<String> = held.iter().map(|kp| kp.derivation_path.clone().unwrap()).collect();
+
+        // Rebuilding in place reseals the derived keys
+        {
+            let db = lost.db.write().unwrap();
+            let rebuilt = recover_keys_with(&lost, &db, phrase.phrase.expose_secret(), &paths).unwrap();
+            assert_eq!((rebuilt.rebuilt, rebuilt.recovered.len()), (2, 0));
+            let other = Mnemonic::generate();
+            assert!(recover_keys_with(&lost, &db, other.phrase().expose_secret(), &paths).is_err());
+        }
+
+        let replacement = test_state();
+        let db = replacement.db.write().unwrap();
+        let recovery = recover_keys_with(&replacement, &db, phrase.phrase.expose_secret(), &paths).unwrap();
+        let recovered: Vec<_> = recovery.recovered.iter().map(|kp| kp.public_key.clone()).collect();
+        assert_eq!(recovered, held.iter().map(|kp| kp.public_key.clone()).collect::<Vec<_>>());
+        let active = db.get_active_keypair().unwrap().unwrap();
+        assert_eq!(active.public_key, held[1].public_key);
+        assert_eq!(db.get_recovery_seed().unwrap().unwrap().fingerprint, phrase.fingerprint);
+
+        // New keys continue from the recovered index
+        let next = rotate_keypair_with(&replacement, &db, "rotate_keypair", "scheduled").unwrap();
+        assert_eq!(next.keypair.derivation_path.as_deref(), Some("m/0'/2'"));
+    }
+
+    #[test]
+    fn test_recovery_activates_last_added_key() {
+        let lost = test_state();
+        let phrase = {
+            let db = lost.db.write().unwrap();
+            create_recovery_phrase_with(&lost, &db).unwrap()
+        };
+
+        // The last path repeats the first, so the key before it takes over
+        let paths: Vec<String> = ["m/0'/0'", "m/0'/1'", "m/0'/0'"].iter().map(|p| p.to_string()).collect();
+        let replacement = test_state();
+        let db = replacement.db.write().unwrap();
+        let recovery = recover_keys_with(&replacement, &db, phrase.phrase.expose_secret(), &paths).unwrap();
+        assert_eq!(recovery.recovered.len(), 2);
+        let active = db.get_active_keypair().unwrap().unwrap();
+        assert_eq!(active.derivation_path.as_deref(), Some("m/0'/1'"));
+        assert_eq!(active.retired_reason, None);
+        let first = db.get_keypair(&recovery.recovered[0].id).unwrap().unwrap();
+        assert_eq!(first.retired_reason.as_deref(), Some("recovered"));
+    }
+}
+
+#[cfg(all(test, feature = "mock-prover"))]
+mod prover_tests {
+    use super::tests::{commit, insert_claim, proof_input, test_state};
+    #[cfg(unix)]
+    use super::tests::remote_signer_state;
+    use super::*;
+    use crate::zk::mock::MockProver;
+
+    fn mock_backend() -> anyhow::Result<Box<dyn ProverBackend>> {
+        Ok(Box::new(MockProver))
+    }
+
+    #[test]
+    fn test_generate_mock_proof_and_reuse_cache() {
+        let state = test_state();
+        let recycled = insert_claim(&state, "recycled_content", serde_json::json!(25), "percent");
+        let carbon = insert_claim(&state, "carbon_footprint", serde_json::json!(12.5), "kg_co2e");
+        let commitment = commit(&state, vec![recycled, carbon]);
+
+        let first = generate_proof_with(&state, proof_input(&commitment.id, "RECYCLED_CONTENT_GTE_V1", Some(20)), mock_backend).unwrap();
+        assert!(first.success, "{:?}", first.error);
+        let first = first.data.unwrap();
+        assert!(zkp_core::is_mock_proof(&hex::decode(&first.proof).unwrap()));
+        assert_eq!(first.public_inputs.commitment_root, commitment.root);
+
+        let second = generate_proof_with(&state, proof_input(&commitment.id, "RECYCLED_CONTENT_GTE_V1", Some(20)), mock_backend)
+            .unwrap()
+            .data
+            .unwrap();
+        assert_eq!(first.proof, second.proof);
+        assert_ne!(first.nonce, second.nonce);
+
+        let carbon_proof = generate_proof_with(&state, proof_input(&commitment.id, "CARBON_FOOTPRINT_LTE_V1", Some(1300)), mock_backend).unwrap();
+        assert!(carbon_proof.success, "{:?}", carbon_proof.error);
+
+        // A cached mock proof is not served once the real prover is selected
+        struct NoirStandIn;
+        impl ProverBackend for NoirStandIn {
+            fn name(&self) -> &'static str {
+                "noir-cli"
+            }
+            fn prove(&self, _inputs: CircuitInputs) -> anyhow::Result<String> {
+                Ok("5eed".to_string())
+            }
+        }
+        fn noir_backend() -> anyhow::Result<Box<dyn ProverBackend>> {
+            Ok(Box::new(NoirStandIn))
+        }
+        let real = generate_proof_with(&state, proof_input(&commitment.id, "RECYCLED_CONTENT_GTE_V1", Some(20)), noir_backend)
+            .unwrap()
+            .data
+            .unwrap();
+        assert_eq!(real.proof, "5eed");
+    }
+
+    #[test]
+    fn test_proof_uses_frozen_snapshot() {
+        let state = test_state();
+        let recycled = insert_claim(&state, "recycled_content", serde_json::json!(25), "percent");
+        let carbon = insert_claim(&state, "carbon_footprint", serde_json::json!(12.5), "kg_co2e");
+        let commitment = commit(&state, vec![recycled.clone(), carbon.clone()]);
+
+        {
+            let db = state.db.write().unwrap();
+            let mut edited = db.get_claim(&recycled).unwrap().unwrap();
+            edited.value = serde_json::json!(5);
+            db.update_claim(&edited).unwrap();
+            db.retire_claim(&carbon).unwrap();
+        }
+
+        // The committed value (25) is proven, not the edited one (5), and the
+        // retired claim still proves
+        let response = generate_proof_with(&state, proof_input(&commitment.id, "RECYCLED_CONTENT_GTE_V1", Some(20)), mock_backend).unwrap();
+        assert!(response.success, "{:?}", response.error);
+        let response = generate_proof_with(&state, proof_input(&commitment.id, "CARBON_FOOTPRINT_LTE_V1", Some(1300)), mock_backend).unwrap();
+        assert!(response.success, "{:?}", response.error);
+    }
+
+    #[test]
+    fn test_claim_selection_by_id_and_type() {
+        let state = test_state();
+        let low = insert_claim(&state, "recycled_content", serde_json::json!(18), "percent");
+        let high = insert_claim(&state, "recycled_content", serde_json::json!(30), "percent");
+        let carbon = insert_claim(&state, "carbon_footprint", serde_json::json!(12.5), "kg_co2e");
+        let commitment = commit(&state, vec![low, high.clone(), carbon.clone()]);
+
+        // Two recycled_content claims: resolving by type is ambiguous
+        let response = generate_proof_with(&state, proof_input(&commitment.id, "RECYCLED_CONTENT_GTE_V1", Some(20)), mock_backend).unwrap();
+        assert!(response.error.unwrap().contains("specify claim_id"));
+
+        let mut input = proof_input(&commitment.id, "RECYCLED_CONTENT_GTE_V1", Some(20));
+        input.claim_id = Some(high);
+        let response = generate_proof_with(&state, input, mock_backend).unwrap();
+        assert!(response.success, "{:?}", response.error);
+
+        // A claim of the wrong type is refused before any witness is built
+        let mut input = proof_input(&commitment.id, "RECYCLED_CONTENT_GTE_V1", Some(20));
+        input.claim_id = Some(carbon);
+        let response = generate_proof_with(&state, input, mock_backend).unwrap();
+        assert!(response.error.unwrap().starts_with("Claim type mismatch"));
+    }
+
+    #[test]
+    fn test_lifecycle_carbon_footprint_proof() {
+        let state = test_state();
+        let recycled = insert_claim(&state, "recycled_content", serde_json::json!(25), "percent");
+        let mut claim_ids = vec![recycled.clone()];
+        for (stage, value) in LifecycleStage::ALL.into_iter().zip([4.5, 3.0, 1.25, 0.75, 0.5]) {
+            claim_ids.push(insert_claim(&state, stage.claim_type(), serde_json::json!(value), "kg_co2e"));
+        }
+        let commitment = commit(&state, claim_ids);
+
+        // Total is 10.0 kg CO2e, i.e. 1000 in scaled units; stages resolve by claim type
+        let response = generate_proof_with(&state, proof_input(&commitment.id, "CARBON_FOOTPRINT_LIFECYCLE_V1", Some(1000)), mock_backend).unwrap();
+        assert!(response.success, "{:?}", response.error);
+
+        let response = generate_proof_with(&state, proof_input(&commitment.id, "CARBON_FOOTPRINT_LIFECYCLE_V1", Some(900)), mock_backend).unwrap();
+        let details = response.details.unwrap();
+        assert_eq!(details["reason"], "above_threshold");
+        assert_eq!(details["margin"], 100);
+
+        let mut stage_claims = BTreeMap::new();
+        stage_claims.insert(LifecycleStage::Transport, recycled);
+        let mut input = proof_input(&commitment.id, "CARBON_FOOTPRINT_LIFECYCLE_V1", Some(1000));
+        input.stage_claims = Some(stage_claims);
+        let response = generate_proof_with(&state, input, mock_backend).unwrap();
+        assert!(!response.success);
+    }
+
+    #[test]
+    fn test_energy_density_range_proof() {
+        let state = test_state();
+        let density = insert_claim(&state, "energy_density", serde_json::json!(265), "Wh/kg");
+        let commitment = commit(&state, vec![density]);
+
+        let mut input = proof_input(&commitment.id, "ENERGY_DENSITY_RANGE_V1", None);
+        input.min_threshold = Some(200);
+        input.max_threshold = Some(300);
+        let response = generate_proof_with(&state, input, mock_backend).unwrap();
+        assert!(response.success, "{:?}", response.error);
+        let package = response.data.unwrap();
+        assert_eq!(package.public_inputs.min_threshold, Some(200));
+        assert_eq!(package.public_inputs.max_threshold, Some(300));
+        assert_eq!(package.public_inputs.threshold, None);
+
+        let mut input = proof_input(&commitment.id, "ENERGY_DENSITY_RANGE_V1", None);
+        input.min_threshold = Some(270);
+        input.max_threshold = Some(300);
+        let response = generate_proof_with(&state, input, mock_backend).unwrap();
+        assert!(!response.success);
+        assert_eq!(response.details.unwrap()["reason"], "below_threshold");
+    }
+
+    #[test]
+    fn test_substance_proof_carries_proof_bytes() {
+        let state = test_state();
+        let substances = insert_claim(
+            &state,
+            "substance_content",
+            serde_json::json!({ "substances": ["7439-92-1", "7440-48-4"] }),
+            "cas",
+        );
+        let commitment = commit(&state, vec![substances]);
+
+        // The substance branch once returned the package without its proof
+        let mut input = proof_input(&commitment.id, "SUBSTANCE_NOT_IN_LIST_V1", None);
+        input.extra = Some(serde_json::json!({ "forbidden_substances": ["7440-43-9"] }));
+        let response = generate_proof_with(&state, input, mock_backend).unwrap();
+        assert!(response.success, "{:?}", response.error);
+        let package = response.data.unwrap();
+        let proof = hex::decode(&package.proof).unwrap();
+        assert!(proof.starts_with(zkp_core::MOCK_PROOF_TAG));
+        assert!(proof.len() > zkp_core::MOCK_PROOF_TAG.len());
+        assert!(package.public_inputs.extra.unwrap().get("forbiddenListHash").is_some());
+
+        let mut input = proof_input(&commitment.id, "SUBSTANCE_NOT_IN_LIST_V1", None);
+        input.extra = Some(serde_json::json!({ "forbidden_substances": ["7440-48-4"] }));
+        let response = generate_proof_with(&state, input, mock_backend).unwrap();
+        assert!(!response.success);
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_external_signer_signs_proof_packages() {
+        let held = KeyPair::generate();
+        let public_key = held.public_key();
+        let (state, socket) = remote_signer_state(held);
+        let recycled = insert_claim(&state, "recycled_content", serde_json::json!(25), "percent");
+        let commitment = commit(&state, vec![recycled]);
+        let response = generate_proof_with(&state, proof_input(&commitment.id, "RECYCLED_CONTENT_GTE_V1", Some(20)), mock_backend).unwrap();
+        let mut package = response.data.unwrap();
+        assert_eq!(package.supplier_public_key.as_deref(), Some(public_key.to_hex().as_str()));
+        let signature = package.supplier_signature.clone().unwrap();
+        assert!(public_key.verify_hex_in(Domain::PROOF_PACKAGE, &package.signing_payload().unwrap(), &signature).unwrap());
+        package.public_inputs.threshold = Some(10);
+        assert!(!public_key.verify_hex_in(Domain::PROOF_PACKAGE, &package.signing_payload().unwrap(), &signature).unwrap());
+        let _ = std::fs::remove_file(&socket);
+    }
 }
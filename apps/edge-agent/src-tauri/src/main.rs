@@ -12,29 +12,129 @@
 
 mod commands;
 mod ollama;
+mod signing;
 mod storage;
 mod zk;
 
-use storage::Database;
-use std::sync::Mutex;
+use crypto::Signer;
+use storage::{DbPool, Vault};
+use std::path::PathBuf;
+use std::sync::Arc;
+use std::time::Duration;
 
 /// Application state shared across commands
 pub struct AppState {
-    db: Mutex<Database>,
+    db: Arc<DbPool>,
+    vault: Arc<Vault>,
     ollama_base: String,
+    /// Key held outside the vault (`SIGNER_BACKEND`); `None` signs with the
+    /// active keypair
+    signer: Option<Arc<dyn Signer>>,
+}
+
+/// Data directory override: `--data-dir <path>` (or `--data-dir=<path>`)
+/// takes precedence over `EDGE_AGENT_DATA_DIR`. `:memory:` selects an
+/// in-memory database.
+fn data_dir_override(
+    mut args: impl Iterator<Item = String>,
+    env_value: Option<String>,
+) -> Option<PathBuf> {
+    while let Some(arg) = args.next() {
+        if arg == "--data-dir" {
+            return args.next().map(PathBuf::from);
+        }
+        if let Some(path) = arg.strip_prefix("--data-dir=") {
+            return Some(PathBuf::from(path));
+        }
+    }
+    env_value.filter(|value| !value.is_empty()).map(PathBuf::from)
 }
 
 fn main() {
+    // `edge-agent gen-manifest [--check]` updates or checks the committed
+    // circuit manifest and exits
+    if std::env::args().nth(1).as_deref() == Some("gen-manifest") {
+        let result = if std::env::args().nth(2).as_deref() == Some("--check") {
+            zk::check_manifest().map(|path| println!("{} matches the local build", path.display()))
+        } else {
+            zk::update_manifest().map(|(path, changes)| {
+                println!("Wrote circuit manifest to {}", path.display());
+                for change in changes {
+                    println!("  {}", change);
+                }
+            })
+        };
+        if let Err(e) = result {
+            eprintln!("gen-manifest failed: {:#}", e);
+            std::process::exit(1);
+        }
+        return;
+    }
+
+    // Vault starts locked; every connection seals sensitive columns through it
+    let vault = Arc::new(Vault::new(Duration::from_secs(
+        storage::vault::DEFAULT_AUTO_LOCK_SECS,
+    )));
+
     // Initialize database
-    let db = Database::new().expect("Failed to initialize database");
+    let data_dir = data_dir_override(
+        std::env::args().skip(1),
+        std::env::var("EDGE_AGENT_DATA_DIR").ok(),
+    );
+    let db = match data_dir {
+        // SQLite's name for a throwaway database; nothing is written to disk
+        Some(dir) if dir.as_os_str() == ":memory:" => DbPool::in_memory(Arc::clone(&vault)),
+        Some(dir) => DbPool::open(
+            &dir.join(storage::DB_FILE_NAME),
+            storage::DEFAULT_READERS,
+            Arc::clone(&vault),
+        ),
+        None => storage::default_data_dir().and_then(|dir| {
+            DbPool::open(&dir.join(storage::DB_FILE_NAME), storage::DEFAULT_READERS, Arc::clone(&vault))
+        }),
+    }
+    .map(Arc::new)
+    .expect("Failed to initialize database");
 
     // Get Ollama base URL from environment or default
     let ollama_base = std::env::var("OLLAMA_BASE_URL")
         .unwrap_or_else(|_| "http://localhost:11434".to_string());
 
+    let signer = signing::signer_from_env().expect("Failed to configure signer");
+
+    // Idle unlocked sessions are locked in the background
+    let auto_lock_secs = db
+        .read()
+        .ok()
+        .and_then(|db| db.get_setting("vault_auto_lock_secs").ok().flatten())
+        .and_then(|v| v.parse().ok());
+    if let Some(secs) = auto_lock_secs {
+        vault
+            .set_auto_lock(Duration::from_secs(secs))
+            .expect("Failed to configure vault auto-lock");
+    }
+    {
+        let vault = Arc::clone(&vault);
+        std::thread::spawn(move || loop {
+            std::thread::sleep(Duration::from_secs(30));
+            let _ = vault.lock_if_idle();
+        });
+    }
+
+    // Evidence past its retention period is shredded at startup and hourly
+    {
+        let db = Arc::clone(&db);
+        std::thread::spawn(move || loop {
+            let _ = commands::apply_retention(&db, chrono::Utc::now());
+            std::thread::sleep(Duration::from_secs(60 * 60));
+        });
+    }
+
     let state = AppState {
-        db: Mutex::new(db),
+        db,
+        vault,
         ollama_base,
+        signer,
     };
 
     tauri::Builder::default()
@@ -45,12 +145,16 @@ fn main() {
             commands::list_evidence,
             commands::get_evidence,
             commands::delete_evidence,
+            commands::get_evidence_dependents,
+            commands::shred_evidence,
+            commands::apply_retention_policies,
             // Claim commands
             commands::extract_claims,
             commands::list_claims,
             commands::get_claim,
             commands::update_claim,
             commands::delete_claim,
+            commands::claim_history,
             commands::verify_claim,
             // Commitment commands
             commands::create_commitment,
@@ -62,6 +166,27 @@ fn main() {
             // Key management
             commands::get_keypair,
             commands::generate_new_keypair,
+            commands::list_keypairs,
+            commands::rotate_keypair,
+            commands::retire_keypair,
+            commands::mark_keypair_compromised,
+            commands::export_key_chain,
+            commands::export_did_document,
+            commands::issue_commitment_credential,
+            commands::backup_keys,
+            commands::restore_keys,
+            commands::create_recovery_phrase,
+            commands::recover_keys,
+            // Vault
+            commands::vault_status,
+            commands::setup_vault,
+            commands::unlock_vault,
+            commands::lock_vault,
+            commands::change_vault_passphrase,
+            // Audit log
+            commands::sign_audit_head,
+            commands::verify_audit_log,
+            commands::export_audit_log,
             // Settings
             commands::get_settings,
             commands::update_settings,
@@ -69,3 +194,22 @@ fn main() {
         .run(tauri::generate_context!())
         .expect("error while running tauri application");
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn args(values: &[&str]) -> impl Iterator<Item = String> {
+        values.iter().map(|v| v.to_string()).collect::<Vec<_>>().into_iter()
+    }
+
+    #[test]
+    fn test_data_dir_override() {
+        let env = Some("/env/profile".to_string());
+        assert_eq!(data_dir_override(args(&["--data-dir", "/cli"]), env.clone()), Some(PathBuf::from("/cli")));
+        assert_eq!(data_dir_override(args(&["--data-dir=/cli"]), env.clone()), Some(PathBuf::from("/cli")));
+        assert_eq!(data_dir_override(args(&[]), env), Some(PathBuf::from("/env/profile")));
+        assert_eq!(data_dir_override(args(&[]), Some(String::new())), None);
+        assert_eq!(data_dir_override(args(&[]), None), None);
+    }
+}
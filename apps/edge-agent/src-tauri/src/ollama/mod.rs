@@ -58,7 +58,7 @@ impl OllamaClient {
             r#"You are a data extraction assistant for Digital Product Passports. Extract structured claims from the following document.
 
 For each claim found, extract:
-- claim_type: One of: recycled_content, carbon_footprint, certification, substance_content, origin, manufacturing_date, battery_capacity, battery_chemistry
+- claim_type: One of: recycled_content, carbon_footprint, certification, substance_content, origin, manufacturing_date, battery_capacity, battery_chemistry, energy_density, carbon_footprint_raw_materials, carbon_footprint_manufacturing, carbon_footprint_transport, carbon_footprint_use, carbon_footprint_end_of_life
 - value: The numeric value or string value
 - unit: The unit of measurement (e.g., "percent", "kg_co2e", "date", "kwh")
 - confidence: Your confidence in the extraction (0.0 to 1.0)
@@ -0,0 +1,56 @@
+//! Signer selection
+//!
+//! Commitments, proof packages and audit heads are signed with the vault's
+//! active keypair unless `SIGNER_BACKEND` selects a key held outside the
+//! Edge Agent: `remote` (a signing daemon on a Unix socket) or `pkcs11` (an
+//! HSM, in builds with the `pkcs11` feature).
+
+use anyhow::{anyhow, Context, Result};
+use crypto::Signer;
+use std::sync::Arc;
+
+/// The external signer configured in the environment, or `None` to sign
+/// with the vault's keys.
+pub fn signer_from_env() -> Result<Option<Arc<dyn Signer>>> {
+    match std::env::var("SIGNER_BACKEND").as_deref() {
+        Ok("software") | Err(_) => Ok(None),
+        Ok("remote") => remote_signer().map(Some),
+        Ok("pkcs11") => pkcs11_signer().map(Some),
+        Ok(other) => Err(anyhow!("Unsupported SIGNER_BACKEND {}", other)),
+    }
+}
+
+fn required(name: &str) -> Result<String> {
+    std::env::var(name).with_context(|| format!("{} must be set", name))
+}
+
+#[cfg(unix)]
+fn remote_signer() -> Result<Arc<dyn Signer>> {
+    let socket = required("SIGNER_SOCKET")?;
+    let key_id = required("SIGNER_KEY_ID")?;
+    let signer = crypto::signer::remote::RemoteSigner::connect(&socket, &key_id)
+        .with_context(|| format!("Signing daemon at {} unavailable", socket))?;
+    Ok(Arc::new(signer))
+}
+
+#[cfg(not(unix))]
+fn remote_signer() -> Result<Arc<dyn Signer>> {
+    Err(anyhow!("The remote signer needs Unix domain sockets"))
+}
+
+#[cfg(feature = "pkcs11")]
+fn pkcs11_signer() -> Result<Arc<dyn Signer>> {
+    let signer = crypto::signer::pkcs11::Pkcs11Signer::open(
+        required("PKCS11_MODULE")?,
+        &required("PKCS11_TOKEN_LABEL")?,
+        &required("PKCS11_PIN")?,
+        &required("PKCS11_KEY_LABEL")?,
+    )
+    .context("PKCS#11 token unavailable")?;
+    Ok(Arc::new(signer))
+}
+
+#[cfg(not(feature = "pkcs11"))]
+fn pkcs11_signer() -> Result<Arc<dyn Signer>> {
+    Err(anyhow!("PKCS#11 signer not available: build with the pkcs11 feature"))
+}
@@ -0,0 +1,317 @@
+//! Tamper-evident audit log
+//!
+//! Every mutation made through the Edge Agent appends an entry naming the
+//! command, the entity touched, BLAKE3 hashes of the entity before and after,
+//! the time and the local actor. Each entry hash covers the previous entry's
+//! hash, so editing, dropping or reordering entries breaks the chain from that
+//! point on. The table rejects updates and deletes, and the chain head is
+//! periodically signed with the active keypair so a rewritten log can be told
+//! apart from the one the supplier signed.
+
+use anyhow::Result;
+use chrono::{DateTime, Utc};
+use crypto::{Domain, PublicKey, Signature};
+use serde::{Deserialize, Serialize};
+
+/// `prev_hash` of the first entry
+pub const GENESIS_HASH: &str = "0000000000000000000000000000000000000000000000000000000000000000";
+
+const ENTRY_DOMAIN: &[u8] = b"zkdpp-edge-agent audit entry v1";
+
+/// A mutation about to be logged
+#[derive(Debug, Clone)]
+pub struct AuditRecord {
+    pub command: String,
+    pub entity_type: String,
+    pub entity_id: String,
+    pub before_hash: Option<String>,
+    pub after_hash: Option<String>,
+    pub actor: String,
+}
+
+impl AuditRecord {
+    pub fn new(command: &str, entity_type: &str, entity_id: &str) -> Self {
+        AuditRecord {
+            command: command.to_string(),
+            entity_type: entity_type.to_string(),
+            entity_id: entity_id.to_string(),
+            before_hash: None,
+            after_hash: None,
+            actor: local_actor(),
+        }
+    }
+
+    pub fn before<T: Serialize>(mut self, state: &T) -> Result<Self> {
+        self.before_hash = Some(state_hash(state)?);
+        Ok(self)
+    }
+
+    pub fn after<T: Serialize>(mut self, state: &T) -> Result<Self> {
+        self.after_hash = Some(state_hash(state)?);
+        Ok(self)
+    }
+}
+
+/// A stored, chained audit entry
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct AuditEntry {
+    pub seq: u64,
+    pub command: String,
+    pub entity_type: String,
+    pub entity_id: String,
+    pub before_hash: Option<String>,
+    pub after_hash: Option<String>,
+    pub actor: String,
+    pub recorded_at: String,
+    pub prev_hash: String,
+    pub entry_hash: String,
+}
+
+/// The chain head at `seq`, signed with a supplier keypair
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct SignedAuditHead {
+    pub seq: u64,
+    pub entry_hash: String,
+    pub public_key: String,
+    pub signature: String,
+    pub signed_at: DateTime<Utc>,
+}
+
+/// Outcome of checking the chain and its signed heads
+#[derive(Debug, Clone, Serialize)]
+pub struct AuditVerification {
+    pub valid: bool,
+    pub entries: u64,
+    pub head_hash: String,
+    pub signed_heads: usize,
+    /// Sequence number of the first entry or head that failed
+    pub first_invalid_seq: Option<u64>,
+    pub error: Option<String>,
+}
+
+/// BLAKE3 hash of an entity's JSON encoding
+pub fn state_hash<T: Serialize>(state: &T) -> Result<String> {
+    Ok(commitments::to_hex(&commitments::hash_bytes(&serde_json::to_vec(state)?)))
+}
+
+/// Hash of an entry, covering the previous entry's hash. Fields are length
+/// prefixed so no two different entries encode alike.
+pub fn entry_hash(prev_hash: &str, seq: u64, record: &AuditRecord, recorded_at: &str) -> String {
+    let mut data = ENTRY_DOMAIN.to_vec();
+    data.extend_from_slice(&seq.to_be_bytes());
+    let fields = [
+        Some(prev_hash),
+        Some(record.command.as_str()),
+        Some(record.entity_type.as_str()),
+        Some(record.entity_id.as_str()),
+        record.before_hash.as_deref(),
+        record.after_hash.as_deref(),
+        Some(record.actor.as_str()),
+        Some(recorded_at),
+    ];
+    for field in fields {
+        match field {
+            Some(value) => {
+                data.push(1);
+                data.extend_from_slice(&(value.len() as u64).to_be_bytes());
+                data.extend_from_slice(value.as_bytes());
+            }
+            None => data.push(0),
+        }
+    }
+    commitments::to_hex(&commitments::hash_bytes(&data))
+}
+
+/// Payload signed for a chain head, in [`Domain::AUDIT_HEAD`]
+pub fn head_payload(seq: u64, entry_hash: &str) -> Vec<u8> {
+    let mut payload = seq.to_be_bytes().to_vec();
+    payload.extend_from_slice(entry_hash.as_bytes());
+    payload
+}
+
+/// Checks that entries are contiguous and correctly chained, and that every
+/// signed head matches its entry and carries a valid signature by one of the
+/// `trusted` keys. The key embedded in a head is only a hint: anyone
+/// rewriting the log could sign it with a key of their own.
+pub fn verify_chain(entries: &[AuditEntry], heads: &[SignedAuditHead], trusted: &[PublicKey]) -> AuditVerification {
+    let mut result = AuditVerification {
+        valid: true,
+        entries: entries.len() as u64,
+        head_hash: entries.last().map(|e| e.entry_hash.clone()).unwrap_or_else(|| GENESIS_HASH.to_string()),
+        signed_heads: heads.len(),
+        first_invalid_seq: None,
+        error: None,
+    };
+    let mut fail = |seq: u64, error: String| {
+        result.valid = false;
+        result.first_invalid_seq = Some(seq);
+        result.error = Some(error);
+    };
+
+    let mut prev_hash = GENESIS_HASH.to_string();
+    for (index, entry) in entries.iter().enumerate() {
+        let expected_seq = index as u64 + 1;
+        if entry.seq != expected_seq {
+            fail(expected_seq, format!("Entry {} is missing", expected_seq));
+            return result;
+        }
+        if entry.prev_hash != prev_hash {
+            fail(entry.seq, format!("Entry {} does not follow entry {}", entry.seq, entry.seq - 1));
+            return result;
+        }
+        let record = AuditRecord {
+            command: entry.command.clone(),
+            entity_type: entry.entity_type.clone(),
+            entity_id: entry.entity_id.clone(),
+            before_hash: entry.before_hash.clone(),
+            after_hash: entry.after_hash.clone(),
+            actor: entry.actor.clone(),
+        };
+        if entry_hash(&entry.prev_hash, entry.seq, &record, &entry.recorded_at) != entry.entry_hash {
+            fail(entry.seq, format!("Entry {} has been altered", entry.seq));
+            return result;
+        }
+        prev_hash = entry.entry_hash.clone();
+    }
+
+    // Head signatures are checked in one batch after the structural checks
+    let mut signed = Vec::with_capacity(heads.len());
+    for head in heads {
+        let entry = entries.get((head.seq as usize).wrapping_sub(1));
+        if entry.map(|e| &e.entry_hash) != Some(&head.entry_hash) {
+            fail(head.seq, format!("Signed head at entry {} does not match the log", head.seq));
+            return result;
+        }
+        let parsed = PublicKey::from_hex(&head.public_key).ok().zip(
+            hex::decode(&head.signature).ok().and_then(|bytes| Signature::from_slice(&bytes).ok()),
+        );
+        match parsed {
+            Some((key, _)) if !trusted.contains(&key) => {
+                fail(head.seq, format!("Head at entry {} is signed by a key the supplier does not hold", head.seq));
+                return result;
+            }
+            Some((key, signature)) => signed.push((key, head_payload(head.seq, &head.entry_hash), signature)),
+            None => {
+                fail(head.seq, format!("Signature on head at entry {} is invalid", head.seq));
+                return result;
+            }
+        }
+    }
+    let items: Vec<(PublicKey, &[u8], Signature)> = signed
+        .iter()
+        .map(|(key, payload, signature)| (*key, payload.as_slice(), *signature))
+        .collect();
+    if let Err(e) = crypto::verify_batch(Domain::AUDIT_HEAD, &items) {
+        let seq = match e {
+            crypto::CryptoError::InvalidSignatures(invalid) => heads[invalid[0]].seq,
+            _ => heads[0].seq,
+        };
+        fail(seq, format!("Signature on head at entry {} is invalid", seq));
+    }
+
+    result
+}
+
+/// The OS account running the Edge Agent
+fn local_actor() -> String {
+    std::env::var("USER")
+        .or_else(|_| std::env::var("USERNAME"))
+        .unwrap_or_else(|_| "local".to_string())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::storage::{Database, Vault};
+    use crypto::{KeyPair, Signer};
+    use std::sync::Arc;
+    use std::time::Duration;
+
+    fn log_with_entries() -> Database {
+        let db = Database::in_memory(Arc::new(Vault::new(Duration::from_secs(60)))).unwrap();
+        db.append_audit(&AuditRecord::new("ingest_document", "evidence", "ev-1").after(&"v1").unwrap()).unwrap();
+        db.append_audit(&AuditRecord::new("verify_claim", "claim", "claim-1").before(&"v1").unwrap()).unwrap();
+        db.append_audit(&AuditRecord::new("revoke_commitment", "commitment", "c-1")).unwrap();
+        db
+    }
+
+    #[test]
+    fn test_chain_detects_tampering() {
+        let db = log_with_entries();
+        let entries = db.list_audit_entries().unwrap();
+        assert_eq!(entries[0].prev_hash, GENESIS_HASH);
+        assert_eq!(entries[1].prev_hash, entries[0].entry_hash);
+        assert!(verify_chain(&entries, &[], &[]).valid);
+
+        let mut altered = entries.clone();
+        altered[1].entity_id = "claim-2".to_string();
+        let result = verify_chain(&altered, &[], &[]);
+        assert!(!result.valid);
+        assert_eq!(result.first_invalid_seq, Some(2));
+
+        let mut dropped = entries.clone();
+        dropped.remove(1);
+        assert_eq!(verify_chain(&dropped, &[], &[]).first_invalid_seq, Some(2));
+
+        assert!(db.conn.execute("UPDATE audit_log SET actor = 'someone'", []).is_err());
+        assert!(db.conn.execute("DELETE FROM audit_log WHERE seq = 3", []).is_err());
+    }
+
+    #[test]
+    fn test_signed_head_verification() {
+        let db = log_with_entries();
+        let entries = db.list_audit_entries().unwrap();
+        let keypair = KeyPair::generate();
+        let head = SignedAuditHead {
+            seq: 2,
+            entry_hash: entries[1].entry_hash.clone(),
+            public_key: keypair.public_key().to_hex(),
+            signature: keypair.sign_hex_in(Domain::AUDIT_HEAD, &head_payload(2, &entries[1].entry_hash)).unwrap(),
+            signed_at: Utc::now(),
+        };
+        db.insert_signed_audit_head(&head).unwrap();
+        let heads = db.list_signed_audit_heads().unwrap();
+        let trusted = [keypair.public_key()];
+        assert!(verify_chain(&entries, &heads, &trusted).valid);
+
+        // A log rewritten after signing no longer matches the signed head
+        let mut forged = head.clone();
+        forged.signature = keypair.sign_hex_in(Domain::AUDIT_HEAD, &head_payload(3, &entries[2].entry_hash)).unwrap();
+        assert_eq!(verify_chain(&entries, &[forged], &trusted).first_invalid_seq, Some(2));
+        assert!(!verify_chain(&entries[..1], &heads, &trusted).valid);
+    }
+
+    #[test]
+    fn test_head_signed_by_foreign_key_rejected() {
+        let db = log_with_entries();
+        let entries = db.list_audit_entries().unwrap();
+        let supplier = KeyPair::generate();
+
+        // A rewritten log re-signed with the forger's own key is internally
+        // consistent, so only the trusted key set catches it
+        let mut rewritten = entries.clone();
+        rewritten[2].entity_id = "c-2".to_string();
+        let record = AuditRecord {
+            command: rewritten[2].command.clone(),
+            entity_type: rewritten[2].entity_type.clone(),
+            entity_id: rewritten[2].entity_id.clone(),
+            before_hash: rewritten[2].before_hash.clone(),
+            after_hash: rewritten[2].after_hash.clone(),
+            actor: rewritten[2].actor.clone(),
+        };
+        rewritten[2].entry_hash = entry_hash(&rewritten[2].prev_hash, 3, &record, &rewritten[2].recorded_at);
+        let forger = KeyPair::generate();
+        let forged = [SignedAuditHead {
+            seq: 3,
+            entry_hash: rewritten[2].entry_hash.clone(),
+            public_key: forger.public_key().to_hex(),
+            signature: forger.sign_hex_in(Domain::AUDIT_HEAD, &head_payload(3, &rewritten[2].entry_hash)).unwrap(),
+            signed_at: Utc::now(),
+        }];
+        assert!(verify_chain(&rewritten, &forged, &[forger.public_key()]).valid);
+
+        let result = verify_chain(&rewritten, &forged, &[supplier.public_key()]);
+        assert!(!result.valid);
+        assert_eq!(result.first_invalid_seq, Some(3));
+    }
+}
@@ -0,0 +1,757 @@
+//! Versioned schema migrations
+//!
+//! The schema version lives in `PRAGMA user_version`. Each migration runs in
+//! its own transaction and bumps the version on success, so an interrupted
+//! upgrade resumes from the last completed step. Databases created before
+//! versioning report version 0; every migration is written to be safe on a
+//! database that already has some of its tables or columns.
+
+use anyhow::{bail, Context, Result};
+use rusqlite::Connection;
+use std::path::{Path, PathBuf};
+
+/// One ordered schema change
+pub struct Migration {
+    pub version: u32,
+    pub description: &'static str,
+    pub up: fn(&Connection) -> Result<()>,
+}
+
+pub const MIGRATIONS: &[Migration] = &[
+    Migration { version: 1, description: "initial schema", up: initial_schema },
+    Migration { version: 2, description: "proof cache", up: proof_cache },
+    Migration { version: 3, description: "frozen commitment leaves", up: commitment_leaves },
+    Migration { version: 4, description: "claim versions", up: claim_versions },
+    Migration { version: 5, description: "reference join tables", up: reference_tables },
+    Migration { version: 6, description: "passphrase vault", up: vault },
+    Migration { version: 7, description: "per-evidence keys", up: evidence_keys },
+    Migration { version: 8, description: "audit log", up: audit_log },
+    Migration { version: 9, description: "keypair lifecycle", up: keypair_lifecycle },
+    Migration { version: 10, description: "recovery seed", up: recovery_seed },
+    Migration { version: 11, description: "signed revocations", up: signed_revocations },
+    Migration { version: 12, description: "sealed fields marker", up: sealed_fields_marker },
+];
+
+/// Schema version this build expects
+#[cfg(test)]
+pub fn latest_version() -> u32 {
+    MIGRATIONS.last().map(|m| m.version).unwrap_or(0)
+}
+
+pub fn user_version(conn: &Connection) -> Result<u32> {
+    Ok(conn.query_row("PRAGMA user_version", [], |row| row.get(0))?)
+}
+
+/// Brings the database up to the latest schema. When `db_path` is given and
+/// an existing database needs upgrading, it is first copied to
+/// `<db_path>.v<version>.bak`.
+pub fn run(conn: &Connection, db_path: Option<&Path>) -> Result<()> {
+    apply(conn, MIGRATIONS, db_path)
+}
+
+fn apply(conn: &Connection, migrations: &[Migration], db_path: Option<&Path>) -> Result<()> {
+    let current = user_version(conn)?;
+    let latest = migrations.last().map(|m| m.version).unwrap_or(0);
+    if current > latest {
+        bail!(
+            "Database schema version {} is newer than this Edge Agent supports ({})",
+            current,
+            latest
+        );
+    }
+    if current == latest {
+        return Ok(());
+    }
+
+    if let Some(path) = db_path {
+        if has_tables(conn)? {
+            backup(conn, &backup_path(path, current))?;
+        }
+    }
+
+    for migration in migrations.iter().filter(|m| m.version > current) {
+        let tx = conn.unchecked_transaction()?;
+        (migration.up)(conn).with_context(|| {
+            format!("Migration {} ({}) failed", migration.version, migration.description)
+        })?;
+        conn.pragma_update(None, "user_version", migration.version)?;
+        tx.commit()?;
+    }
+
+    Ok(())
+}
+
+pub fn backup_path(db_path: &Path, version: u32) -> PathBuf {
+    let mut name = db_path.as_os_str().to_owned();
+    name.push(format!(".v{}.bak", version));
+    PathBuf::from(name)
+}
+
+fn backup(conn: &Connection, target: &Path) -> Result<()> {
+    if target.exists() {
+        std::fs::remove_file(target)?;
+    }
+    let target_str = target.to_str().context("Backup path is not valid UTF-8")?;
+    conn.execute("VACUUM INTO ?1", [target_str])
+        .with_context(|| format!("Failed to back up database to {}", target.display()))?;
+    Ok(())
+}
+
+fn has_tables(conn: &Connection) -> Result<bool> {
+    let count: i64 = conn.query_row(
+        "SELECT COUNT(*) FROM sqlite_master WHERE type = 'table'",
+        [],
+        |row| row.get(0),
+    )?;
+    Ok(count > 0)
+}
+
+fn add_column_if_missing(conn: &Connection, table: &str, column: &str, definition: &str) -> Result<()> {
+    let mut stmt = conn.prepare(&format!("PRAGMA table_info({})", table))?;
+    let exists = stmt
+        .query_map([], |row| row.get::<_, String>(1))?
+        .filter_map(|name| name.ok())
+        .any(|name| name == column);
+    if !exists {
+        conn.execute(
+            &format!("ALTER TABLE {} ADD COLUMN {} {}", table, column, definition),
+            [],
+        )?;
+    }
+    Ok(())
+}
+
+fn initial_schema(conn: &Connection) -> Result<()> {
+    conn.execute_batch(
+        r#"
+        -- Evidence table
+        CREATE TABLE IF NOT EXISTS evidence (
+            id TEXT PRIMARY KEY,
+            evidence_type TEXT NOT NULL,
+            original_filename TEXT,
+            mime_type TEXT,
+            content_hash TEXT NOT NULL,
+            extracted_text TEXT,
+            issuer_name TEXT,
+            issuer_type TEXT,
+            valid_from TEXT,
+            valid_until TEXT,
+            raw_content BLOB,
+            created_at TEXT NOT NULL
+        );
+
+        -- Claims table
+        CREATE TABLE IF NOT EXISTS claims (
+            id TEXT PRIMARY KEY,
+            claim_type TEXT NOT NULL,
+            value TEXT NOT NULL,
+            unit TEXT NOT NULL,
+            product_id TEXT NOT NULL,
+            evidence_ids TEXT NOT NULL,
+            confidence REAL,
+            verified INTEGER NOT NULL DEFAULT 0,
+            metadata TEXT NOT NULL DEFAULT '{}',
+            created_at TEXT NOT NULL,
+            updated_at TEXT NOT NULL
+        );
+
+        -- Commitments table
+        CREATE TABLE IF NOT EXISTS commitments (
+            id TEXT PRIMARY KEY,
+            root TEXT NOT NULL,
+            claim_count INTEGER NOT NULL,
+            claim_ids TEXT NOT NULL,
+            public_key TEXT NOT NULL,
+            signature TEXT NOT NULL,
+            valid_from TEXT,
+            valid_until TEXT,
+            revoked INTEGER NOT NULL DEFAULT 0,
+            revoked_at TEXT,
+            revoked_reason TEXT,
+            created_at TEXT NOT NULL
+        );
+
+        -- Keypairs table
+        CREATE TABLE IF NOT EXISTS keypairs (
+            id TEXT PRIMARY KEY,
+            public_key TEXT NOT NULL UNIQUE,
+            secret_key_encrypted TEXT NOT NULL,
+            created_at TEXT NOT NULL,
+            is_active INTEGER NOT NULL DEFAULT 0
+        );
+
+        -- Settings table
+        CREATE TABLE IF NOT EXISTS settings (
+            key TEXT PRIMARY KEY,
+            value TEXT NOT NULL
+        );
+
+        -- Indexes
+        CREATE INDEX IF NOT EXISTS idx_claims_product ON claims(product_id);
+        CREATE INDEX IF NOT EXISTS idx_claims_type ON claims(claim_type);
+        CREATE INDEX IF NOT EXISTS idx_commitments_root ON commitments(root);
+        "#,
+    )?;
+    Ok(())
+}
+
+fn proof_cache(conn: &Connection) -> Result<()> {
+    conn.execute_batch(
+        r#"
+        -- Proof cache (proof bytes only; nonces are never reused)
+        CREATE TABLE IF NOT EXISTS proof_cache (
+            cache_key TEXT PRIMARY KEY,
+            commitment_id TEXT NOT NULL,
+            predicate_id TEXT NOT NULL,
+            public_inputs TEXT NOT NULL,
+            proof TEXT NOT NULL,
+            created_at TEXT NOT NULL,
+            expires_at TEXT NOT NULL
+        );
+
+        CREATE INDEX IF NOT EXISTS idx_proof_cache_commitment ON proof_cache(commitment_id);
+        "#,
+    )?;
+    Ok(())
+}
+
+fn commitment_leaves(conn: &Connection) -> Result<()> {
+    conn.execute_batch(
+        r#"
+        -- Frozen leaves of each commitment, in tree order
+        CREATE TABLE IF NOT EXISTS commitment_leaves (
+            commitment_id TEXT NOT NULL,
+            position INTEGER NOT NULL,
+            claim_id TEXT NOT NULL,
+            leaf_hash TEXT NOT NULL,
+            canonical_claim TEXT NOT NULL,
+            PRIMARY KEY (commitment_id, position)
+        );
+        "#,
+    )?;
+    Ok(())
+}
+
+/// Recreated after the vault seals legacy plaintext history in place
+pub(super) const CLAIM_VERSIONS_NO_UPDATE: &str = r#"
+    CREATE TRIGGER IF NOT EXISTS claim_versions_no_update
+    BEFORE UPDATE ON claim_versions
+    BEGIN
+        SELECT RAISE(ABORT, 'claim versions are immutable');
+    END;
+"#;
+
+fn claim_versions(conn: &Connection) -> Result<()> {
+    add_column_if_missing(conn, "claims", "version", "INTEGER NOT NULL DEFAULT 1")?;
+    add_column_if_missing(conn, "claims", "retired_at", "TEXT")?;
+    add_column_if_missing(conn, "commitment_leaves", "claim_version", "INTEGER NOT NULL DEFAULT 1")?;
+    conn.execute_batch(
+        r#"
+        -- Append-only claim history; every edit or retirement adds a row
+        CREATE TABLE IF NOT EXISTS claim_versions (
+            claim_id TEXT NOT NULL,
+            version INTEGER NOT NULL,
+            change TEXT NOT NULL,
+            claim_type TEXT NOT NULL,
+            value TEXT NOT NULL,
+            unit TEXT NOT NULL,
+            product_id TEXT NOT NULL,
+            evidence_ids TEXT NOT NULL,
+            confidence REAL,
+            verified INTEGER NOT NULL,
+            metadata TEXT NOT NULL,
+            created_at TEXT NOT NULL,
+            recorded_at TEXT NOT NULL,
+            PRIMARY KEY (claim_id, version)
+        );
+
+        CREATE TRIGGER IF NOT EXISTS claim_versions_no_delete
+        BEFORE DELETE ON claim_versions
+        BEGIN
+            SELECT RAISE(ABORT, 'claim versions are immutable');
+        END;
+
+        -- Existing claims start their history at their current version
+        INSERT INTO claim_versions (claim_id, version, change, claim_type, value, unit,
+            product_id, evidence_ids, confidence, verified, metadata, created_at, recorded_at)
+        SELECT id, version, 'created', claim_type, value, unit, product_id, evidence_ids,
+            confidence, verified, metadata, created_at, updated_at
+        FROM claims
+        WHERE id NOT IN (SELECT claim_id FROM claim_versions);
+        "#,
+    )?;
+    conn.execute_batch(CLAIM_VERSIONS_NO_UPDATE)?;
+    Ok(())
+}
+
+fn reference_tables(conn: &Connection) -> Result<()> {
+    conn.execute_batch(
+        r#"
+        -- Evidence backing each claim. Evidence in use cannot be deleted.
+        CREATE TABLE IF NOT EXISTS claim_evidence (
+            claim_id TEXT NOT NULL REFERENCES claims(id) ON DELETE CASCADE,
+            evidence_id TEXT NOT NULL REFERENCES evidence(id) ON DELETE RESTRICT,
+            PRIMARY KEY (claim_id, evidence_id)
+        );
+
+        -- Claims included in each commitment. Committed claims cannot be deleted;
+        -- removing a commitment releases its claims.
+        CREATE TABLE IF NOT EXISTS commitment_claims (
+            commitment_id TEXT NOT NULL REFERENCES commitments(id) ON DELETE CASCADE,
+            claim_id TEXT NOT NULL REFERENCES claims(id) ON DELETE RESTRICT,
+            position INTEGER NOT NULL,
+            PRIMARY KEY (commitment_id, claim_id)
+        );
+
+        CREATE INDEX IF NOT EXISTS idx_claim_evidence_evidence ON claim_evidence(evidence_id);
+        CREATE INDEX IF NOT EXISTS idx_commitment_claims_claim ON commitment_claims(claim_id);
+
+        -- Move the JSON reference lists into the join tables; references to
+        -- rows that no longer exist cannot be linked and are left out
+        INSERT OR IGNORE INTO claim_evidence (claim_id, evidence_id)
+        SELECT claims.id, refs.value
+        FROM claims, json_each(claims.evidence_ids) AS refs
+        WHERE refs.value IN (SELECT id FROM evidence);
+
+        INSERT OR IGNORE INTO commitment_claims (commitment_id, claim_id, position)
+        SELECT commitments.id, refs.value, refs.key
+        FROM commitments, json_each(commitments.claim_ids) AS refs
+        WHERE refs.value IN (SELECT id FROM claims);
+        "#,
+    )?;
+    Ok(())
+}
+
+fn vault(conn: &Connection) -> Result<()> {
+    conn.execute_batch(
+        r#"
+        -- Single-row vault header: Argon2id parameters and the wrapped data key
+        CREATE TABLE IF NOT EXISTS vault (
+            id INTEGER PRIMARY KEY CHECK (id = 1),
+            kdf TEXT NOT NULL,
+            salt BLOB NOT NULL,
+            m_cost INTEGER NOT NULL,
+            t_cost INTEGER NOT NULL,
+            p_cost INTEGER NOT NULL,
+            wrapped_key BLOB NOT NULL,
+            created_at TEXT NOT NULL,
+            updated_at TEXT NOT NULL
+        );
+        "#,
+    )?;
+    Ok(())
+}
+
+fn evidence_keys(conn: &Connection) -> Result<()> {
+    // Each evidence record's key, wrapped by the vault key; cleared to shred
+    add_column_if_missing(conn, "evidence", "wrapped_key", "BLOB")?;
+    add_column_if_missing(conn, "evidence", "shredded_at", "TEXT")?;
+    Ok(())
+}
+
+fn audit_log(conn: &Connection) -> Result<()> {
+    conn.execute_batch(
+        r#"
+        -- Hash-chained log of every mutation; append-only
+        CREATE TABLE IF NOT EXISTS audit_log (
+            seq INTEGER PRIMARY KEY,
+            command TEXT NOT NULL,
+            entity_type TEXT NOT NULL,
+            entity_id TEXT NOT NULL,
+            before_hash TEXT,
+            after_hash TEXT,
+            actor TEXT NOT NULL,
+            recorded_at TEXT NOT NULL,
+            prev_hash TEXT NOT NULL,
+            entry_hash TEXT NOT NULL UNIQUE
+        );
+
+        CREATE INDEX IF NOT EXISTS idx_audit_log_entity ON audit_log(entity_type, entity_id);
+
+        -- Chain heads signed with the active keypair
+        CREATE TABLE IF NOT EXISTS audit_heads (
+            seq INTEGER PRIMARY KEY REFERENCES audit_log(seq),
+            entry_hash TEXT NOT NULL,
+            public_key TEXT NOT NULL,
+            signature TEXT NOT NULL,
+            signed_at TEXT NOT NULL
+        );
+
+        CREATE TRIGGER IF NOT EXISTS audit_log_no_update
+        BEFORE UPDATE ON audit_log
+        BEGIN
+            SELECT RAISE(ABORT, 'audit log is append-only');
+        END;
+
+        CREATE TRIGGER IF NOT EXISTS audit_log_no_delete
+        BEFORE DELETE ON audit_log
+        BEGIN
+            SELECT RAISE(ABORT, 'audit log is append-only');
+        END;
+
+        CREATE TRIGGER IF NOT EXISTS audit_heads_no_update
+        BEFORE UPDATE ON audit_heads
+        BEGIN
+            SELECT RAISE(ABORT, 'audit log is append-only');
+        END;
+
+        CREATE TRIGGER IF NOT EXISTS audit_heads_no_delete
+        BEFORE DELETE ON audit_heads
+        BEGIN
+            SELECT RAISE(ABORT, 'audit log is append-only');
+        END;
+        "#,
+    )?;
+    Ok(())
+}
+
+fn keypair_lifecycle(conn: &Connection) -> Result<()> {
+    add_column_if_missing(conn, "keypairs", "retired_at", "TEXT")?;
+    add_column_if_missing(conn, "keypairs", "retired_reason", "TEXT")?;
+    add_column_if_missing(conn, "keypairs", "compromised", "INTEGER NOT NULL DEFAULT 0")?;
+    conn.execute_batch(
+        r#"
+        -- Certificates linking each rotated key to its successor; a key has at
+        -- most one predecessor and one successor
+        CREATE TABLE IF NOT EXISTS key_rotations (
+            new_key_id TEXT PRIMARY KEY REFERENCES keypairs(id),
+            previous_key_id TEXT NOT NULL UNIQUE REFERENCES keypairs(id),
+            previous_key TEXT NOT NULL,
+            new_key TEXT NOT NULL UNIQUE,
+            issued_at INTEGER NOT NULL,
+            signature TEXT NOT NULL,
+            new_key_signature TEXT NOT NULL
+        );
+        "#,
+    )?;
+    Ok(())
+}
+
+fn recovery_seed(conn: &Connection) -> Result<()> {
+    // SLIP-0010 path of keys derived from the recovery phrase
+    add_column_if_missing(conn, "keypairs", "derivation_path", "TEXT")?;
+    conn.execute_batch(
+        r#"
+        -- Single-row BIP-39 seed, sealed with the vault data key
+        CREATE TABLE IF NOT EXISTS recovery_seed (
+            id INTEGER PRIMARY KEY CHECK (id = 1),
+            sealed_seed TEXT NOT NULL,
+            fingerprint TEXT NOT NULL,
+            created_at TEXT NOT NULL
+        );
+        "#,
+    )?;
+    Ok(())
+}
+
+fn signed_revocations(conn: &Connection) -> Result<()> {
+    // SignedMessage<Revocation> as JSON; NULL if revoked without a key
+    add_column_if_missing(conn, "commitments", "revocation", "TEXT")
+}
+
+fn sealed_fields_marker(conn: &Connection) -> Result<()> {
+    // Set once legacy plaintext columns are sealed; plaintext found after
+    // that is rejected rather than read or sealed
+    add_column_if_missing(conn, "vault", "fields_sealed_at", "TEXT")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn open() -> Connection {
+        let conn = Connection::open_in_memory().unwrap();
+        conn.execute_batch("PRAGMA foreign_keys = ON;").unwrap();
+        conn
+    }
+
+    /// A database as shipped at `version`, holding one evidence file, one
+    /// claim citing it and one commitment over the claim.
+    fn fixture(version: u32) -> Connection {
+        let conn = open();
+        apply(&conn, &MIGRATIONS[..version.max(1) as usize], None).unwrap();
+        conn.execute_batch(
+            r#"
+            INSERT INTO evidence (id, evidence_type, content_hash, created_at)
+            VALUES ('ev-1', 'lab_report', 'aa', '2025-01-01T00:00:00Z');
+            INSERT INTO claims (id, claim_type, value, unit, product_id, evidence_ids,
+                verified, metadata, created_at, updated_at)
+            VALUES ('claim-1', 'recycled_content', '25', 'percent', 'battery-001', '["ev-1"]',
+                1, '{}', '2025-01-01T00:00:00Z', '2025-01-01T00:00:00Z');
+            INSERT INTO commitments (id, root, claim_count, claim_ids, public_key, signature,
+                created_at)
+            VALUES ('commitment-1', 'bb', 1, '["claim-1"]', 'pk', 'sig', '2025-01-01T00:00:00Z');
+            "#,
+        )
+        .unwrap();
+        if version >= 4 {
+            conn.execute_batch(
+                r#"
+                INSERT INTO claim_versions (claim_id, version, change, claim_type, value, unit,
+                    product_id, evidence_ids, verified, metadata, created_at, recorded_at)
+                SELECT id, 1, 'created', claim_type, value, unit, product_id, evidence_ids,
+                    verified, metadata, created_at, updated_at FROM claims;
+                "#,
+            )
+            .unwrap();
+        }
+        if version >= 5 {
+            conn.execute_batch(
+                r#"
+                INSERT INTO claim_evidence (claim_id, evidence_id) VALUES ('claim-1', 'ev-1');
+                INSERT INTO commitment_claims (commitment_id, claim_id, position)
+                VALUES ('commitment-1', 'claim-1', 0);
+                "#,
+            )
+            .unwrap();
+        }
+        // Databases from before versioning never set user_version
+        conn.pragma_update(None, "user_version", version).unwrap();
+        conn
+    }
+
+    /// The schema batch the first release ran on every start, before
+    /// migrations existed; kept verbatim so later edits to `initial_schema`
+    /// cannot hide an upgrade break
+    const BASELINE_SCHEMA: &str = r#"
+        CREATE TABLE IF NOT EXISTS evidence (
+            id TEXT PRIMARY KEY,
+            evidence_type TEXT NOT NULL,
+            original_filename TEXT,
+            mime_type TEXT,
+            content_hash TEXT NOT NULL,
+            extracted_text TEXT,
+            issuer_name TEXT,
+            issuer_type TEXT,
+            valid_from TEXT,
+            valid_until TEXT,
+            raw_content BLOB,
+            created_at TEXT NOT NULL
+        );
+
+        CREATE TABLE IF NOT EXISTS claims (
+            id TEXT PRIMARY KEY,
+            claim_type TEXT NOT NULL,
+            value TEXT NOT NULL,
+            unit TEXT NOT NULL,
+            product_id TEXT NOT NULL,
+            evidence_ids TEXT NOT NULL,
+            confidence REAL,
+            verified INTEGER NOT NULL DEFAULT 0,
+            metadata TEXT NOT NULL DEFAULT '{}',
+            created_at TEXT NOT NULL,
+            updated_at TEXT NOT NULL
+        );
+
+        CREATE TABLE IF NOT EXISTS commitments (
+            id TEXT PRIMARY KEY,
+            root TEXT NOT NULL,
+            claim_count INTEGER NOT NULL,
+            claim_ids TEXT NOT NULL,
+            public_key TEXT NOT NULL,
+            signature TEXT NOT NULL,
+            valid_from TEXT,
+            valid_until TEXT,
+            revoked INTEGER NOT NULL DEFAULT 0,
+            revoked_at TEXT,
+            revoked_reason TEXT,
+            created_at TEXT NOT NULL
+        );
+
+        CREATE TABLE IF NOT EXISTS keypairs (
+            id TEXT PRIMARY KEY,
+            public_key TEXT NOT NULL UNIQUE,
+            secret_key_encrypted TEXT NOT NULL,
+            created_at TEXT NOT NULL,
+            is_active INTEGER NOT NULL DEFAULT 0
+        );
+
+        CREATE TABLE IF NOT EXISTS settings (
+            key TEXT PRIMARY KEY,
+            value TEXT NOT NULL
+        );
+
+        CREATE INDEX IF NOT EXISTS idx_claims_product ON claims(product_id);
+        CREATE INDEX IF NOT EXISTS idx_claims_type ON claims(claim_type);
+        CREATE INDEX IF NOT EXISTS idx_commitments_root ON commitments(root);
+    "#;
+
+    fn columns(conn: &Connection) -> Vec<(String, Vec<String>)> {
+        let mut stmt = conn
+            .prepare("SELECT name FROM sqlite_master WHERE type = 'table' ORDER BY name")
+            .unwrap();
+        let tables: Vec<String> = stmt.query_map([], |row| row.get(0)).unwrap().map(|t| t.unwrap()).collect();
+        tables
+            .into_iter()
+            .map(|table| {
+                let mut stmt = conn.prepare(&format!("PRAGMA table_info({})", table)).unwrap();
+                let mut names: Vec<String> =
+                    stmt.query_map([], |row| row.get(1)).unwrap().map(|c| c.unwrap()).collect();
+                names.sort();
+                (table, names)
+            })
+            .collect()
+    }
+
+    fn count(conn: &Connection, sql: &str) -> i64 {
+        conn.query_row(sql, [], |row| row.get(0)).unwrap()
+    }
+
+    #[test]
+    fn test_fresh_database_reaches_latest() {
+        let conn = open();
+        run(&conn, None).unwrap();
+        assert_eq!(user_version(&conn).unwrap(), latest_version());
+
+        // Running again is a no-op
+        run(&conn, None).unwrap();
+        assert_eq!(user_version(&conn).unwrap(), latest_version());
+    }
+
+    #[test]
+    fn test_upgrade_from_every_released_version() {
+        let fresh = open();
+        run(&fresh, None).unwrap();
+        let expected = columns(&fresh);
+
+        for version in 0..latest_version() {
+            let conn = fixture(version);
+            run(&conn, None).unwrap();
+
+            assert_eq!(user_version(&conn).unwrap(), latest_version(), "from v{}", version);
+            assert_eq!(columns(&conn), expected, "from v{}", version);
+            assert_eq!(count(&conn, "SELECT COUNT(*) FROM claim_versions WHERE claim_id = 'claim-1'"), 1);
+            assert_eq!(count(&conn, "SELECT COUNT(*) FROM claim_evidence WHERE evidence_id = 'ev-1'"), 1);
+            assert_eq!(count(&conn, "SELECT COUNT(*) FROM commitment_claims WHERE claim_id = 'claim-1'"), 1);
+        }
+    }
+
+    #[test]
+    fn test_upgrade_from_baseline_schema() {
+        let fresh = open();
+        run(&fresh, None).unwrap();
+
+        let conn = open();
+        conn.execute_batch(BASELINE_SCHEMA).unwrap();
+        conn.execute_batch(
+            r#"
+            INSERT INTO evidence (id, evidence_type, original_filename, content_hash, raw_content, created_at)
+            VALUES ('ev-1', 'lab_report', 'lab.pdf', 'aa', X'01020304', '2025-01-01T00:00:00Z'),
+                   ('ev-2', 'certificate', 'iso.pdf', 'cc', NULL, '2025-01-02T00:00:00Z');
+            INSERT INTO claims (id, claim_type, value, unit, product_id, evidence_ids,
+                confidence, verified, metadata, created_at, updated_at)
+            VALUES ('claim-1', 'recycled_content', '25', 'percent', 'battery-001', '["ev-1","ev-2"]',
+                    0.9, 1, '{"page":3}', '2025-01-03T00:00:00Z', '2025-01-03T00:00:00Z'),
+                   ('claim-2', 'carbon_footprint', '12.5', 'kg_co2e', 'battery-001', '["ev-2","ev-gone"]',
+                    NULL, 0, '{}', '2025-01-04T00:00:00Z', '2025-01-05T00:00:00Z');
+            INSERT INTO commitments (id, root, claim_count, claim_ids, public_key, signature,
+                valid_until, created_at)
+            VALUES ('commitment-1', 'bb', 2, '["claim-2","claim-1"]', 'pk', 'sig',
+                    '2025-02-01T00:00:00Z', '2025-01-06T00:00:00Z');
+            INSERT INTO keypairs (id, public_key, secret_key_encrypted, created_at, is_active)
+            VALUES ('key-1', 'pk', 'sk', '2025-01-01T00:00:00Z', 1);
+            INSERT INTO settings (key, value) VALUES ('supplier_id', 'supplier-7');
+            "#,
+        )
+        .unwrap();
+        assert_eq!(user_version(&conn).unwrap(), 0);
+
+        run(&conn, None).unwrap();
+        assert_eq!(user_version(&conn).unwrap(), latest_version());
+        assert_eq!(columns(&conn), columns(&fresh));
+
+        // Original rows are untouched
+        let claim: (String, String, String, Option<f64>, String) = conn
+            .query_row(
+                "SELECT value, unit, evidence_ids, confidence, metadata FROM claims WHERE id = 'claim-1'",
+                [],
+                |row| Ok((row.get(0)?, row.get(1)?, row.get(2)?, row.get(3)?, row.get(4)?)),
+            )
+            .unwrap();
+        assert_eq!(claim, ("25".into(), "percent".into(), r#"["ev-1","ev-2"]"#.into(), Some(0.9), r#"{"page":3}"#.into()));
+        let raw: Vec<u8> = conn.query_row("SELECT raw_content FROM evidence WHERE id = 'ev-1'", [], |row| row.get(0)).unwrap();
+        assert_eq!(raw, vec![1, 2, 3, 4]);
+        let claim_ids: String = conn
+            .query_row("SELECT claim_ids FROM commitments WHERE id = 'commitment-1'", [], |row| row.get(0))
+            .unwrap();
+        assert_eq!(claim_ids, r#"["claim-2","claim-1"]"#);
+        assert_eq!(count(&conn, "SELECT COUNT(*) FROM keypairs WHERE is_active = 1"), 1);
+        assert_eq!(count(&conn, "SELECT COUNT(*) FROM settings WHERE value = 'supplier-7'"), 1);
+
+        // JSON reference lists are linked, skipping evidence that no longer exists
+        let mut stmt = conn.prepare("SELECT claim_id, evidence_id FROM claim_evidence ORDER BY claim_id, evidence_id").unwrap();
+        let links: Vec<(String, String)> = stmt.query_map([], |row| Ok((row.get(0)?, row.get(1)?))).unwrap().map(|l| l.unwrap()).collect();
+        assert_eq!(links, vec![
+            ("claim-1".to_string(), "ev-1".to_string()),
+            ("claim-1".to_string(), "ev-2".to_string()),
+            ("claim-2".to_string(), "ev-2".to_string()),
+        ]);
+        let mut stmt = conn.prepare("SELECT claim_id, position FROM commitment_claims ORDER BY position").unwrap();
+        let members: Vec<(String, i64)> = stmt.query_map([], |row| Ok((row.get(0)?, row.get(1)?))).unwrap().map(|m| m.unwrap()).collect();
+        assert_eq!(members, vec![("claim-2".to_string(), 0), ("claim-1".to_string(), 1)]);
+
+        // Every claim starts its history at its baseline state
+        let mut stmt = conn.prepare("SELECT claim_id, version, value, recorded_at FROM claim_versions ORDER BY claim_id").unwrap();
+        let history: Vec<(String, i64, String, String)> =
+            stmt.query_map([], |row| Ok((row.get(0)?, row.get(1)?, row.get(2)?, row.get(3)?))).unwrap().map(|h| h.unwrap()).collect();
+        assert_eq!(history, vec![
+            ("claim-1".to_string(), 1, "25".to_string(), "2025-01-03T00:00:00Z".to_string()),
+            ("claim-2".to_string(), 1, "12.5".to_string(), "2025-01-05T00:00:00Z".to_string()),
+        ]);
+    }
+
+    #[test]
+    fn test_unversioned_database_at_latest_schema() {
+        // Installs that ran the pre-versioning schema batch have every table
+        // but report version 0
+        let conn = fixture(latest_version());
+        conn.pragma_update(None, "user_version", 0).unwrap();
+        run(&conn, None).unwrap();
+        assert_eq!(user_version(&conn).unwrap(), latest_version());
+        assert_eq!(count(&conn, "SELECT COUNT(*) FROM claim_versions"), 1);
+    }
+
+    #[test]
+    fn test_newer_database_rejected() {
+        let conn = open();
+        conn.pragma_update(None, "user_version", latest_version() + 1).unwrap();
+        assert!(run(&conn, None).is_err());
+    }
+
+    #[test]
+    fn test_failed_migration_rolls_back() {
+        fn create_and_fail(conn: &Connection) -> Result<()> {
+            conn.execute_batch("CREATE TABLE partial (id TEXT);")?;
+            bail!("boom")
+        }
+        let migrations = [
+            Migration { version: 1, description: "initial schema", up: initial_schema },
+            Migration { version: 2, description: "broken", up: create_and_fail },
+        ];
+
+        let conn = open();
+        assert!(apply(&conn, &migrations, None).is_err());
+        assert_eq!(user_version(&conn).unwrap(), 1);
+        assert_eq!(count(&conn, "SELECT COUNT(*) FROM sqlite_master WHERE name = 'partial'"), 0);
+    }
+
+    #[test]
+    fn test_backup_before_upgrade() {
+        let dir = std::env::temp_dir().join(format!("edge-agent-migrations-{}", uuid::Uuid::new_v4()));
+        std::fs::create_dir_all(&dir).unwrap();
+        let db_path = dir.join("edge-agent.db");
+
+        let conn = Connection::open(&db_path).unwrap();
+        apply(&conn, &MIGRATIONS[..1], Some(&db_path)).unwrap();
+        // A brand-new database has nothing to back up
+        assert!(!backup_path(&db_path, 0).exists());
+
+        run(&conn, Some(&db_path)).unwrap();
+        let backup = Connection::open(backup_path(&db_path, 1)).unwrap();
+        assert_eq!(user_version(&backup).unwrap(), 1);
+
+        drop(backup);
+        drop(conn);
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+}
@@ -1,16 +1,32 @@
 //! SQLite storage for the Edge Agent
 //!
 //! Stores evidence, claims, commitments, and keys locally with encryption.
+//! Extracted text, claim values and metadata, and frozen commitment leaves
+//! are sealed per column with the vault data key, bound to their row, so only
+//! identifiers, types, units and product IDs are searchable in the clear.
 
 use anyhow::{Context, Result};
-use aes_gcm::{Aes256Gcm, KeyInit, Nonce};
-use aes_gcm::aead::{Aead, rand_core::RngCore};
-use rand::rngs::OsRng;
 use chrono::{DateTime, Utc};
-use rusqlite::{params, Connection};
+use crypto::domain::push_field;
+use crypto::{Domain, RotationCertificate, Signable, SignedMessage};
+use rusqlite::{params, Connection, OpenFlags};
 use serde::{Deserialize, Serialize};
+use std::cell::Cell;
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
 use uuid::Uuid;
 
+pub mod audit;
+mod migrations;
+mod pool;
+pub mod vault;
+
+pub use pool::{DbPool, DEFAULT_READERS};
+pub use audit::{AuditEntry, AuditRecord, SignedAuditHead};
+pub use vault::{Vault, VaultRecord};
+use vault::DataKey;
+
 /// Evidence record - source documents
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct Evidence {
@@ -25,6 +41,8 @@ pub struct Evidence {
     pub valid_from: Option<DateTime<Utc>>,
     pub valid_until: Option<DateTime<Utc>>,
     pub created_at: DateTime<Utc>,
+    /// When the content was crypto-shredded; the hash is kept for audit
+    pub shredded_at: Option<DateTime<Utc>>,
 }
 
 /// Claim record - extracted data points
@@ -43,6 +61,87 @@ pub struct Claim {
     pub updated_at: DateTime<Utc>,
 }
 
+/// Kind of change recorded by a claim version
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ClaimChange {
+    Created,
+    Updated,
+    Retired,
+}
+
+impl ClaimChange {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            ClaimChange::Created => "created",
+            ClaimChange::Updated => "updated",
+            ClaimChange::Retired => "retired",
+        }
+    }
+
+    fn parse(value: &str) -> Self {
+        match value {
+            "retired" => ClaimChange::Retired,
+            "updated" => ClaimChange::Updated,
+            _ => ClaimChange::Created,
+        }
+    }
+}
+
+/// Immutable snapshot of a claim at one point in its history
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ClaimVersion {
+    pub claim_id: String,
+    pub version: u32,
+    pub change: ClaimChange,
+    pub claim: Claim,
+    pub recorded_at: DateTime<Utc>,
+}
+
+/// Current version and retirement state of a claim
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ClaimStatus {
+    pub version: u32,
+    pub retired_at: Option<DateTime<Utc>>,
+}
+
+/// Lifecycle stage of a carbon footprint claim, in the order the
+/// `carbon_footprint_lifecycle_v1` circuit expects them
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum LifecycleStage {
+    RawMaterials,
+    Manufacturing,
+    Transport,
+    Use,
+    EndOfLife,
+}
+
+impl LifecycleStage {
+    pub const ALL: [LifecycleStage; 5] = [
+        LifecycleStage::RawMaterials,
+        LifecycleStage::Manufacturing,
+        LifecycleStage::Transport,
+        LifecycleStage::Use,
+        LifecycleStage::EndOfLife,
+    ];
+
+    /// Claim type under which the stage's footprint is committed
+    pub fn claim_type(&self) -> &'static str {
+        match self {
+            LifecycleStage::RawMaterials => "carbon_footprint_raw_materials",
+            LifecycleStage::Manufacturing => "carbon_footprint_manufacturing",
+            LifecycleStage::Transport => "carbon_footprint_transport",
+            LifecycleStage::Use => "carbon_footprint_use",
+            LifecycleStage::EndOfLife => "carbon_footprint_end_of_life",
+        }
+    }
+
+    pub fn from_claim_type(claim_type: &str) -> Option<Self> {
+        Self::ALL.into_iter().find(|stage| stage.claim_type() == claim_type)
+    }
+}
+
 /// Commitment record - Merkle root of claims
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct Commitment {
@@ -58,6 +157,51 @@ pub struct Commitment {
     pub revoked_at: Option<DateTime<Utc>>,
     pub revoked_reason: Option<String>,
     pub created_at: DateTime<Utc>,
+    /// The supplier's signed revocation; `None` unless revoked with a key
+    #[serde(default)]
+    pub revocation: Option<SignedMessage<Revocation>>,
+}
+
+/// Statement that a commitment is withdrawn, signed in [`Domain::REVOCATION`]
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Revocation {
+    pub commitment_id: String,
+    pub root: String,
+    pub reason: String,
+    pub revoked_at: DateTime<Utc>,
+}
+
+impl Signable for Revocation {
+    const DOMAIN: Domain = Domain::REVOCATION;
+
+    fn signing_payload(&self) -> crypto::Result<Vec<u8>> {
+        let mut payload = Vec::new();
+        push_field(&mut payload, self.commitment_id.as_bytes());
+        push_field(&mut payload, self.root.to_ascii_lowercase().as_bytes());
+        push_field(&mut payload, self.reason.as_bytes());
+        push_field(&mut payload, self.revoked_at.to_rfc3339().as_bytes());
+        Ok(payload)
+    }
+}
+
+/// One frozen leaf of a commitment: the claim exactly as it was committed
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct CommitmentLeaf {
+    pub commitment_id: String,
+    pub position: usize,
+    pub claim_id: String,
+    /// Claim version that was committed
+    pub claim_version: u32,
+    pub leaf_hash: String,
+    /// Canonical JSON encoding of the claim at commitment time
+    pub canonical_claim: String,
+}
+
+/// Claims and commitments that depend on an evidence file
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct EvidenceDependents {
+    pub claim_ids: Vec<String>,
+    pub commitment_ids: Vec<String>,
 }
 
 /// Keypair stored locally
@@ -65,211 +209,222 @@ pub struct Commitment {
 pub struct StoredKeypair {
     pub id: String,
     pub public_key: String,
-    pub secret_key_encrypted: String, // Encrypted with user password
+    pub secret_key_encrypted: String, // Sealed with the vault data key
     pub created_at: DateTime<Utc>,
     pub is_active: bool,
+    /// When the key stopped signing; unset for keys replaced before
+    /// retirement was recorded
+    pub retired_at: Option<DateTime<Utc>>,
+    pub retired_reason: Option<String>,
+    /// Set when the secret key may have leaked
+    pub compromised: bool,
+    /// SLIP-0010 path for keys derived from the recovery phrase
+    pub derivation_path: Option<String>,
 }
 
-/// Database connection wrapper
-pub struct Database {
-    conn: Connection,
+/// Recovery seed, sealed with the vault data key
+#[derive(Debug, Clone)]
+pub struct RecoverySeedRecord {
+    pub sealed_seed: String,
+    /// Short public identifier of the seed, to check a re-entered phrase
+    pub fingerprint: String,
+    pub created_at: DateTime<Utc>,
 }
 
-const KEY_PREFIX_ENC: &str = "enc:";
-const KEY_PREFIX_RAW: &str = "raw:";
-const BLOB_PREFIX: &[u8; 4] = b"ENC1";
-
-fn load_master_key() -> Result<Option<[u8; 32]>> {
-    let key = match std::env::var("EDGE_AGENT_MASTER_KEY") {
-        Ok(v) => v,
-        Err(_) => return Ok(None),
-    };
-    let key_bytes = hex::decode(key.trim())
-        .context("EDGE_AGENT_MASTER_KEY must be 64 hex chars")?;
-    if key_bytes.len() != 32 {
-        return Err(anyhow::anyhow!("EDGE_AGENT_MASTER_KEY must be 32 bytes"));
-    }
-    let mut arr = [0u8; 32];
-    arr.copy_from_slice(&key_bytes);
-    Ok(Some(arr))
+/// Lifecycle state of a keypair
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum KeyStatus {
+    Active,
+    Retired,
+    Compromised,
 }
 
-fn encrypt_bytes(key: &[u8; 32], plaintext: &[u8]) -> Result<Vec<u8>> {
-    let cipher = Aes256Gcm::new_from_slice(key).context("Invalid master key")?;
-    let mut nonce_bytes = [0u8; 12];
-    OsRng.fill_bytes(&mut nonce_bytes);
-    let nonce = Nonce::from_slice(&nonce_bytes);
-    let mut out = Vec::new();
-    out.extend_from_slice(&nonce_bytes);
-    out.extend_from_slice(&cipher.encrypt(nonce, plaintext).context("Encryption failed")?);
-    Ok(out)
+impl StoredKeypair {
+    pub fn status(&self) -> KeyStatus {
+        if self.compromised {
+            KeyStatus::Compromised
+        } else if self.is_active {
+            KeyStatus::Active
+        } else {
+            KeyStatus::Retired
+        }
+    }
 }
 
-fn decrypt_bytes(key: &[u8; 32], ciphertext: &[u8]) -> Result<Vec<u8>> {
-    if ciphertext.len() < 12 {
-        return Err(anyhow::anyhow!("Ciphertext too short"));
-    }
-    let (nonce_bytes, data) = ciphertext.split_at(12);
-    let cipher = Aes256Gcm::new_from_slice(key).context("Invalid master key")?;
-    let nonce = Nonce::from_slice(nonce_bytes);
-    cipher.decrypt(nonce, data).context("Decryption failed")
+/// Cached proof for a previously proven public statement
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct CachedProof {
+    pub cache_key: String,
+    pub commitment_id: String,
+    pub predicate_id: String,
+    pub public_inputs: serde_json::Value,
+    pub proof: String,
+    pub created_at: DateTime<Utc>,
+    pub expires_at: DateTime<Utc>,
 }
 
-pub fn encode_secret_key(secret: &[u8]) -> Result<String> {
-    if let Some(key) = load_master_key()? {
-        let encrypted = encrypt_bytes(&key, secret)?;
-        Ok(format!("{}{}", KEY_PREFIX_ENC, base64::encode(encrypted)))
-    } else if std::env::var("ALLOW_PLAINTEXT_KEYS").as_deref() == Ok("true") {
-        Ok(format!("{}{}", KEY_PREFIX_RAW, hex::encode(secret)))
-    } else {
-        Err(anyhow::anyhow!("EDGE_AGENT_MASTER_KEY is required unless ALLOW_PLAINTEXT_KEYS=true"))
-    }
+/// Evidence with its content and text sealed under a fresh per-evidence key,
+/// ready to store; see [`Database::seal_evidence`]
+pub struct SealedEvidence<'a> {
+    evidence: &'a Evidence,
+    wrapped_key: Vec<u8>,
+    content: Option<Vec<u8>>,
+    extracted_text: Option<String>,
 }
 
-pub fn decode_secret_key(encoded: &str) -> Result<Vec<u8>> {
-    if let Some(rest) = encoded.strip_prefix(KEY_PREFIX_ENC) {
-        let key = load_master_key()?.ok_or_else(|| anyhow::anyhow!("EDGE_AGENT_MASTER_KEY required to decrypt"))?;
-        let bytes = base64::decode(rest).context("Invalid encrypted key data")?;
-        return decrypt_bytes(&key, &bytes);
-    }
+/// Database connection wrapper
+pub struct Database {
+    conn: Connection,
+    vault: Arc<Vault>,
+    /// Open [`Database::atomic`] scopes
+    atomic_depth: Cell<u32>,
+    /// A WAL checkpoint requested inside an atomic scope, run once it closes
+    checkpoint_due: Cell<bool>,
+}
 
-    if let Some(rest) = encoded.strip_prefix(KEY_PREFIX_RAW) {
-        if std::env::var("ALLOW_PLAINTEXT_KEYS").as_deref() != Ok("true") {
-            return Err(anyhow::anyhow!("Plaintext keys disabled"));
-        }
-        return Ok(hex::decode(rest).context("Invalid raw key hex")?);
-    }
+/// File name of the database inside the data directory
+pub const DB_FILE_NAME: &str = "edge-agent.db";
 
-    // Legacy hex storage
-    if std::env::var("ALLOW_PLAINTEXT_KEYS").as_deref() == Ok("true") {
-        return Ok(hex::decode(encoded).context("Invalid legacy key hex")?);
-    }
+/// How long a connection waits on another connection's lock
+const BUSY_TIMEOUT: std::time::Duration = std::time::Duration::from_secs(5);
 
-    Err(anyhow::anyhow!("Unrecognized key encoding"))
+/// Associated data binding a sealed column value to its column and row
+fn field_aad(column: &str, row_id: &str) -> String {
+    format!("{}/{}", column, row_id)
 }
 
-fn maybe_encrypt_blob(raw: &[u8]) -> Result<Option<Vec<u8>>> {
-    if let Some(key) = load_master_key()? {
-        let encrypted = encrypt_bytes(&key, raw)?;
-        let mut out = Vec::with_capacity(BLOB_PREFIX.len() + encrypted.len());
-        out.extend_from_slice(BLOB_PREFIX);
-        out.extend_from_slice(&encrypted);
-        Ok(Some(out))
-    } else if std::env::var("ALLOW_PLAINTEXT_EVIDENCE").as_deref() == Ok("true") {
-        Ok(Some(raw.to_vec()))
-    } else {
-        Ok(None)
+/// Key sealing an evidence record's content. Rows not yet given their own
+/// key (before the vault migrates them) are sealed with the vault key.
+fn evidence_key(vault_key: &DataKey, id: &str, wrapped_key: Option<Vec<u8>>) -> Result<DataKey> {
+    match wrapped_key {
+        Some(wrapped) => vault_key.unwrap_subkey(&field_aad("evidence.wrapped_key", id), &wrapped),
+        None => Ok(vault_key.clone()),
     }
 }
 
-impl Database {
-    /// Creates a new database connection, initializing schema if needed
-    pub fn new() -> Result<Self> {
-        // Get app data directory
-        let data_dir = dirs::data_dir()
-            .context("Could not find data directory")?
-            .join("zkdpp-edge-agent");
+fn signed_audit_head_from_row(row: &rusqlite::Row) -> rusqlite::Result<SignedAuditHead> {
+    Ok(SignedAuditHead {
+        seq: row.get::<_, i64>(0)? as u64,
+        entry_hash: row.get(1)?,
+        public_key: row.get(2)?,
+        signature: row.get(3)?,
+        signed_at: DateTime::parse_from_rfc3339(&row.get::<_, String>(4)?)
+            .map(|d| d.with_timezone(&Utc))
+            .unwrap_or_else(|_| Utc::now()),
+    })
+}
 
-        std::fs::create_dir_all(&data_dir)?;
+/// Decrypts a sealed column (result index `idx`) inside a row mapper
+fn open_column(key: &DataKey, idx: usize, column: &str, row_id: &str, stored: String) -> rusqlite::Result<String> {
+    key.open_field(&field_aad(column, row_id), &stored)
+        .map_err(|e| rusqlite::Error::FromSqlConversionFailure(idx, rusqlite::types::Type::Text, e.into()))
+}
 
-        let db_path = data_dir.join("edge-agent.db");
-        let conn = Connection::open(&db_path)?;
+/// Default data directory (`<platform data dir>/zkdpp-edge-agent`)
+pub fn default_data_dir() -> Result<PathBuf> {
+    Ok(dirs::data_dir()
+        .context("Could not find data directory")?
+        .join("zkdpp-edge-agent"))
+}
 
-        let db = Database { conn };
-        db.init_schema()?;
+impl Database {
+    /// Opens (or creates) the database at `path` in WAL mode, migrating it
+    /// if needed
+    pub fn open(path: &Path, vault: Arc<Vault>) -> Result<Self> {
+        if let Some(parent) = path.parent() {
+            std::fs::create_dir_all(parent)
+                .with_context(|| format!("Could not create {}", parent.display()))?;
+        }
+        let conn = Connection::open(path)
+            .with_context(|| format!("Could not open database {}", path.display()))?;
+        conn.busy_timeout(BUSY_TIMEOUT)?;
+        conn.pragma_update_and_check(None, "journal_mode", "WAL", |row| row.get::<_, String>(0))?;
+        conn.pragma_update(None, "synchronous", "NORMAL")?;
+        Self::with_connection(conn, Some(path), vault)
+    }
 
-        Ok(db)
+    /// Opens a read-only connection to a database already opened by `open`
+    fn open_reader(path: &Path, vault: Arc<Vault>) -> Result<Self> {
+        let conn = Connection::open_with_flags(
+            path,
+            OpenFlags::SQLITE_OPEN_READ_ONLY | OpenFlags::SQLITE_OPEN_URI | OpenFlags::SQLITE_OPEN_NO_MUTEX,
+        )
+        .with_context(|| format!("Could not open database {}", path.display()))?;
+        conn.busy_timeout(BUSY_TIMEOUT)?;
+        Ok(Database::from_parts(conn, vault))
     }
 
-    /// Initializes the database schema
-    fn init_schema(&self) -> Result<()> {
-        self.conn.execute_batch(
-            r#"
-            -- Evidence table
-            CREATE TABLE IF NOT EXISTS evidence (
-                id TEXT PRIMARY KEY,
-                evidence_type TEXT NOT NULL,
-                original_filename TEXT,
-                mime_type TEXT,
-                content_hash TEXT NOT NULL,
-                extracted_text TEXT,
-                issuer_name TEXT,
-                issuer_type TEXT,
-                valid_from TEXT,
-                valid_until TEXT,
-                raw_content BLOB,
-                created_at TEXT NOT NULL
-            );
-
-            -- Claims table
-            CREATE TABLE IF NOT EXISTS claims (
-                id TEXT PRIMARY KEY,
-                claim_type TEXT NOT NULL,
-                value TEXT NOT NULL,
-                unit TEXT NOT NULL,
-                product_id TEXT NOT NULL,
-                evidence_ids TEXT NOT NULL,
-                confidence REAL,
-                verified INTEGER NOT NULL DEFAULT 0,
-                metadata TEXT NOT NULL DEFAULT '{}',
-                created_at TEXT NOT NULL,
-                updated_at TEXT NOT NULL
-            );
-
-            -- Commitments table
-            CREATE TABLE IF NOT EXISTS commitments (
-                id TEXT PRIMARY KEY,
-                root TEXT NOT NULL,
-                claim_count INTEGER NOT NULL,
-                claim_ids TEXT NOT NULL,
-                public_key TEXT NOT NULL,
-                signature TEXT NOT NULL,
-                valid_from TEXT,
-                valid_until TEXT,
-                revoked INTEGER NOT NULL DEFAULT 0,
-                revoked_at TEXT,
-                revoked_reason TEXT,
-                created_at TEXT NOT NULL
-            );
-
-            -- Keypairs table
-            CREATE TABLE IF NOT EXISTS keypairs (
-                id TEXT PRIMARY KEY,
-                public_key TEXT NOT NULL UNIQUE,
-                secret_key_encrypted TEXT NOT NULL,
-                created_at TEXT NOT NULL,
-                is_active INTEGER NOT NULL DEFAULT 0
-            );
-
-            -- Settings table
-            CREATE TABLE IF NOT EXISTS settings (
-                key TEXT PRIMARY KEY,
-                value TEXT NOT NULL
-            );
-
-            -- Indexes
-            CREATE INDEX IF NOT EXISTS idx_claims_product ON claims(product_id);
-            CREATE INDEX IF NOT EXISTS idx_claims_type ON claims(claim_type);
-            CREATE INDEX IF NOT EXISTS idx_commitments_root ON commitments(root);
-            "#,
-        )?;
+    /// Opens a private in-memory database that disappears when dropped
+    pub fn in_memory(vault: Arc<Vault>) -> Result<Self> {
+        Self::with_connection(Connection::open_in_memory()?, None, vault)
+    }
 
-        Ok(())
+    fn with_connection(conn: Connection, db_path: Option<&Path>, vault: Arc<Vault>) -> Result<Self> {
+        // SQLite leaves foreign key enforcement off unless asked per connection;
+        // secure_delete zeroes freed pages so deleted rows leave no copies
+        conn.execute_batch("PRAGMA foreign_keys = ON; PRAGMA secure_delete = ON;")?;
+        migrations::run(&conn, db_path)?;
+        Ok(Database::from_parts(conn, vault))
+    }
+
+    fn from_parts(conn: Connection, vault: Arc<Vault>) -> Self {
+        Database { conn, vault, atomic_depth: Cell::new(0), checkpoint_due: Cell::new(false) }
+    }
+
+    /// Runs `f` so that everything it writes is committed together or not at
+    /// all. Scopes nest: an inner failure only undoes the inner scope's
+    /// writes, and the outermost scope commits.
+    pub fn atomic<T>(&self, f: impl FnOnce() -> Result<T>) -> Result<T> {
+        self.conn.execute_batch("SAVEPOINT atomic")?;
+        self.atomic_depth.set(self.atomic_depth.get() + 1);
+        let result = f().and_then(|value| {
+            self.conn.execute_batch("RELEASE atomic")?;
+            Ok(value)
+        });
+        self.atomic_depth.set(self.atomic_depth.get() - 1);
+        if result.is_err() {
+            // Best effort: the original error is the one worth reporting
+            let _ = self.conn.execute_batch("ROLLBACK TO atomic; RELEASE atomic");
+        }
+        if self.atomic_depth.get() == 0 && self.checkpoint_due.replace(false) {
+            self.checkpoint()?;
+        }
+        result
     }
 
     // === Evidence operations ===
 
-    pub fn insert_evidence(&self, evidence: &Evidence, raw_content: Option<&[u8]>) -> Result<()> {
-        let stored_content = match raw_content {
-            Some(bytes) => maybe_encrypt_blob(bytes)?,
-            None => None,
-        };
+    /// Stores evidence under a fresh per-evidence key wrapped by the vault
+    /// key; `content` is the original document, sealed here.
+    #[cfg(test)]
+    pub fn insert_evidence(&self, evidence: &Evidence, content: Option<&[u8]>) -> Result<()> {
+        self.insert_sealed_evidence(&Self::seal_evidence(&self.vault, evidence, content)?)
+    }
+
+    /// Seals evidence for [`Database::insert_sealed_evidence`] without a
+    /// connection, so large documents are encrypted outside the writer lock.
+    pub fn seal_evidence<'a>(vault: &Vault, evidence: &'a Evidence, content: Option<&[u8]>) -> Result<SealedEvidence<'a>> {
+        let vault_key = vault.key()?;
+        let evidence_key = DataKey::generate();
+        let wrapped_key = vault_key.wrap_subkey(&field_aad("evidence.wrapped_key", &evidence.id), &evidence_key)?;
+        let content = content.map(|raw| evidence_key.seal_blob(raw)).transpose()?;
+        let extracted_text = evidence
+            .extracted_text
+            .as_deref()
+            .map(|text| evidence_key.seal_field(&field_aad("evidence.extracted_text", &evidence.id), text))
+            .transpose()?;
+        Ok(SealedEvidence { evidence, wrapped_key, content, extracted_text })
+    }
+
+    pub fn insert_sealed_evidence(&self, sealed: &SealedEvidence) -> Result<()> {
+        let evidence = sealed.evidence;
         self.conn.execute(
             r#"
             INSERT INTO evidence (id, evidence_type, original_filename, mime_type,
                 content_hash, extracted_text, issuer_name, issuer_type,
-                valid_from, valid_until, raw_content, created_at)
-            VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10, ?11, ?12)
+                valid_from, valid_until, raw_content, wrapped_key, created_at)
+            VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10, ?11, ?12, ?13)
             "#,
             params![
                 evidence.id,
@@ -277,12 +432,13 @@ impl Database {
                 evidence.original_filename,
                 evidence.mime_type,
                 evidence.content_hash,
-                evidence.extracted_text,
+                sealed.extracted_text,
                 evidence.issuer_name,
                 evidence.issuer_type,
                 evidence.valid_from.map(|d| d.to_rfc3339()),
                 evidence.valid_until.map(|d| d.to_rfc3339()),
-                stored_content,
+                sealed.content,
+                sealed.wrapped_key,
                 evidence.created_at.to_rfc3339()
             ],
         )?;
@@ -290,66 +446,30 @@ impl Database {
     }
 
     pub fn list_evidence(&self) -> Result<Vec<Evidence>> {
+        let key = self.vault.key()?;
         let mut stmt = self.conn.prepare(
             "SELECT id, evidence_type, original_filename, mime_type, content_hash,
-                    extracted_text, issuer_name, issuer_type, valid_from, valid_until, created_at
+                    extracted_text, issuer_name, issuer_type, valid_from, valid_until, created_at,
+                    wrapped_key, shredded_at
              FROM evidence ORDER BY created_at DESC"
         )?;
 
-        let rows = stmt.query_map([], |row| {
-            Ok(Evidence {
-                id: row.get(0)?,
-                evidence_type: row.get(1)?,
-                original_filename: row.get(2)?,
-                mime_type: row.get(3)?,
-                content_hash: row.get(4)?,
-                extracted_text: row.get(5)?,
-                issuer_name: row.get(6)?,
-                issuer_type: row.get(7)?,
-                valid_from: row.get::<_, Option<String>>(8)?
-                    .and_then(|s| DateTime::parse_from_rfc3339(&s).ok())
-                    .map(|d| d.with_timezone(&Utc)),
-                valid_until: row.get::<_, Option<String>>(9)?
-                    .and_then(|s| DateTime::parse_from_rfc3339(&s).ok())
-                    .map(|d| d.with_timezone(&Utc)),
-                created_at: DateTime::parse_from_rfc3339(&row.get::<_, String>(10)?)
-                    .map(|d| d.with_timezone(&Utc))
-                    .unwrap_or_else(|_| Utc::now()),
-            })
-        })?;
+        let rows = stmt.query_map([], |row| Self::map_evidence_row(&key, row))?;
 
         rows.collect::<std::result::Result<Vec<_>, _>>()
             .context("Failed to list evidence")
     }
 
     pub fn get_evidence(&self, id: &str) -> Result<Option<Evidence>> {
+        let key = self.vault.key()?;
         let mut stmt = self.conn.prepare(
             "SELECT id, evidence_type, original_filename, mime_type, content_hash,
-                    extracted_text, issuer_name, issuer_type, valid_from, valid_until, created_at
+                    extracted_text, issuer_name, issuer_type, valid_from, valid_until, created_at,
+                    wrapped_key, shredded_at
              FROM evidence WHERE id = ?1"
         )?;
 
-        let result = stmt.query_row([id], |row| {
-            Ok(Evidence {
-                id: row.get(0)?,
-                evidence_type: row.get(1)?,
-                original_filename: row.get(2)?,
-                mime_type: row.get(3)?,
-                content_hash: row.get(4)?,
-                extracted_text: row.get(5)?,
-                issuer_name: row.get(6)?,
-                issuer_type: row.get(7)?,
-                valid_from: row.get::<_, Option<String>>(8)?
-                    .and_then(|s| DateTime::parse_from_rfc3339(&s).ok())
-                    .map(|d| d.with_timezone(&Utc)),
-                valid_until: row.get::<_, Option<String>>(9)?
-                    .and_then(|s| DateTime::parse_from_rfc3339(&s).ok())
-                    .map(|d| d.with_timezone(&Utc)),
-                created_at: DateTime::parse_from_rfc3339(&row.get::<_, String>(10)?)
-                    .map(|d| d.with_timezone(&Utc))
-                    .unwrap_or_else(|_| Utc::now()),
-            })
-        });
+        let result = stmt.query_row([id], |row| Self::map_evidence_row(&key, row));
 
         match result {
             Ok(evidence) => Ok(Some(evidence)),
@@ -358,32 +478,268 @@ impl Database {
         }
     }
 
+    fn map_evidence_row(vault_key: &DataKey, row: &rusqlite::Row) -> rusqlite::Result<Evidence> {
+        let id: String = row.get(0)?;
+        let extracted_text = match row.get::<_, Option<String>>(5)? {
+            Some(text) => {
+                let evidence_key = evidence_key(vault_key, &id, row.get(11)?)
+                    .map_err(|e| rusqlite::Error::FromSqlConversionFailure(11, rusqlite::types::Type::Blob, e.into()))?;
+                Some(open_column(&evidence_key, 5, "evidence.extracted_text", &id, text)?)
+            }
+            None => None,
+        };
+        Ok(Evidence {
+            id,
+            evidence_type: row.get(1)?,
+            original_filename: row.get(2)?,
+            mime_type: row.get(3)?,
+            content_hash: row.get(4)?,
+            extracted_text,
+            issuer_name: row.get(6)?,
+            issuer_type: row.get(7)?,
+            valid_from: row.get::<_, Option<String>>(8)?
+                .and_then(|s| DateTime::parse_from_rfc3339(&s).ok())
+                .map(|d| d.with_timezone(&Utc)),
+            valid_until: row.get::<_, Option<String>>(9)?
+                .and_then(|s| DateTime::parse_from_rfc3339(&s).ok())
+                .map(|d| d.with_timezone(&Utc)),
+            created_at: DateTime::parse_from_rfc3339(&row.get::<_, String>(10)?)
+                .map(|d| d.with_timezone(&Utc))
+                .unwrap_or_else(|_| Utc::now()),
+            shredded_at: row.get::<_, Option<String>>(12)?
+                .and_then(|s| DateTime::parse_from_rfc3339(&s).ok())
+                .map(|d| d.with_timezone(&Utc)),
+        })
+    }
+
+    // Nothing reads stored evidence back yet
+    #[cfg(test)]
+    pub fn get_evidence_content(&self, id: &str) -> Result<Option<Vec<u8>>> {
+        let key = self.vault.key()?;
+        let (wrapped_key, blob): (Option<Vec<u8>>, Option<Vec<u8>>) = self.conn.query_row(
+            "SELECT wrapped_key, raw_content FROM evidence WHERE id = ?1",
+            [id],
+            |row| Ok((row.get(0)?, row.get(1)?)),
+        )?;
+        blob.map(|blob| evidence_key(&key, id, wrapped_key)?.open_blob(&blob))
+            .transpose()
+    }
+
+    /// Live evidence stored before per-evidence keys, with its stored blob
+    pub fn list_unkeyed_evidence(&self) -> Result<Vec<(String, Option<Vec<u8>>)>> {
+        let mut stmt = self.conn.prepare(
+            "SELECT id, raw_content FROM evidence
+             WHERE wrapped_key IS NULL AND shredded_at IS NULL ORDER BY id"
+        )?;
+        let rows = stmt.query_map([], |row| Ok((row.get(0)?, row.get(1)?)))?;
+        rows.collect::<std::result::Result<Vec<_>, _>>()
+            .context("Failed to load evidence content")
+    }
+
+    /// Gives legacy evidence its own key, resealing the (already opened)
+    /// content and the extracted text under it.
+    pub fn assign_evidence_key(&self, vault_key: &DataKey, id: &str, content: Option<&[u8]>) -> Result<()> {
+        let text_aad = field_aad("evidence.extracted_text", id);
+        let extracted_text = self
+            .conn
+            .query_row("SELECT extracted_text FROM evidence WHERE id = ?1", [id], |row| {
+                row.get::<_, Option<String>>(0)
+            })?
+            .map(|text| vault_key.open_legacy_field(&text_aad, &text))
+            .transpose()?;
+
+        let evidence_key = DataKey::generate();
+        let wrapped_key = vault_key.wrap_subkey(&field_aad("evidence.wrapped_key", id), &evidence_key)?;
+        self.conn.execute(
+            "UPDATE evidence SET wrapped_key = ?2, raw_content = ?3, extracted_text = ?4 WHERE id = ?1",
+            params![
+                id,
+                wrapped_key,
+                content.map(|raw| evidence_key.seal_blob(raw)).transpose()?,
+                extracted_text.map(|text| evidence_key.seal_field(&text_aad, &text)).transpose()?
+            ],
+        )?;
+        Ok(())
+    }
+
+    /// Crypto-shreds evidence: its wrapped key is destroyed together with the
+    /// sealed content and text, so leftover copies of the ciphertext can no
+    /// longer be opened. Metadata and the content hash stay for audit.
+    pub fn shred_evidence(&self, id: &str) -> Result<bool> {
+        let shredded = self.shred_evidence_row(id, Utc::now())?;
+        self.checkpoint()?;
+        Ok(shredded)
+    }
+
+    /// Live evidence of `evidence_type` ingested before `cutoff`
+    pub fn expired_evidence(&self, evidence_type: &str, cutoff: DateTime<Utc>) -> Result<Vec<String>> {
+        let mut stmt = self.conn.prepare(
+            "SELECT id, created_at FROM evidence WHERE evidence_type = ?1 AND shredded_at IS NULL"
+        )?;
+        let expired = stmt
+            .query_map([evidence_type], |row| Ok((row.get::<_, String>(0)?, row.get::<_, String>(1)?)))?
+            .collect::<std::result::Result<Vec<_>, _>>()?
+            .into_iter()
+            .filter(|(_, created_at)| {
+                DateTime::parse_from_rfc3339(created_at)
+                    .map(|d| d.with_timezone(&Utc) < cutoff)
+                    .unwrap_or(false)
+            })
+            .map(|(id, _)| id)
+            .collect::<Vec<_>>();
+        Ok(expired)
+    }
+
+    /// Shreds each of `ids` not shredded yet, returning those shredded.
+    pub fn shred_evidence_ids(&self, ids: &[String]) -> Result<Vec<String>> {
+        let now = Utc::now();
+        let shredded = self.atomic(|| {
+            let mut shredded = Vec::new();
+            for id in ids {
+                if self.shred_evidence_row(id, now)? {
+                    shredded.push(id.clone());
+                }
+            }
+            Ok(shredded)
+        })?;
+        if !shredded.is_empty() {
+            self.checkpoint()?;
+        }
+        Ok(shredded)
+    }
+
+    fn shred_evidence_row(&self, id: &str, now: DateTime<Utc>) -> Result<bool> {
+        let affected = self.conn.execute(
+            "UPDATE evidence SET wrapped_key = NULL, raw_content = NULL, extracted_text = NULL,
+                 shredded_at = ?2
+             WHERE id = ?1 AND shredded_at IS NULL",
+            params![id, now.to_rfc3339()],
+        )?;
+        Ok(affected > 0)
+    }
+
+    /// Deletes evidence that no claim refers to, shredding its key with it.
     pub fn delete_evidence(&self, id: &str) -> Result<bool> {
+        let dependents = self.get_evidence_dependents(id)?;
+        if !dependents.claim_ids.is_empty() {
+            return Err(anyhow::anyhow!(
+                "Evidence {} is referenced by {} claim(s)",
+                id,
+                dependents.claim_ids.len()
+            ));
+        }
         let affected = self.conn.execute("DELETE FROM evidence WHERE id = ?1", [id])?;
+        self.checkpoint()?;
         Ok(affected > 0)
     }
 
+    /// Moves WAL frames into the database and truncates the log, so destroyed
+    /// keys do not linger there (freed pages are zeroed by `secure_delete`)
+    /// Inside an atomic scope the checkpoint waits until the scope closes.
+    fn checkpoint(&self) -> Result<()> {
+        if self.atomic_depth.get() > 0 {
+            self.checkpoint_due.set(true);
+            return Ok(());
+        }
+        self.conn.query_row("PRAGMA wal_checkpoint(TRUNCATE)", [], |_| Ok(()))?;
+        Ok(())
+    }
+
+    /// Claims citing the evidence, and the commitments that include them.
+    pub fn get_evidence_dependents(&self, evidence_id: &str) -> Result<EvidenceDependents> {
+        let mut stmt = self.conn.prepare(
+            "SELECT claim_id FROM claim_evidence WHERE evidence_id = ?1 ORDER BY claim_id"
+        )?;
+        let claim_ids = stmt
+            .query_map([evidence_id], |row| row.get(0))?
+            .collect::<std::result::Result<Vec<String>, _>>()?;
+
+        let mut stmt = self.conn.prepare(
+            "SELECT DISTINCT cc.commitment_id FROM commitment_claims cc
+             JOIN claim_evidence ce ON ce.claim_id = cc.claim_id
+             WHERE ce.evidence_id = ?1 ORDER BY cc.commitment_id"
+        )?;
+        let commitment_ids = stmt
+            .query_map([evidence_id], |row| row.get(0))?
+            .collect::<std::result::Result<Vec<String>, _>>()?;
+
+        Ok(EvidenceDependents { claim_ids, commitment_ids })
+    }
+
     // === Claim operations ===
 
     pub fn insert_claim(&self, claim: &Claim) -> Result<()> {
+        let key = self.vault.key()?;
+        self.atomic(|| {
+            self.conn.execute(
+                r#"
+                INSERT INTO claims (id, claim_type, value, unit, product_id, evidence_ids,
+                    confidence, verified, metadata, created_at, updated_at, version)
+                VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10, ?11, 1)
+                "#,
+                params![
+                    claim.id,
+                    claim.claim_type,
+                    key.seal_field(&field_aad("claims.value", &claim.id), &serde_json::to_string(&claim.value)?)?,
+                    claim.unit,
+                    claim.product_id,
+                    serde_json::to_string(&claim.evidence_ids)?,
+                    claim.confidence,
+                    claim.verified as i32,
+                    key.seal_field(&field_aad("claims.metadata", &claim.id), &serde_json::to_string(&claim.metadata)?)?,
+                    claim.created_at.to_rfc3339(),
+                    claim.updated_at.to_rfc3339()
+                ],
+            )?;
+            self.link_claim_evidence(claim)?;
+            self.insert_claim_version(&key, claim, 1, ClaimChange::Created, claim.updated_at)?;
+            Ok(())
+        })?;
+        Ok(())
+    }
+
+    fn link_claim_evidence(&self, claim: &Claim) -> Result<()> {
+        self.conn.execute("DELETE FROM claim_evidence WHERE claim_id = ?1", [&claim.id])?;
+        for evidence_id in &claim.evidence_ids {
+            self.conn
+                .execute(
+                    "INSERT OR IGNORE INTO claim_evidence (claim_id, evidence_id) VALUES (?1, ?2)",
+                    params![claim.id, evidence_id],
+                )
+                .with_context(|| format!("Evidence {} not found", evidence_id))?;
+        }
+        Ok(())
+    }
+
+    fn insert_claim_version(
+        &self,
+        key: &DataKey,
+        claim: &Claim,
+        version: u32,
+        change: ClaimChange,
+        recorded_at: DateTime<Utc>,
+    ) -> Result<()> {
+        let row_id = format!("{}/{}", claim.id, version);
         self.conn.execute(
             r#"
-            INSERT INTO claims (id, claim_type, value, unit, product_id, evidence_ids,
-                confidence, verified, metadata, created_at, updated_at)
-            VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10, ?11)
+            INSERT INTO claim_versions (claim_id, version, change, claim_type, value, unit,
+                product_id, evidence_ids, confidence, verified, metadata, created_at, recorded_at)
+            VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10, ?11, ?12, ?13)
             "#,
             params![
                 claim.id,
+                version,
+                change.as_str(),
                 claim.claim_type,
-                serde_json::to_string(&claim.value)?,
+                key.seal_field(&field_aad("claim_versions.value", &row_id), &serde_json::to_string(&claim.value)?)?,
                 claim.unit,
                 claim.product_id,
                 serde_json::to_string(&claim.evidence_ids)?,
                 claim.confidence,
                 claim.verified as i32,
-                serde_json::to_string(&claim.metadata)?,
+                key.seal_field(&field_aad("claim_versions.metadata", &row_id), &serde_json::to_string(&claim.metadata)?)?,
                 claim.created_at.to_rfc3339(),
-                claim.updated_at.to_rfc3339()
+                recorded_at.to_rfc3339()
             ],
         )?;
         Ok(())
@@ -393,35 +749,41 @@ impl Database {
         let query = match product_id {
             Some(_) => "SELECT id, claim_type, value, unit, product_id, evidence_ids,
                                confidence, verified, metadata, created_at, updated_at
-                        FROM claims WHERE product_id = ?1 ORDER BY created_at DESC",
+                        FROM claims WHERE product_id = ?1 AND retired_at IS NULL
+                        ORDER BY created_at DESC",
             None => "SELECT id, claim_type, value, unit, product_id, evidence_ids,
                             confidence, verified, metadata, created_at, updated_at
-                     FROM claims ORDER BY created_at DESC",
+                     FROM claims WHERE retired_at IS NULL ORDER BY created_at DESC",
         };
 
+        let key = self.vault.key()?;
+        let map_row = |row: &rusqlite::Row<'_>| Self::map_claim_row(&key, row);
         let mut stmt = self.conn.prepare(query)?;
 
         let rows = if let Some(pid) = product_id {
-            stmt.query_map([pid], Self::map_claim_row)?
+            stmt.query_map([pid], &map_row)?
         } else {
-            stmt.query_map([], Self::map_claim_row)?
+            stmt.query_map([], &map_row)?
         };
 
         rows.collect::<std::result::Result<Vec<_>, _>>()
             .context("Failed to list claims")
     }
 
-    fn map_claim_row(row: &rusqlite::Row) -> rusqlite::Result<Claim> {
+    fn map_claim_row(key: &DataKey, row: &rusqlite::Row) -> rusqlite::Result<Claim> {
+        let id: String = row.get(0)?;
+        let value = open_column(key, 2, "claims.value", &id, row.get(2)?)?;
+        let metadata = open_column(key, 8, "claims.metadata", &id, row.get(8)?)?;
         Ok(Claim {
-            id: row.get(0)?,
+            id,
             claim_type: row.get(1)?,
-            value: serde_json::from_str(&row.get::<_, String>(2)?).unwrap_or(serde_json::Value::Null),
+            value: serde_json::from_str(&value).unwrap_or(serde_json::Value::Null),
             unit: row.get(3)?,
             product_id: row.get(4)?,
             evidence_ids: serde_json::from_str(&row.get::<_, String>(5)?).unwrap_or_default(),
             confidence: row.get(6)?,
             verified: row.get::<_, i32>(7)? != 0,
-            metadata: serde_json::from_str(&row.get::<_, String>(8)?).unwrap_or(serde_json::Value::Object(Default::default())),
+            metadata: serde_json::from_str(&metadata).unwrap_or(serde_json::Value::Object(Default::default())),
             created_at: DateTime::parse_from_rfc3339(&row.get::<_, String>(9)?)
                 .map(|d| d.with_timezone(&Utc))
                 .unwrap_or_else(|_| Utc::now()),
@@ -432,13 +794,14 @@ impl Database {
     }
 
     pub fn get_claim(&self, id: &str) -> Result<Option<Claim>> {
+        let key = self.vault.key()?;
         let mut stmt = self.conn.prepare(
             "SELECT id, claim_type, value, unit, product_id, evidence_ids,
                     confidence, verified, metadata, created_at, updated_at
              FROM claims WHERE id = ?1"
         )?;
 
-        let result = stmt.query_row([id], Self::map_claim_row);
+        let result = stmt.query_row([id], |row| Self::map_claim_row(&key, row));
 
         match result {
             Ok(claim) => Ok(Some(claim)),
@@ -447,34 +810,151 @@ impl Database {
         }
     }
 
+    pub fn get_claim_status(&self, id: &str) -> Result<Option<ClaimStatus>> {
+        let result = self.conn.query_row(
+            "SELECT version, retired_at FROM claims WHERE id = ?1",
+            [id],
+            |row| {
+                Ok(ClaimStatus {
+                    version: row.get(0)?,
+                    retired_at: row.get::<_, Option<String>>(1)?
+                        .and_then(|s| DateTime::parse_from_rfc3339(&s).ok())
+                        .map(|d| d.with_timezone(&Utc)),
+                })
+            },
+        );
+
+        match result {
+            Ok(status) => Ok(Some(status)),
+            Err(rusqlite::Error::QueryReturnedNoRows) => Ok(None),
+            Err(e) => Err(e.into()),
+        }
+    }
+
+    /// Records an edit as a new claim version; earlier (possibly committed)
+    /// versions stay untouched. Retired claims cannot be edited.
     pub fn update_claim(&self, claim: &Claim) -> Result<bool> {
-        let affected = self.conn.execute(
-            r#"
-            UPDATE claims SET
-                claim_type = ?2, value = ?3, unit = ?4, product_id = ?5,
-                evidence_ids = ?6, confidence = ?7, verified = ?8,
-                metadata = ?9, updated_at = ?10
-            WHERE id = ?1
-            "#,
-            params![
-                claim.id,
-                claim.claim_type,
-                serde_json::to_string(&claim.value)?,
-                claim.unit,
-                claim.product_id,
-                serde_json::to_string(&claim.evidence_ids)?,
-                claim.confidence,
-                claim.verified as i32,
-                serde_json::to_string(&claim.metadata)?,
-                Utc::now().to_rfc3339()
-            ],
+        let status = match self.get_claim_status(&claim.id)? {
+            Some(status) => status,
+            None => return Ok(false),
+        };
+        if status.retired_at.is_some() {
+            return Err(anyhow::anyhow!("Claim {} is retired and cannot be edited", claim.id));
+        }
+
+        let version = status.version + 1;
+        let now = Utc::now();
+        let key = self.vault.key()?;
+        self.atomic(|| {
+            self.conn.execute(
+                r#"
+                UPDATE claims SET
+                    claim_type = ?2, value = ?3, unit = ?4, product_id = ?5,
+                    evidence_ids = ?6, confidence = ?7, verified = ?8,
+                    metadata = ?9, updated_at = ?10, version = ?11
+                WHERE id = ?1
+                "#,
+                params![
+                    claim.id,
+                    claim.claim_type,
+                    key.seal_field(&field_aad("claims.value", &claim.id), &serde_json::to_string(&claim.value)?)?,
+                    claim.unit,
+                    claim.product_id,
+                    serde_json::to_string(&claim.evidence_ids)?,
+                    claim.confidence,
+                    claim.verified as i32,
+                    key.seal_field(&field_aad("claims.metadata", &claim.id), &serde_json::to_string(&claim.metadata)?)?,
+                    now.to_rfc3339(),
+                    version
+                ],
+            )?;
+            self.link_claim_evidence(claim)?;
+            self.insert_claim_version(&key, claim, version, ClaimChange::Updated, now)?;
+            Ok(())
+        })?;
+        Ok(true)
+    }
+
+    /// Soft-deletes a claim: it disappears from listings and cannot be
+    /// edited or committed again, but its history and commitments remain.
+    pub fn retire_claim(&self, id: &str) -> Result<bool> {
+        let claim = match self.get_claim(id)? {
+            Some(claim) => claim,
+            None => return Ok(false),
+        };
+        let status = match self.get_claim_status(id)? {
+            Some(status) if status.retired_at.is_none() => status,
+            _ => return Ok(false),
+        };
+
+        let version = status.version + 1;
+        let now = Utc::now();
+        let key = self.vault.key()?;
+        self.atomic(|| {
+            self.conn.execute(
+                "UPDATE claims SET retired_at = ?2, updated_at = ?2, version = ?3 WHERE id = ?1",
+                params![id, now.to_rfc3339(), version],
+            )?;
+            self.insert_claim_version(&key, &claim, version, ClaimChange::Retired, now)?;
+            Ok(())
+        })?;
+        Ok(true)
+    }
+
+    /// Returns every version of a claim, oldest first.
+    pub fn get_claim_history(&self, id: &str) -> Result<Vec<ClaimVersion>> {
+        let key = self.vault.key()?;
+        let mut stmt = self.conn.prepare(
+            "SELECT claim_id, version, change, claim_type, value, unit, product_id, evidence_ids,
+                    confidence, verified, metadata, created_at, recorded_at
+             FROM claim_versions WHERE claim_id = ?1 ORDER BY version"
         )?;
-        Ok(affected > 0)
+
+        let rows = stmt.query_map([id], |row| {
+            let claim_id: String = row.get(0)?;
+            let version: u32 = row.get(1)?;
+            let row_id = format!("{}/{}", claim_id, version);
+            let value = open_column(&key, 4, "claim_versions.value", &row_id, row.get(4)?)?;
+            let metadata = open_column(&key, 10, "claim_versions.metadata", &row_id, row.get(10)?)?;
+            let recorded_at = DateTime::parse_from_rfc3339(&row.get::<_, String>(12)?)
+                .map(|d| d.with_timezone(&Utc))
+                .unwrap_or_else(|_| Utc::now());
+            Ok(ClaimVersion {
+                claim_id: claim_id.clone(),
+                version,
+                change: ClaimChange::parse(&row.get::<_, String>(2)?),
+                claim: Claim {
+                    id: claim_id,
+                    claim_type: row.get(3)?,
+                    value: serde_json::from_str(&value).unwrap_or(serde_json::Value::Null),
+                    unit: row.get(5)?,
+                    product_id: row.get(6)?,
+                    evidence_ids: serde_json::from_str(&row.get::<_, String>(7)?).unwrap_or_default(),
+                    confidence: row.get(8)?,
+                    verified: row.get::<_, i32>(9)? != 0,
+                    metadata: serde_json::from_str(&metadata).unwrap_or(serde_json::Value::Object(Default::default())),
+                    created_at: DateTime::parse_from_rfc3339(&row.get::<_, String>(11)?)
+                        .map(|d| d.with_timezone(&Utc))
+                        .unwrap_or_else(|_| Utc::now()),
+                    updated_at: recorded_at,
+                },
+                recorded_at,
+            })
+        })?;
+
+        rows.collect::<std::result::Result<Vec<_>, _>>()
+            .context("Failed to load claim history")
     }
 
-    pub fn delete_claim(&self, id: &str) -> Result<bool> {
-        let affected = self.conn.execute("DELETE FROM claims WHERE id = ?1", [id])?;
-        Ok(affected > 0)
+    /// IDs of commitments that froze the given claim version.
+    pub fn commitments_for_claim_version(&self, claim_id: &str, version: u32) -> Result<Vec<String>> {
+        let mut stmt = self.conn.prepare(
+            "SELECT commitment_id FROM commitment_leaves
+             WHERE claim_id = ?1 AND claim_version = ?2 ORDER BY commitment_id"
+        )?;
+        let rows = stmt.query_map(params![claim_id, version], |row| row.get(0))?;
+        rows.collect::<std::result::Result<Vec<_>, _>>()
+            .context("Failed to load commitments for claim version")
     }
 
     // === Commitment operations ===
@@ -499,13 +979,77 @@ impl Database {
                 commitment.created_at.to_rfc3339()
             ],
         )?;
+        for (position, claim_id) in commitment.claim_ids.iter().enumerate() {
+            self.conn
+                .execute(
+                    "INSERT INTO commitment_claims (commitment_id, claim_id, position) VALUES (?1, ?2, ?3)",
+                    params![commitment.id, claim_id, position as i64],
+                )
+                .with_context(|| format!("Claim {} not found", claim_id))?;
+        }
         Ok(())
     }
 
+    /// Stores a commitment together with its frozen leaves atomically.
+    pub fn insert_commitment_snapshot(&self, commitment: &Commitment, leaves: &[CommitmentLeaf]) -> Result<()> {
+        let key = self.vault.key()?;
+        self.atomic(|| {
+            self.insert_commitment(commitment)?;
+            for leaf in leaves {
+                let row_id = format!("{}/{}", leaf.commitment_id, leaf.position);
+                self.conn.execute(
+                    r#"
+                    INSERT INTO commitment_leaves (commitment_id, position, claim_id, claim_version,
+                        leaf_hash, canonical_claim)
+                    VALUES (?1, ?2, ?3, ?4, ?5, ?6)
+                    "#,
+                    params![
+                        leaf.commitment_id,
+                        leaf.position as i64,
+                        leaf.claim_id,
+                        leaf.claim_version,
+                        leaf.leaf_hash,
+                        key.seal_field(&field_aad("commitment_leaves.canonical_claim", &row_id), &leaf.canonical_claim)?
+                    ],
+                )?;
+            }
+            Ok(())
+        })?;
+        Ok(())
+    }
+
+    /// Returns the frozen leaves of a commitment in tree order (empty for
+    /// commitments created before snapshots were stored).
+    pub fn get_commitment_leaves(&self, commitment_id: &str) -> Result<Vec<CommitmentLeaf>> {
+        let key = self.vault.key()?;
+        let mut stmt = self.conn.prepare(
+            "SELECT commitment_id, position, claim_id, claim_version, leaf_hash, canonical_claim
+             FROM commitment_leaves WHERE commitment_id = ?1 ORDER BY position"
+        )?;
+
+        let rows = stmt.query_map([commitment_id], |row| {
+            let commitment_id: String = row.get(0)?;
+            let position = row.get::<_, i64>(1)? as usize;
+            let row_id = format!("{}/{}", commitment_id, position);
+            Ok(CommitmentLeaf {
+                canonical_claim: open_column(&key, 5, "commitment_leaves.canonical_claim", &row_id, row.get(5)?)?,
+                commitment_id,
+                position,
+                claim_id: row.get(2)?,
+                claim_version: row.get(3)?,
+                leaf_hash: row.get(4)?,
+            })
+        })?;
+
+        rows.collect::<std::result::Result<Vec<_>, _>>()
+            .context("Failed to load commitment leaves")
+    }
+
     pub fn list_commitments(&self) -> Result<Vec<Commitment>> {
         let mut stmt = self.conn.prepare(
             "SELECT id, root, claim_count, claim_ids, public_key, signature,
-                    valid_from, valid_until, revoked, revoked_at, revoked_reason, created_at
+                    valid_from, valid_until, revoked, revoked_at, revoked_reason, created_at,
+                    revocation
              FROM commitments ORDER BY created_at DESC"
         )?;
 
@@ -531,6 +1075,8 @@ impl Database {
                 created_at: DateTime::parse_from_rfc3339(&row.get::<_, String>(11)?)
                     .map(|d| d.with_timezone(&Utc))
                     .unwrap_or_else(|_| Utc::now()),
+                revocation: row.get::<_, Option<String>>(12)?
+                    .and_then(|s| serde_json::from_str(&s).ok()),
             })
         })?;
 
@@ -541,7 +1087,8 @@ impl Database {
     pub fn get_commitment(&self, id: &str) -> Result<Option<Commitment>> {
         let mut stmt = self.conn.prepare(
             "SELECT id, root, claim_count, claim_ids, public_key, signature,
-                    valid_from, valid_until, revoked, revoked_at, revoked_reason, created_at
+                    valid_from, valid_until, revoked, revoked_at, revoked_reason, created_at,
+                    revocation
              FROM commitments WHERE id = ?1"
         )?;
 
@@ -567,6 +1114,8 @@ impl Database {
                 created_at: DateTime::parse_from_rfc3339(&row.get::<_, String>(11)?)
                     .map(|d| d.with_timezone(&Utc))
                     .unwrap_or_else(|_| Utc::now()),
+                revocation: row.get::<_, Option<String>>(12)?
+                    .and_then(|s| serde_json::from_str(&s).ok()),
             })
         });
 
@@ -577,47 +1126,163 @@ impl Database {
         }
     }
 
-    pub fn revoke_commitment(&self, id: &str, reason: &str) -> Result<bool> {
+    /// Marks a commitment revoked, keeping the supplier's signed statement
+    /// when there is one.
+    pub fn revoke_commitment(
+        &self,
+        id: &str,
+        reason: &str,
+        revoked_at: DateTime<Utc>,
+        revocation: Option<&SignedMessage<Revocation>>,
+    ) -> Result<bool> {
         let affected = self.conn.execute(
             r#"
             UPDATE commitments SET
                 revoked = 1,
                 revoked_at = ?2,
-                revoked_reason = ?3
+                revoked_reason = ?3,
+                revocation = ?4
             WHERE id = ?1
             "#,
-            params![id, Utc::now().to_rfc3339(), reason],
+            params![
+                id,
+                revoked_at.to_rfc3339(),
+                reason,
+                revocation.map(serde_json::to_string).transpose()?
+            ],
         )?;
+        self.invalidate_cached_proofs(id)?;
         Ok(affected > 0)
     }
 
-    // === Keypair operations ===
+    // === Proof cache operations ===
+
+    /// Returns a live cache entry; expired entries are purged on lookup.
+    pub fn get_cached_proof(&self, cache_key: &str, now: DateTime<Utc>) -> Result<Option<CachedProof>> {
+        self.conn.execute(
+            "DELETE FROM proof_cache WHERE expires_at <= ?1",
+            [now.to_rfc3339()],
+        )?;
 
-    pub fn get_active_keypair(&self) -> Result<Option<StoredKeypair>> {
         let mut stmt = self.conn.prepare(
-            "SELECT id, public_key, secret_key_encrypted, created_at, is_active
-             FROM keypairs WHERE is_active = 1 LIMIT 1"
+            "SELECT p.cache_key, p.commitment_id, p.predicate_id, p.public_inputs, p.proof,
+                    p.created_at, p.expires_at
+             FROM proof_cache p
+             JOIN commitments c ON c.id = p.commitment_id
+             WHERE p.cache_key = ?1 AND c.revoked = 0"
         )?;
 
-        let result = stmt.query_row([], |row| {
-            Ok(StoredKeypair {
-                id: row.get(0)?,
-                public_key: row.get(1)?,
-                secret_key_encrypted: row.get(2)?,
-                created_at: DateTime::parse_from_rfc3339(&row.get::<_, String>(3)?)
+        let result = stmt.query_row([cache_key], |row| {
+            Ok(CachedProof {
+                cache_key: row.get(0)?,
+                commitment_id: row.get(1)?,
+                predicate_id: row.get(2)?,
+                public_inputs: serde_json::from_str(&row.get::<_, String>(3)?).unwrap_or(serde_json::Value::Null),
+                proof: row.get(4)?,
+                created_at: DateTime::parse_from_rfc3339(&row.get::<_, String>(5)?)
+                    .map(|d| d.with_timezone(&Utc))
+                    .unwrap_or_else(|_| Utc::now()),
+                expires_at: DateTime::parse_from_rfc3339(&row.get::<_, String>(6)?)
                     .map(|d| d.with_timezone(&Utc))
                     .unwrap_or_else(|_| Utc::now()),
-                is_active: row.get::<_, i32>(4)? != 0,
             })
         });
 
         match result {
+            Ok(entry) => Ok(Some(entry)),
+            Err(rusqlite::Error::QueryReturnedNoRows) => Ok(None),
+            Err(e) => Err(e.into()),
+        }
+    }
+
+    pub fn insert_cached_proof(&self, entry: &CachedProof) -> Result<()> {
+        self.conn.execute(
+            r#"
+            INSERT OR REPLACE INTO proof_cache (cache_key, commitment_id, predicate_id,
+                public_inputs, proof, created_at, expires_at)
+            VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7)
+            "#,
+            params![
+                entry.cache_key,
+                entry.commitment_id,
+                entry.predicate_id,
+                serde_json::to_string(&entry.public_inputs)?,
+                entry.proof,
+                entry.created_at.to_rfc3339(),
+                entry.expires_at.to_rfc3339()
+            ],
+        )?;
+        Ok(())
+    }
+
+    pub fn invalidate_cached_proofs(&self, commitment_id: &str) -> Result<usize> {
+        let affected = self.conn.execute(
+            "DELETE FROM proof_cache WHERE commitment_id = ?1",
+            [commitment_id],
+        )?;
+        Ok(affected)
+    }
+
+    // === Keypair operations ===
+
+    pub fn get_active_keypair(&self) -> Result<Option<StoredKeypair>> {
+        let mut stmt = self.conn.prepare(
+            "SELECT id, public_key, secret_key_encrypted, created_at, is_active,
+                    retired_at, retired_reason, compromised, derivation_path
+             FROM keypairs WHERE is_active = 1 LIMIT 1"
+        )?;
+
+        match stmt.query_row([], Self::map_keypair_row) {
             Ok(kp) => Ok(Some(kp)),
             Err(rusqlite::Error::QueryReturnedNoRows) => Ok(None),
             Err(e) => Err(e.into()),
         }
     }
 
+    pub fn get_keypair(&self, id: &str) -> Result<Option<StoredKeypair>> {
+        let mut stmt = self.conn.prepare(
+            "SELECT id, public_key, secret_key_encrypted, created_at, is_active,
+                    retired_at, retired_reason, compromised, derivation_path
+             FROM keypairs WHERE id = ?1"
+        )?;
+
+        match stmt.query_row([id], Self::map_keypair_row) {
+            Ok(kp) => Ok(Some(kp)),
+            Err(rusqlite::Error::QueryReturnedNoRows) => Ok(None),
+            Err(e) => Err(e.into()),
+        }
+    }
+
+    /// Every keypair, oldest first
+    pub fn list_keypairs(&self) -> Result<Vec<StoredKeypair>> {
+        let mut stmt = self.conn.prepare(
+            "SELECT id, public_key, secret_key_encrypted, created_at, is_active,
+                    retired_at, retired_reason, compromised, derivation_path
+             FROM keypairs ORDER BY created_at, id"
+        )?;
+        let rows = stmt.query_map([], Self::map_keypair_row)?;
+        rows.collect::<std::result::Result<Vec<_>, _>>()
+            .context("Failed to load keypairs")
+    }
+
+    fn map_keypair_row(row: &rusqlite::Row) -> rusqlite::Result<StoredKeypair> {
+        Ok(StoredKeypair {
+            id: row.get(0)?,
+            public_key: row.get(1)?,
+            secret_key_encrypted: row.get(2)?,
+            created_at: DateTime::parse_from_rfc3339(&row.get::<_, String>(3)?)
+                .map(|d| d.with_timezone(&Utc))
+                .unwrap_or_else(|_| Utc::now()),
+            is_active: row.get::<_, i32>(4)? != 0,
+            retired_at: row.get::<_, Option<String>>(5)?
+                .and_then(|s| DateTime::parse_from_rfc3339(&s).ok())
+                .map(|d| d.with_timezone(&Utc)),
+            retired_reason: row.get(6)?,
+            compromised: row.get::<_, i32>(7)? != 0,
+            derivation_path: row.get(8)?,
+        })
+    }
+
     pub fn insert_keypair(&self, keypair: &StoredKeypair) -> Result<()> {
         // Deactivate existing keypairs if this one is active
         if keypair.is_active {
@@ -626,20 +1291,480 @@ impl Database {
 
         self.conn.execute(
             r#"
-            INSERT INTO keypairs (id, public_key, secret_key_encrypted, created_at, is_active)
-            VALUES (?1, ?2, ?3, ?4, ?5)
+            INSERT INTO keypairs (id, public_key, secret_key_encrypted, created_at, is_active,
+                retired_at, retired_reason, compromised, derivation_path)
+            VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9)
             "#,
             params![
                 keypair.id,
                 keypair.public_key,
                 keypair.secret_key_encrypted,
                 keypair.created_at.to_rfc3339(),
-                keypair.is_active as i32
+                keypair.is_active as i32,
+                keypair.retired_at.map(|d| d.to_rfc3339()),
+                keypair.retired_reason,
+                keypair.compromised as i32,
+                keypair.derivation_path
+            ],
+        )?;
+        Ok(())
+    }
+
+    /// Stops a key from signing. Compromise can be recorded on an already
+    /// retired key, but a compromised key is never reinstated.
+    pub fn retire_keypair(&self, id: &str, reason: &str, compromised: bool) -> Result<bool> {
+        let affected = self.conn.execute(
+            r#"
+            UPDATE keypairs SET
+                is_active = 0,
+                retired_at = COALESCE(retired_at, ?2),
+                retired_reason = ?3,
+                compromised = ?4
+            WHERE id = ?1 AND compromised = 0
+            "#,
+            params![id, Utc::now().to_rfc3339(), reason, compromised as i32],
+        )?;
+        Ok(affected > 0)
+    }
+
+    /// Retires the active key and activates its successor together with the
+    /// certificate linking them.
+    pub fn rotate_keypair(
+        &self,
+        previous_id: &str,
+        successor: &StoredKeypair,
+        certificate: &RotationCertificate,
+        reason: &str,
+    ) -> Result<()> {
+        self.atomic(|| {
+            if !self.retire_keypair(previous_id, reason, false)? {
+                anyhow::bail!("Keypair {} cannot be rotated", previous_id);
+            }
+            self.insert_keypair(successor)?;
+            self.insert_key_rotation(previous_id, &successor.id, certificate)?;
+            Ok(())
+        })?;
+        Ok(())
+    }
+
+    pub fn insert_key_rotation(
+        &self,
+        previous_id: &str,
+        new_id: &str,
+        certificate: &RotationCertificate,
+    ) -> Result<()> {
+        self.conn.execute(
+            r#"
+            INSERT INTO key_rotations (new_key_id, previous_key_id, previous_key, new_key,
+                issued_at, signature, new_key_signature)
+            VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7)
+            "#,
+            params![
+                new_id,
+                previous_id,
+                certificate.previous_key,
+                certificate.new_key,
+                certificate.issued_at as i64,
+                certificate.signature,
+                certificate.new_key_signature
             ],
         )?;
         Ok(())
     }
 
+    /// Inserts restored keypairs, then every certificate whose two keys are
+    /// now held and that is not recorded yet.
+    pub fn restore_keypairs(&self, keypairs: &[StoredKeypair], certificates: &[RotationCertificate]) -> Result<()> {
+        self.atomic(|| {
+            for keypair in keypairs {
+                self.insert_keypair(keypair)?;
+            }
+            let ids: HashMap<String, String> = self
+                .list_keypairs()?
+                .into_iter()
+                .map(|kp| (kp.public_key, kp.id))
+                .collect();
+            let recorded: Vec<String> = self.list_key_rotations()?.into_iter().map(|c| c.new_key).collect();
+            for certificate in certificates {
+                if recorded.contains(&certificate.new_key) {
+                    continue;
+                }
+                if let (Some(previous_id), Some(new_id)) = (ids.get(&certificate.previous_key), ids.get(&certificate.new_key)) {
+                    self.insert_key_rotation(previous_id, new_id, certificate)?;
+                }
+            }
+            Ok(())
+        })?;
+        Ok(())
+    }
+
+    /// Every rotation certificate, in rotation order
+    pub fn list_key_rotations(&self) -> Result<Vec<RotationCertificate>> {
+        let mut stmt = self.conn.prepare(
+            "SELECT previous_key, new_key, issued_at, signature, new_key_signature
+             FROM key_rotations ORDER BY issued_at, rowid"
+        )?;
+        let rows = stmt.query_map([], Self::map_rotation_row)?;
+        rows.collect::<std::result::Result<Vec<_>, _>>()
+            .context("Failed to load key rotations")
+    }
+
+    fn map_rotation_row(row: &rusqlite::Row) -> rusqlite::Result<RotationCertificate> {
+        Ok(RotationCertificate {
+            previous_key: row.get(0)?,
+            new_key: row.get(1)?,
+            issued_at: row.get::<_, i64>(2)? as u64,
+            signature: row.get(3)?,
+            new_key_signature: row.get(4)?,
+        })
+    }
+
+    /// Rotation certificates leading to `public_key`, oldest first. The first
+    /// certificate's previous key is the root of the chain.
+    pub fn key_chain(&self, public_key: &str) -> Result<Vec<RotationCertificate>> {
+        let mut stmt = self.conn.prepare(
+            "SELECT previous_key, new_key, issued_at, signature, new_key_signature
+             FROM key_rotations WHERE new_key = ?1"
+        )?;
+        let mut chain = Vec::new();
+        let mut current = public_key.to_string();
+        loop {
+            match stmt.query_row([&current], Self::map_rotation_row) {
+                Ok(certificate) => {
+                    current = certificate.previous_key.clone();
+                    chain.push(certificate);
+                }
+                Err(rusqlite::Error::QueryReturnedNoRows) => break,
+                Err(e) => return Err(e.into()),
+            }
+        }
+        chain.reverse();
+        Ok(chain)
+    }
+
+    pub fn list_keypair_secrets(&self) -> Result<Vec<(String, String)>> {
+        let mut stmt = self.conn.prepare("SELECT id, secret_key_encrypted FROM keypairs ORDER BY id")?;
+        let rows = stmt.query_map([], |row| Ok((row.get(0)?, row.get(1)?)))?;
+        rows.collect::<std::result::Result<Vec<_>, _>>()
+            .context("Failed to load keypairs")
+    }
+
+    pub fn update_keypair_secret(&self, id: &str, secret_key_encrypted: &str) -> Result<()> {
+        self.conn.execute(
+            "UPDATE keypairs SET secret_key_encrypted = ?2 WHERE id = ?1",
+            params![id, secret_key_encrypted],
+        )?;
+        Ok(())
+    }
+
+    /// Seals claim and commitment columns still stored in plaintext (written
+    /// before field encryption). Claim history is append-only, so its update
+    /// trigger is lifted meanwhile; callers run this inside a transaction.
+    pub fn seal_plaintext_fields(&self, key: &DataKey) -> Result<()> {
+        let mut stmt = self.conn.prepare("SELECT id, value, metadata FROM claims")?;
+        let rows = stmt
+            .query_map([], |row| {
+                Ok((row.get::<_, String>(0)?, row.get::<_, String>(1)?, row.get::<_, String>(2)?))
+            })?
+            .collect::<std::result::Result<Vec<_>, _>>()?;
+        for (id, value, metadata) in rows.into_iter().filter(|(_, value, _)| !vault::is_sealed_field(value)) {
+            self.conn.execute(
+                "UPDATE claims SET value = ?2, metadata = ?3 WHERE id = ?1",
+                params![
+                    id,
+                    key.seal_field(&field_aad("claims.value", &id), &value)?,
+                    key.seal_field(&field_aad("claims.metadata", &id), &metadata)?
+                ],
+            )?;
+        }
+
+        let mut stmt = self.conn.prepare("SELECT claim_id, version, value, metadata FROM claim_versions")?;
+        let rows = stmt
+            .query_map([], |row| {
+                Ok((
+                    format!("{}/{}", row.get::<_, String>(0)?, row.get::<_, u32>(1)?),
+                    row.get::<_, String>(0)?,
+                    row.get::<_, u32>(1)?,
+                    row.get::<_, String>(2)?,
+                    row.get::<_, String>(3)?,
+                ))
+            })?
+            .collect::<std::result::Result<Vec<_>, _>>()?;
+        let plaintext: Vec<_> = rows.into_iter().filter(|row| !vault::is_sealed_field(&row.3)).collect();
+        if !plaintext.is_empty() {
+            self.conn.execute_batch("DROP TRIGGER IF EXISTS claim_versions_no_update;")?;
+            for (row_id, claim_id, version, value, metadata) in plaintext {
+                self.conn.execute(
+                    "UPDATE claim_versions SET value = ?3, metadata = ?4 WHERE claim_id = ?1 AND version = ?2",
+                    params![
+                        claim_id,
+                        version,
+                        key.seal_field(&field_aad("claim_versions.value", &row_id), &value)?,
+                        key.seal_field(&field_aad("claim_versions.metadata", &row_id), &metadata)?
+                    ],
+                )?;
+            }
+            self.conn.execute_batch(migrations::CLAIM_VERSIONS_NO_UPDATE)?;
+        }
+
+        let mut stmt = self.conn.prepare(
+            "SELECT commitment_id, position, canonical_claim FROM commitment_leaves"
+        )?;
+        let rows = stmt
+            .query_map([], |row| {
+                Ok((row.get::<_, String>(0)?, row.get::<_, i64>(1)?, row.get::<_, String>(2)?))
+            })?
+            .collect::<std::result::Result<Vec<_>, _>>()?;
+        for (commitment_id, position, canonical) in rows.into_iter().filter(|row| !vault::is_sealed_field(&row.2)) {
+            let row_id = format!("{}/{}", commitment_id, position);
+            self.conn.execute(
+                "UPDATE commitment_leaves SET canonical_claim = ?3 WHERE commitment_id = ?1 AND position = ?2",
+                params![
+                    commitment_id,
+                    position,
+                    key.seal_field(&field_aad("commitment_leaves.canonical_claim", &row_id), &canonical)?
+                ],
+            )?;
+        }
+
+        Ok(())
+    }
+
+    // === Audit log operations ===
+
+    /// Appends an entry chained to the current head.
+    pub fn append_audit(&self, record: &AuditRecord) -> Result<AuditEntry> {
+        let (seq, prev_hash) = match self.audit_head()? {
+            Some((seq, hash)) => (seq + 1, hash),
+            None => (1, audit::GENESIS_HASH.to_string()),
+        };
+        let recorded_at = Utc::now().to_rfc3339();
+        let entry_hash = audit::entry_hash(&prev_hash, seq, record, &recorded_at);
+        self.conn.execute(
+            r#"
+            INSERT INTO audit_log (seq, command, entity_type, entity_id, before_hash,
+                after_hash, actor, recorded_at, prev_hash, entry_hash)
+            VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10)
+            "#,
+            params![
+                seq as i64,
+                record.command,
+                record.entity_type,
+                record.entity_id,
+                record.before_hash,
+                record.after_hash,
+                record.actor,
+                recorded_at,
+                prev_hash,
+                entry_hash
+            ],
+        )?;
+        Ok(AuditEntry {
+            seq,
+            command: record.command.clone(),
+            entity_type: record.entity_type.clone(),
+            entity_id: record.entity_id.clone(),
+            before_hash: record.before_hash.clone(),
+            after_hash: record.after_hash.clone(),
+            actor: record.actor.clone(),
+            recorded_at,
+            prev_hash,
+            entry_hash,
+        })
+    }
+
+    /// Sequence number and hash of the latest entry
+    pub fn audit_head(&self) -> Result<Option<(u64, String)>> {
+        let result = self.conn.query_row(
+            "SELECT seq, entry_hash FROM audit_log ORDER BY seq DESC LIMIT 1",
+            [],
+            |row| Ok((row.get::<_, i64>(0)? as u64, row.get(1)?)),
+        );
+
+        match result {
+            Ok(head) => Ok(Some(head)),
+            Err(rusqlite::Error::QueryReturnedNoRows) => Ok(None),
+            Err(e) => Err(e.into()),
+        }
+    }
+
+    pub fn list_audit_entries(&self) -> Result<Vec<AuditEntry>> {
+        let mut stmt = self.conn.prepare(
+            "SELECT seq, command, entity_type, entity_id, before_hash, after_hash, actor,
+                    recorded_at, prev_hash, entry_hash
+             FROM audit_log ORDER BY seq"
+        )?;
+        let rows = stmt.query_map([], |row| {
+            Ok(AuditEntry {
+                seq: row.get::<_, i64>(0)? as u64,
+                command: row.get(1)?,
+                entity_type: row.get(2)?,
+                entity_id: row.get(3)?,
+                before_hash: row.get(4)?,
+                after_hash: row.get(5)?,
+                actor: row.get(6)?,
+                recorded_at: row.get(7)?,
+                prev_hash: row.get(8)?,
+                entry_hash: row.get(9)?,
+            })
+        })?;
+        rows.collect::<std::result::Result<Vec<_>, _>>()
+            .context("Failed to load audit log")
+    }
+
+    pub fn insert_signed_audit_head(&self, head: &SignedAuditHead) -> Result<()> {
+        self.conn.execute(
+            r#"
+            INSERT INTO audit_heads (seq, entry_hash, public_key, signature, signed_at)
+            VALUES (?1, ?2, ?3, ?4, ?5)
+            "#,
+            params![
+                head.seq as i64,
+                head.entry_hash,
+                head.public_key,
+                head.signature,
+                head.signed_at.to_rfc3339()
+            ],
+        )?;
+        Ok(())
+    }
+
+    /// Signed chain heads, oldest first
+    pub fn list_signed_audit_heads(&self) -> Result<Vec<SignedAuditHead>> {
+        let mut stmt = self.conn.prepare(
+            "SELECT seq, entry_hash, public_key, signature, signed_at FROM audit_heads ORDER BY seq"
+        )?;
+        let rows = stmt.query_map([], signed_audit_head_from_row)?;
+        rows.collect::<std::result::Result<Vec<_>, _>>()
+            .context("Failed to load signed audit heads")
+    }
+
+    /// The most recently signed head, without loading the others
+    pub fn latest_signed_audit_head(&self) -> Result<Option<SignedAuditHead>> {
+        let result = self.conn.query_row(
+            "SELECT seq, entry_hash, public_key, signature, signed_at FROM audit_heads
+             ORDER BY seq DESC LIMIT 1",
+            [],
+            signed_audit_head_from_row,
+        );
+
+        match result {
+            Ok(head) => Ok(Some(head)),
+            Err(rusqlite::Error::QueryReturnedNoRows) => Ok(None),
+            Err(e) => Err(e.into()),
+        }
+    }
+
+    // === Vault operations ===
+
+    pub fn get_vault_record(&self) -> Result<Option<VaultRecord>> {
+        let result = self.conn.query_row(
+            "SELECT salt, m_cost, t_cost, p_cost, wrapped_key, created_at, updated_at, fields_sealed_at
+             FROM vault WHERE id = 1",
+            [],
+            |row| {
+                Ok(VaultRecord {
+                    salt: row.get(0)?,
+                    params: vault::KdfParams {
+                        m_cost: row.get(1)?,
+                        t_cost: row.get(2)?,
+                        p_cost: row.get(3)?,
+                    },
+                    wrapped_key: row.get(4)?,
+                    created_at: DateTime::parse_from_rfc3339(&row.get::<_, String>(5)?)
+                        .map(|d| d.with_timezone(&Utc))
+                        .unwrap_or_else(|_| Utc::now()),
+                    updated_at: DateTime::parse_from_rfc3339(&row.get::<_, String>(6)?)
+                        .map(|d| d.with_timezone(&Utc))
+                        .unwrap_or_else(|_| Utc::now()),
+                    fields_sealed_at: row
+                        .get::<_, Option<String>>(7)?
+                        .and_then(|d| DateTime::parse_from_rfc3339(&d).ok())
+                        .map(|d| d.with_timezone(&Utc)),
+                })
+            },
+        );
+
+        match result {
+            Ok(record) => Ok(Some(record)),
+            Err(rusqlite::Error::QueryReturnedNoRows) => Ok(None),
+            Err(e) => Err(e.into()),
+        }
+    }
+
+    pub fn insert_vault_record(&self, record: &VaultRecord) -> Result<()> {
+        self.conn.execute(
+            r#"
+            INSERT INTO vault (id, kdf, salt, m_cost, t_cost, p_cost, wrapped_key, created_at, updated_at,
+                fields_sealed_at)
+            VALUES (1, 'argon2id', ?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8)
+            "#,
+            params![
+                record.salt,
+                record.params.m_cost,
+                record.params.t_cost,
+                record.params.p_cost,
+                record.wrapped_key,
+                record.created_at.to_rfc3339(),
+                record.updated_at.to_rfc3339(),
+                record.fields_sealed_at.map(|d| d.to_rfc3339())
+            ],
+        )?;
+        Ok(())
+    }
+
+    pub fn update_vault_record(&self, record: &VaultRecord) -> Result<()> {
+        self.conn.execute(
+            r#"
+            UPDATE vault SET salt = ?1, m_cost = ?2, t_cost = ?3, p_cost = ?4,
+                wrapped_key = ?5, updated_at = ?6, fields_sealed_at = ?7
+            WHERE id = 1
+            "#,
+            params![
+                record.salt,
+                record.params.m_cost,
+                record.params.t_cost,
+                record.params.p_cost,
+                record.wrapped_key,
+                record.updated_at.to_rfc3339(),
+                record.fields_sealed_at.map(|d| d.to_rfc3339())
+            ],
+        )?;
+        Ok(())
+    }
+
+    // === Recovery seed operations ===
+
+    pub fn get_recovery_seed(&self) -> Result<Option<RecoverySeedRecord>> {
+        let result = self.conn.query_row(
+            "SELECT sealed_seed, fingerprint, created_at FROM recovery_seed WHERE id = 1",
+            [],
+            |row| {
+                Ok(RecoverySeedRecord {
+                    sealed_seed: row.get(0)?,
+                    fingerprint: row.get(1)?,
+                    created_at: DateTime::parse_from_rfc3339(&row.get::<_, String>(2)?)
+                        .map(|d| d.with_timezone(&Utc))
+                        .unwrap_or_else(|_| Utc::now()),
+                })
+            },
+        );
+
+        match result {
+            Ok(record) => Ok(Some(record)),
+            Err(rusqlite::Error::QueryReturnedNoRows) => Ok(None),
+            Err(e) => Err(e.into()),
+        }
+    }
+
+    pub fn insert_recovery_seed(&self, record: &RecoverySeedRecord) -> Result<()> {
+        self.conn.execute(
+            "INSERT INTO recovery_seed (id, sealed_seed, fingerprint, created_at) VALUES (1, ?1, ?2, ?3)",
+            params![record.sealed_seed, record.fingerprint, record.created_at.to_rfc3339()],
+        )?;
+        Ok(())
+    }
+
     // === Settings operations ===
 
     pub fn get_setting(&self, key: &str) -> Result<Option<String>> {
@@ -661,3 +1786,215 @@ impl Database {
         Ok(())
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::time::Duration;
+
+    fn locked_vault() -> Arc<Vault> {
+        Arc::new(Vault::new(Duration::from_secs(60)))
+    }
+
+    fn test_db() -> Database {
+        let vault = locked_vault();
+        let db = Database::in_memory(Arc::clone(&vault)).unwrap();
+        vault.initialize(&db, "correct horse battery staple", vault::TEST_KDF_PARAMS).unwrap();
+        db
+    }
+
+    fn evidence(id: &str) -> Evidence {
+        Evidence {
+            id: id.to_string(),
+            evidence_type: "lab_report".to_string(),
+            original_filename: None,
+            mime_type: None,
+            content_hash: "00".repeat(32),
+            extracted_text: None,
+            issuer_name: None,
+            issuer_type: None,
+            valid_from: None,
+            valid_until: None,
+            created_at: Utc::now(),
+            shredded_at: None,
+        }
+    }
+
+    fn claim(id: &str, evidence_ids: &[&str]) -> Claim {
+        Claim {
+            id: id.to_string(),
+            claim_type: "recycled_content".to_string(),
+            value: serde_json::json!(25),
+            unit: "percent".to_string(),
+            product_id: "battery-001".to_string(),
+            evidence_ids: evidence_ids.iter().map(|id| id.to_string()).collect(),
+            confidence: None,
+            verified: true,
+            metadata: serde_json::json!({}),
+            created_at: Utc::now(),
+            updated_at: Utc::now(),
+        }
+    }
+
+    fn commitment(id: &str, claim_ids: &[&str]) -> Commitment {
+        Commitment {
+            id: id.to_string(),
+            root: "00".repeat(32),
+            claim_count: claim_ids.len(),
+            claim_ids: claim_ids.iter().map(|id| id.to_string()).collect(),
+            public_key: String::new(),
+            signature: String::new(),
+            valid_from: None,
+            valid_until: None,
+            revoked: false,
+            revoked_at: None,
+            revoked_reason: None,
+            created_at: Utc::now(),
+            revocation: None,
+        }
+    }
+
+    #[test]
+    fn test_open_persists_at_path() {
+        let dir = std::env::temp_dir().join(format!("edge-agent-open-{}", Uuid::new_v4()));
+        let path = dir.join("profile-a").join(DB_FILE_NAME);
+
+        let db = Database::open(&path, locked_vault()).unwrap();
+        db.set_setting("profile", "a").unwrap();
+        drop(db);
+
+        let db = Database::open(&path, locked_vault()).unwrap();
+        assert_eq!(db.get_setting("profile").unwrap().as_deref(), Some("a"));
+        assert!(Database::in_memory(locked_vault()).unwrap().get_setting("profile").unwrap().is_none());
+
+        drop(db);
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn test_atomic_rolls_back_on_error() {
+        let db = test_db();
+        let failed: Result<()> = db.atomic(|| {
+            db.set_setting("supplier_id", "acme")?;
+            db.atomic(|| db.insert_claim(&claim("claim-1", &[])))?;
+            Err(anyhow::anyhow!("audit append failed"))
+        });
+        assert!(failed.is_err());
+        assert!(db.get_setting("supplier_id").unwrap().is_none());
+        assert!(db.get_claim("claim-1").unwrap().is_none());
+
+        db.atomic(|| db.set_setting("supplier_id", "acme")).unwrap();
+        assert_eq!(db.get_setting("supplier_id").unwrap().as_deref(), Some("acme"));
+    }
+
+    #[test]
+    fn test_evidence_in_use_cannot_be_deleted() {
+        let db = test_db();
+        db.insert_evidence(&evidence("ev-1"), None).unwrap();
+        db.insert_evidence(&evidence("ev-2"), None).unwrap();
+        db.insert_claim(&claim("claim-1", &["ev-1"])).unwrap();
+        db.insert_commitment(&commitment("commitment-1", &["claim-1"])).unwrap();
+
+        let dependents = db.get_evidence_dependents("ev-1").unwrap();
+        assert_eq!(dependents.claim_ids, vec!["claim-1"]);
+        assert_eq!(dependents.commitment_ids, vec!["commitment-1"]);
+
+        assert!(db.delete_evidence("ev-1").is_err());
+        assert!(db.get_evidence("ev-1").unwrap().is_some());
+        assert!(db.delete_evidence("ev-2").unwrap());
+    }
+
+    #[test]
+    fn test_shredded_evidence_cannot_be_opened() {
+        let db = test_db();
+        let mut ev = evidence("ev-1");
+        ev.extracted_text = Some("Recycled cobalt: 25%".to_string());
+        db.insert_evidence(&ev, Some(b"lab report")).unwrap();
+        db.insert_evidence(&evidence("ev-2"), Some(b"other report")).unwrap();
+        db.insert_claim(&claim("claim-1", &["ev-1"])).unwrap();
+
+        let (wrapped_key, blob): (Vec<u8>, Vec<u8>) = db.conn.query_row(
+            "SELECT wrapped_key, raw_content FROM evidence WHERE id = 'ev-1'",
+            [],
+            |row| Ok((row.get(0)?, row.get(1)?)),
+        ).unwrap();
+        assert_eq!(db.get_evidence_content("ev-1").unwrap().unwrap(), b"lab report");
+        // Each record has its own key; the vault key alone opens nothing
+        assert!(db.vault.key().unwrap().open_blob(&blob).is_err());
+
+        // Referenced evidence is shredded in place and keeps its hash
+        assert!(db.shred_evidence("ev-1").unwrap());
+        assert!(!db.shred_evidence("ev-1").unwrap());
+        let shredded = db.get_evidence("ev-1").unwrap().unwrap();
+        assert!(shredded.shredded_at.is_some());
+        assert!(shredded.extracted_text.is_none());
+        assert_eq!(shredded.content_hash, ev.content_hash);
+        assert!(db.get_evidence_content("ev-1").unwrap().is_none());
+
+        // A copy of the old ciphertext cannot be restored without the key
+        db.conn.execute("UPDATE evidence SET raw_content = ?1 WHERE id = 'ev-1'", [&blob]).unwrap();
+        assert!(db.get_evidence_content("ev-1").is_err());
+        let vault_key = db.vault.key().unwrap();
+        let evidence_key = vault_key.unwrap_subkey("evidence.wrapped_key/ev-1", &wrapped_key).unwrap();
+        assert!(vault_key.unwrap_subkey("evidence.wrapped_key/ev-2", &wrapped_key).is_err());
+        assert_eq!(evidence_key.open_blob(&blob).unwrap(), b"lab report");
+
+        assert!(db.delete_evidence("ev-2").unwrap());
+        assert!(db.get_evidence("ev-2").unwrap().is_none());
+    }
+
+    #[test]
+    fn test_dangling_references_rejected() {
+        let db = test_db();
+        assert!(db.insert_claim(&claim("claim-1", &["missing"])).is_err());
+        assert!(db.get_claim("claim-1").unwrap().is_none());
+        assert!(db.insert_commitment(&commitment("commitment-1", &["missing"])).is_err());
+    }
+
+    #[test]
+    fn test_json_references_migrated() {
+        let db = test_db();
+        db.insert_evidence(&evidence("ev-1"), None).unwrap();
+        db.insert_claim(&claim("claim-1", &["ev-1"])).unwrap();
+        db.conn.execute("DELETE FROM claim_evidence", []).unwrap();
+
+        db.conn.pragma_update(None, "user_version", 4).unwrap();
+        migrations::run(&db.conn, None).unwrap();
+        assert_eq!(db.get_evidence_dependents("ev-1").unwrap().claim_ids, vec!["claim-1"]);
+    }
+
+    #[test]
+    fn test_sensitive_fields_sealed_per_row() {
+        let db = test_db();
+        let mut ev = evidence("ev-1");
+        ev.extracted_text = Some("Recycled cobalt: 25%".to_string());
+        db.insert_evidence(&ev, None).unwrap();
+        let mut first = claim("claim-1", &["ev-1"]);
+        first.metadata = serde_json::json!({"page": 3});
+        db.insert_claim(&first).unwrap();
+        db.insert_claim(&claim("claim-2", &["ev-1"])).unwrap();
+
+        let (value, metadata, product_id): (String, String, String) = db.conn.query_row(
+            "SELECT value, metadata, product_id FROM claims WHERE id = 'claim-1'",
+            [],
+            |row| Ok((row.get(0)?, row.get(1)?, row.get(2)?)),
+        ).unwrap();
+        assert!(vault::is_sealed_field(&value));
+        assert!(vault::is_sealed_field(&metadata));
+        assert_eq!(product_id, "battery-001");
+        assert_eq!(db.list_claims(Some("battery-001")).unwrap().len(), 2);
+        assert_eq!(db.get_claim("claim-1").unwrap().unwrap().metadata, serde_json::json!({"page": 3}));
+        assert_eq!(
+            db.get_evidence("ev-1").unwrap().unwrap().extracted_text.as_deref(),
+            Some("Recycled cobalt: 25%")
+        );
+
+        // A sealed value moved to another row no longer decrypts
+        db.conn.execute("UPDATE claims SET value = ?1 WHERE id = 'claim-2'", [&value]).unwrap();
+        assert!(db.get_claim("claim-2").is_err());
+
+        db.vault.lock().unwrap();
+        assert!(db.list_claims(None).is_err());
+        assert!(db.get_evidence_dependents("ev-1").is_ok());
+    }
+}
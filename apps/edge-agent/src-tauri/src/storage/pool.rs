@@ -0,0 +1,103 @@
+//! Connection pool for the Edge Agent database
+//!
+//! One writer connection plus a few read-only connections. With the database
+//! in WAL mode, readers see the last committed state while the writer is busy,
+//! so listing evidence or claims does not wait behind a proof or an import.
+//! Handlers should hold a guard only for the queries themselves and drop it
+//! before any long-running work.
+
+use super::{Database, Vault};
+use anyhow::{anyhow, Result};
+use std::path::Path;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::{Arc, Mutex, MutexGuard, TryLockError};
+
+/// Read-only connections opened next to the writer
+pub const DEFAULT_READERS: usize = 4;
+
+pub struct DbPool {
+    writer: Mutex<Database>,
+    readers: Vec<Mutex<Database>>,
+    next_reader: AtomicUsize,
+}
+
+impl DbPool {
+    /// Opens the database at `path` with `readers` read-only connections,
+    /// all sealing and opening sensitive columns through `vault`
+    pub fn open(path: &Path, readers: usize, vault: Arc<Vault>) -> Result<Self> {
+        let writer = Database::open(path, Arc::clone(&vault))?;
+        let readers = (0..readers)
+            .map(|_| Database::open_reader(path, Arc::clone(&vault)).map(Mutex::new))
+            .collect::<Result<Vec<_>>>()?;
+        Ok(DbPool {
+            writer: Mutex::new(writer),
+            readers,
+            next_reader: AtomicUsize::new(0),
+        })
+    }
+
+    /// In-memory databases cannot be shared across connections, so reads
+    /// and writes both use the single connection
+    pub fn in_memory(vault: Arc<Vault>) -> Result<Self> {
+        Ok(DbPool {
+            writer: Mutex::new(Database::in_memory(vault)?),
+            readers: Vec::new(),
+            next_reader: AtomicUsize::new(0),
+        })
+    }
+
+    /// A connection for queries; prefers an idle reader
+    pub fn read(&self) -> Result<MutexGuard<'_, Database>> {
+        if self.readers.is_empty() {
+            return self.write();
+        }
+        for reader in &self.readers {
+            match reader.try_lock() {
+                Ok(guard) => return Ok(guard),
+                Err(TryLockError::WouldBlock) | Err(TryLockError::Poisoned(_)) => continue,
+            }
+        }
+        let index = self.next_reader.fetch_add(1, Ordering::Relaxed) % self.readers.len();
+        lock(&self.readers[index])
+    }
+
+    /// The writer connection; writes are serialized through it
+    pub fn write(&self) -> Result<MutexGuard<'_, Database>> {
+        lock(&self.writer)
+    }
+}
+
+fn lock(conn: &Mutex<Database>) -> Result<MutexGuard<'_, Database>> {
+    conn.lock().map_err(|_| anyhow!("Database connection lock poisoned"))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::storage::vault::TEST_KDF_PARAMS;
+    use std::time::Duration;
+
+    #[test]
+    fn test_readers_proceed_while_writer_busy() {
+        let dir = std::env::temp_dir().join(format!("edge-agent-pool-{}", uuid::Uuid::new_v4()));
+        let vault = Arc::new(Vault::new(Duration::from_secs(60)));
+        let pool = DbPool::open(&dir.join(super::super::DB_FILE_NAME), 2, Arc::clone(&vault)).unwrap();
+
+        let writer = pool.write().unwrap();
+        vault.initialize(&writer, "correct horse battery staple", TEST_KDF_PARAMS).unwrap();
+        writer.set_setting("supplier_id", "supplier-1").unwrap();
+        let mode: String = writer.conn.query_row("PRAGMA journal_mode", [], |row| row.get(0)).unwrap();
+        assert_eq!(mode, "wal");
+
+        // The writer guard is still held
+        let reader = pool.read().unwrap();
+        assert_eq!(reader.get_setting("supplier_id").unwrap().as_deref(), Some("supplier-1"));
+        assert!(reader.set_setting("supplier_id", "other").is_err());
+        let second = pool.read().unwrap();
+        assert!(second.list_claims(None).unwrap().is_empty());
+
+        drop((writer, reader, second));
+        drop(pool);
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+}
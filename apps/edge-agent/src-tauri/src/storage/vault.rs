@@ -0,0 +1,599 @@
+//! Passphrase-protected vault for data at rest
+//!
+//! A random 256-bit data key encrypts signing keys, the per-evidence keys that
+//! seal each document and its extracted text, and the sensitive columns of
+//! claims and commitment snapshots. Only a
+//! wrapped copy of the data key is stored, sealed with AES-256-GCM under a key
+//! derived from the user's passphrase with Argon2id, so changing the
+//! passphrase rewraps the data key without touching the data. The unwrapped
+//! key lives in memory while the vault is unlocked and is dropped on lock or
+//! once the vault has been idle for the auto-lock period. Keys and decrypted
+//! secrets are wiped from memory when dropped.
+
+use super::Database;
+use aes_gcm::aead::{Aead, Payload, rand_core::RngCore};
+use aes_gcm::{Aes256Gcm, KeyInit, Nonce};
+use anyhow::{anyhow, Context, Result};
+use argon2::{Algorithm, Argon2, Params, Version};
+use base64::{engine::general_purpose::STANDARD as BASE64, Engine};
+use chrono::{DateTime, Utc};
+use crypto::{ExposeSecret, KeyPair, SecretVec};
+use rand::rngs::OsRng;
+use serde::{Deserialize, Serialize};
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+use zeroize::{Zeroize, Zeroizing};
+
+/// Secret keys sealed with the vault data key
+const SECRET_PREFIX: &str = "vault:";
+/// Evidence blobs sealed with a vault-held key
+const BLOB_PREFIX: &[u8; 4] = b"VLT1";
+/// Text columns sealed with a vault-held key
+const FIELD_PREFIX: &str = "fv1:";
+/// Pre-vault formats, migrated when the vault is first unlocked
+const LEGACY_SECRET_PREFIX_ENC: &str = "enc:";
+const LEGACY_SECRET_PREFIX_RAW: &str = "raw:";
+const LEGACY_BLOB_PREFIX: &[u8; 4] = b"ENC1";
+
+const WRAP_AAD: &[u8] = b"zkdpp-edge-agent vault data key v1";
+const NONCE_LEN: usize = 12;
+const SALT_LEN: usize = 16;
+pub const MIN_PASSPHRASE_LEN: usize = 12;
+pub const DEFAULT_AUTO_LOCK_SECS: u64 = 15 * 60;
+
+/// Argon2id cost parameters (memory in KiB)
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub struct KdfParams {
+    pub m_cost: u32,
+    pub t_cost: u32,
+    pub p_cost: u32,
+}
+
+impl Default for KdfParams {
+    fn default() -> Self {
+        KdfParams { m_cost: 64 * 1024, t_cost: 3, p_cost: 1 }
+    }
+}
+
+/// Minimum Argon2 costs, so tests do not spend seconds deriving keys
+#[cfg(test)]
+pub const TEST_KDF_PARAMS: KdfParams = KdfParams { m_cost: 8, t_cost: 1, p_cost: 1 };
+
+/// Stored vault header: KDF salt and parameters plus the wrapped data key
+#[derive(Debug, Clone)]
+pub struct VaultRecord {
+    pub salt: Vec<u8>,
+    pub params: KdfParams,
+    pub wrapped_key: Vec<u8>,
+    pub created_at: DateTime<Utc>,
+    pub updated_at: DateTime<Utc>,
+    /// When legacy plaintext columns were sealed; unset until the first unlock
+    pub fields_sealed_at: Option<DateTime<Utc>>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct VaultStatus {
+    pub initialized: bool,
+    pub unlocked: bool,
+    pub auto_lock_secs: u64,
+}
+
+/// The unwrapped data key, wiped on drop
+#[derive(Clone)]
+pub struct DataKey([u8; 32]);
+
+impl Drop for DataKey {
+    fn drop(&mut self) {
+        self.0.zeroize();
+    }
+}
+
+impl DataKey {
+    /// A fresh random key
+    pub fn generate() -> Self {
+        let mut key = [0u8; 32];
+        OsRng.fill_bytes(&mut key);
+        DataKey(key)
+    }
+
+    /// Wraps a subordinate key (such as a per-evidence key), bound to `aad`
+    pub fn wrap_subkey(&self, aad: &str, key: &DataKey) -> Result<Vec<u8>> {
+        encrypt_bytes(&self.0, &key.0, aad.as_bytes())
+    }
+
+    pub fn unwrap_subkey(&self, aad: &str, wrapped: &[u8]) -> Result<DataKey> {
+        let key = Zeroizing::new(
+            decrypt_bytes(&self.0, wrapped, aad.as_bytes()).with_context(|| format!("Could not unwrap {}", aad))?,
+        );
+        DataKey::from_slice(&key).context("Wrapped key has the wrong length")
+    }
+
+    fn from_slice(bytes: &[u8]) -> Result<DataKey> {
+        if bytes.len() != 32 {
+            return Err(anyhow!("Expected a 32-byte key, got {} bytes", bytes.len()));
+        }
+        let mut key = [0u8; 32];
+        key.copy_from_slice(bytes);
+        Ok(DataKey(key))
+    }
+
+    pub fn encrypt_secret(&self, secret: &[u8]) -> Result<String> {
+        Ok(format!("{}{}", SECRET_PREFIX, BASE64.encode(encrypt_bytes(&self.0, secret, b"")?)))
+    }
+
+    pub fn decrypt_secret(&self, encoded: &str) -> Result<SecretVec> {
+        let rest = encoded
+            .strip_prefix(SECRET_PREFIX)
+            .ok_or_else(|| anyhow!("Key is not sealed by the vault"))?;
+        let bytes = BASE64.decode(rest).context("Invalid sealed key data")?;
+        Ok(SecretVec::new(decrypt_bytes(&self.0, &bytes, b"")?))
+    }
+
+    /// Seals a signing key for the `keypairs` table
+    pub fn encrypt_keypair(&self, keypair: &KeyPair) -> Result<String> {
+        self.encrypt_secret(keypair.secret_bytes().expose_secret())
+    }
+
+    /// Opens a signing key sealed with [`DataKey::encrypt_keypair`]
+    pub fn decrypt_keypair(&self, encoded: &str) -> Result<KeyPair> {
+        Ok(KeyPair::from_bytes(self.decrypt_secret(encoded)?.expose_secret())?)
+    }
+
+    pub fn seal_blob(&self, raw: &[u8]) -> Result<Vec<u8>> {
+        let encrypted = encrypt_bytes(&self.0, raw, b"")?;
+        let mut out = Vec::with_capacity(BLOB_PREFIX.len() + encrypted.len());
+        out.extend_from_slice(BLOB_PREFIX);
+        out.extend_from_slice(&encrypted);
+        Ok(out)
+    }
+
+    pub fn open_blob(&self, sealed: &[u8]) -> Result<Vec<u8>> {
+        let rest = sealed
+            .strip_prefix(BLOB_PREFIX.as_slice())
+            .ok_or_else(|| anyhow!("Evidence blob is not sealed by the vault"))?;
+        decrypt_bytes(&self.0, rest, b"")
+    }
+
+    /// Encrypts a column value; `aad` names the column and row so a sealed
+    /// value cannot be moved to another row
+    pub fn seal_field(&self, aad: &str, value: &str) -> Result<String> {
+        let encrypted = encrypt_bytes(&self.0, value.as_bytes(), aad.as_bytes())?;
+        Ok(format!("{}{}", FIELD_PREFIX, BASE64.encode(encrypted)))
+    }
+
+    /// Decrypts a column value. Plaintext is sealed when the vault is first
+    /// unlocked, so an unsealed value here has been written behind the
+    /// vault's back and is rejected.
+    pub fn open_field(&self, aad: &str, stored: &str) -> Result<String> {
+        let rest = stored
+            .strip_prefix(FIELD_PREFIX)
+            .ok_or_else(|| anyhow!("{} is not sealed by the vault", aad))?;
+        let bytes = BASE64.decode(rest).context("Invalid sealed field data")?;
+        let plaintext = decrypt_bytes(&self.0, &bytes, aad.as_bytes())
+            .with_context(|| format!("Could not decrypt {}", aad))?;
+        String::from_utf8(plaintext).context("Sealed field is not UTF-8")
+    }
+
+    /// Like [`DataKey::open_field`], but returns values written before field
+    /// encryption as stored; only for migrating them
+    pub fn open_legacy_field(&self, aad: &str, stored: &str) -> Result<String> {
+        if is_sealed_field(stored) {
+            self.open_field(aad, stored)
+        } else {
+            Ok(stored.to_string())
+        }
+    }
+}
+
+/// Whether a stored column value is sealed (rather than legacy plaintext)
+pub fn is_sealed_field(stored: &str) -> bool {
+    stored.starts_with(FIELD_PREFIX)
+}
+
+struct VaultState {
+    key: Option<DataKey>,
+    last_used: Instant,
+    auto_lock: Duration,
+}
+
+/// In-memory unlock state of the vault
+pub struct Vault {
+    state: Mutex<VaultState>,
+}
+
+impl Vault {
+    pub fn new(auto_lock: Duration) -> Self {
+        Vault {
+            state: Mutex::new(VaultState { key: None, last_used: Instant::now(), auto_lock }),
+        }
+    }
+
+    pub fn status(&self, db: &Database) -> Result<VaultStatus> {
+        let initialized = db.get_vault_record()?.is_some();
+        let state = self.lock_state()?;
+        Ok(VaultStatus {
+            initialized,
+            unlocked: state.key.is_some(),
+            auto_lock_secs: state.auto_lock.as_secs(),
+        })
+    }
+
+    /// Creates the vault, seals any pre-vault keys and evidence with the new
+    /// data key, and leaves the vault unlocked.
+    pub fn initialize(&self, db: &Database, passphrase: &str, params: KdfParams) -> Result<()> {
+        if db.get_vault_record()?.is_some() {
+            return Err(anyhow!("Vault is already set up"));
+        }
+        check_passphrase(passphrase)?;
+
+        let key = DataKey::generate();
+        let now = Utc::now();
+        let (salt, wrapped_key) = wrap_key(&key, passphrase, params)?;
+        let record = VaultRecord { salt, params, wrapped_key, created_at: now, updated_at: now, fields_sealed_at: None };
+
+        db.atomic(|| {
+            db.insert_vault_record(&record)?;
+            migrate_legacy(db, &key)?;
+            Ok(())
+        })?;
+
+        self.set_key(key)
+    }
+
+    pub fn unlock(&self, db: &Database, passphrase: &str) -> Result<()> {
+        let record = db.get_vault_record()?.ok_or_else(|| anyhow!("Vault is not set up"))?;
+        let key = unwrap_key(&record, passphrase)?;
+
+        db.atomic(|| migrate_legacy(db, &key))?;
+
+        self.set_key(key)
+    }
+
+    pub fn lock(&self) -> Result<()> {
+        self.lock_state()?.key = None;
+        Ok(())
+    }
+
+    /// Rewraps the data key under a new passphrase; stored data is unchanged.
+    pub fn change_passphrase(&self, db: &Database, current: &str, new: &str) -> Result<()> {
+        let record = db.get_vault_record()?.ok_or_else(|| anyhow!("Vault is not set up"))?;
+        let key = unwrap_key(&record, current)?;
+        check_passphrase(new)?;
+
+        let (salt, wrapped_key) = wrap_key(&key, new, record.params)?;
+        db.update_vault_record(&VaultRecord { salt, wrapped_key, updated_at: Utc::now(), ..record })?;
+        self.set_key(key)
+    }
+
+    /// The data key, if the vault is unlocked and has not idled out
+    pub fn key(&self) -> Result<DataKey> {
+        let mut state = self.lock_state()?;
+        expire_if_idle(&mut state);
+        let key = state.key.clone().ok_or_else(|| anyhow!("Vault is locked"))?;
+        state.last_used = Instant::now();
+        Ok(key)
+    }
+
+    /// Drops the data key once the vault has been idle for the auto-lock period
+    pub fn lock_if_idle(&self) -> Result<()> {
+        expire_if_idle(&mut *self.lock_state()?);
+        Ok(())
+    }
+
+    pub fn set_auto_lock(&self, auto_lock: Duration) -> Result<()> {
+        self.lock_state()?.auto_lock = auto_lock;
+        Ok(())
+    }
+
+    fn set_key(&self, key: DataKey) -> Result<()> {
+        let mut state = self.lock_state()?;
+        state.key = Some(key);
+        state.last_used = Instant::now();
+        Ok(())
+    }
+
+    fn lock_state(&self) -> Result<std::sync::MutexGuard<'_, VaultState>> {
+        self.state.lock().map_err(|_| anyhow!("Vault lock poisoned"))
+    }
+}
+
+fn expire_if_idle(state: &mut VaultState) {
+    if state.key.is_some() && state.last_used.elapsed() >= state.auto_lock {
+        state.key = None;
+    }
+}
+
+pub fn check_passphrase(passphrase: &str) -> Result<()> {
+    if passphrase.chars().count() < MIN_PASSPHRASE_LEN {
+        return Err(anyhow!("Passphrase must be at least {} characters", MIN_PASSPHRASE_LEN));
+    }
+    Ok(())
+}
+
+fn derive_wrapping_key(passphrase: &str, salt: &[u8], params: KdfParams) -> Result<Zeroizing<[u8; 32]>> {
+    // Same ceiling as imported keystores, so a tampered header cannot make
+    // unlocking exhaust memory or hash for hours
+    let max = crypto::KeystoreParams::MAX;
+    if params.m_cost > max.m_cost || params.t_cost > max.t_cost || params.p_cost > max.p_cost {
+        return Err(anyhow!(
+            "KDF parameters m={} t={} p={} exceed the limits m={} t={} p={}",
+            params.m_cost, params.t_cost, params.p_cost, max.m_cost, max.t_cost, max.p_cost
+        ));
+    }
+    let params = Params::new(params.m_cost, params.t_cost, params.p_cost, Some(32))
+        .map_err(|e| anyhow!("Invalid KDF parameters: {}", e))?;
+    let mut out = Zeroizing::new([0u8; 32]);
+    Argon2::new(Algorithm::Argon2id, Version::V0x13, params)
+        .hash_password_into(passphrase.as_bytes(), salt, out.as_mut_slice())
+        .map_err(|e| anyhow!("Key derivation failed: {}", e))?;
+    Ok(out)
+}
+
+fn wrap_key(key: &DataKey, passphrase: &str, params: KdfParams) -> Result<(Vec<u8>, Vec<u8>)> {
+    let mut salt = vec![0u8; SALT_LEN];
+    OsRng.fill_bytes(&mut salt);
+    let wrapping_key = derive_wrapping_key(passphrase, &salt, params)?;
+
+    let cipher = Aes256Gcm::new_from_slice(wrapping_key.as_slice()).map_err(|_| anyhow!("Invalid wrapping key"))?;
+    let mut nonce_bytes = [0u8; NONCE_LEN];
+    OsRng.fill_bytes(&mut nonce_bytes);
+    let sealed = cipher
+        .encrypt(Nonce::from_slice(&nonce_bytes), Payload { msg: &key.0, aad: WRAP_AAD })
+        .map_err(|_| anyhow!("Failed to wrap data key"))?;
+    let mut wrapped = nonce_bytes.to_vec();
+    wrapped.extend_from_slice(&sealed);
+    Ok((salt, wrapped))
+}
+
+fn unwrap_key(record: &VaultRecord, passphrase: &str) -> Result<DataKey> {
+    if record.wrapped_key.len() < NONCE_LEN {
+        return Err(anyhow!("Stored data key is corrupt"));
+    }
+    let wrapping_key = derive_wrapping_key(passphrase, &record.salt, record.params)?;
+    let cipher = Aes256Gcm::new_from_slice(wrapping_key.as_slice()).map_err(|_| anyhow!("Invalid wrapping key"))?;
+    let (nonce_bytes, sealed) = record.wrapped_key.split_at(NONCE_LEN);
+    let key = cipher
+        .decrypt(Nonce::from_slice(nonce_bytes), Payload { msg: sealed, aad: WRAP_AAD })
+        .map(Zeroizing::new)
+        .map_err(|_| anyhow!("Incorrect passphrase"))?;
+    DataKey::from_slice(&key).context("Stored data key is corrupt")
+}
+
+fn encrypt_bytes(key: &[u8; 32], plaintext: &[u8], aad: &[u8]) -> Result<Vec<u8>> {
+    let cipher = Aes256Gcm::new_from_slice(key).map_err(|_| anyhow!("Invalid data key"))?;
+    let mut nonce_bytes = [0u8; NONCE_LEN];
+    OsRng.fill_bytes(&mut nonce_bytes);
+    let nonce = Nonce::from_slice(&nonce_bytes);
+    let mut out = Vec::new();
+    out.extend_from_slice(&nonce_bytes);
+    out.extend_from_slice(
+        &cipher
+            .encrypt(nonce, Payload { msg: plaintext, aad })
+            .map_err(|_| anyhow!("Encryption failed"))?,
+    );
+    Ok(out)
+}
+
+fn decrypt_bytes(key: &[u8; 32], ciphertext: &[u8], aad: &[u8]) -> Result<Vec<u8>> {
+    if ciphertext.len() < NONCE_LEN {
+        return Err(anyhow!("Ciphertext too short"));
+    }
+    let (nonce_bytes, data) = ciphertext.split_at(NONCE_LEN);
+    let cipher = Aes256Gcm::new_from_slice(key).map_err(|_| anyhow!("Invalid data key"))?;
+    cipher
+        .decrypt(Nonce::from_slice(nonce_bytes), Payload { msg: data, aad })
+        .map_err(|_| anyhow!("Decryption failed"))
+}
+
+/// Key from `EDGE_AGENT_MASTER_KEY`, only needed to migrate data encrypted
+/// before the vault existed
+fn legacy_master_key() -> Result<DataKey> {
+    let key = Zeroizing::new(
+        std::env::var("EDGE_AGENT_MASTER_KEY")
+            .map_err(|_| anyhow!("EDGE_AGENT_MASTER_KEY is required once to migrate data encrypted with it"))?,
+    );
+    let key_bytes = Zeroizing::new(hex::decode(key.trim()).context("EDGE_AGENT_MASTER_KEY must be 64 hex chars")?);
+    DataKey::from_slice(&key_bytes).context("EDGE_AGENT_MASTER_KEY must be 32 bytes")
+}
+
+/// Reseals pre-vault secret keys (`enc:`, `raw:`, bare hex) and, once,
+/// plaintext sensitive columns with the data key, and moves evidence sealed
+/// before per-evidence keys (`VLT1`, `ENC1` or plaintext) under a key of its
+/// own.
+fn migrate_legacy(db: &Database, key: &DataKey) -> Result<()> {
+    for (id, encoded) in db.list_keypair_secrets()? {
+        if encoded.starts_with(SECRET_PREFIX) {
+            continue;
+        }
+        let secret = SecretVec::new(if let Some(rest) = encoded.strip_prefix(LEGACY_SECRET_PREFIX_ENC) {
+            let bytes = BASE64.decode(rest).context("Invalid encrypted key data")?;
+            decrypt_bytes(&legacy_master_key()?.0, &bytes, b"")?
+        } else if let Some(rest) = encoded.strip_prefix(LEGACY_SECRET_PREFIX_RAW) {
+            hex::decode(rest).context("Invalid raw key hex")?
+        } else {
+            hex::decode(&encoded).context("Invalid legacy key hex")?
+        });
+        db.update_keypair_secret(&id, &key.encrypt_secret(secret.expose_secret())?)?;
+    }
+
+    for (id, blob) in db.list_unkeyed_evidence()? {
+        let raw = match blob {
+            Some(blob) if blob.starts_with(BLOB_PREFIX) => Some(key.open_blob(&blob)?),
+            Some(blob) => match blob.strip_prefix(LEGACY_BLOB_PREFIX.as_slice()) {
+                Some(rest) => Some(decrypt_bytes(&legacy_master_key()?.0, rest, b"")?),
+                None => Some(blob),
+            },
+            None => None,
+        };
+        db.assign_evidence_key(key, &id, raw.as_deref())?;
+    }
+
+    let record = db.get_vault_record()?.ok_or_else(|| anyhow!("Vault is not set up"))?;
+    if record.fields_sealed_at.is_none() {
+        db.seal_plaintext_fields(key)?;
+        db.update_vault_record(&VaultRecord { fields_sealed_at: Some(Utc::now()), ..record })?;
+    }
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::storage::StoredKeypair;
+    use std::sync::Arc;
+
+    const PASSPHRASE: &str = "correct horse battery staple";
+    const TEST_PARAMS: KdfParams = TEST_KDF_PARAMS;
+
+    fn vault_and_db(auto_lock: Duration) -> (Arc<Vault>, Database) {
+        let vault = Arc::new(Vault::new(auto_lock));
+        let db = Database::in_memory(Arc::clone(&vault)).unwrap();
+        (vault, db)
+    }
+
+    fn keypair(id: &str, secret_key_encrypted: String) -> StoredKeypair {
+        StoredKeypair {
+            id: id.to_string(),
+            public_key: format!("pk-{}", id),
+            secret_key_encrypted,
+            created_at: Utc::now(),
+            is_active: false,
+            retired_at: None,
+            retired_reason: None,
+            compromised: false,
+            derivation_path: None,
+        }
+    }
+
+    #[test]
+    fn test_unlock_lock_and_change_passphrase() {
+        let (vault, db) = vault_and_db(Duration::from_secs(60));
+        assert!(!vault.status(&db).unwrap().initialized);
+        assert!(vault.initialize(&db, "too short", TEST_PARAMS).is_err());
+
+        vault.initialize(&db, PASSPHRASE, TEST_PARAMS).unwrap();
+        let sealed = vault.key().unwrap().encrypt_secret(&[7u8; 32]).unwrap();
+        assert!(vault.initialize(&db, PASSPHRASE, TEST_PARAMS).is_err());
+
+        vault.lock().unwrap();
+        assert!(vault.key().is_err());
+        assert_eq!(vault.unlock(&db, "wrong passphrase!!").unwrap_err().to_string(), "Incorrect passphrase");
+
+        vault.change_passphrase(&db, PASSPHRASE, "a brand new passphrase").unwrap();
+        vault.lock().unwrap();
+        assert!(vault.unlock(&db, PASSPHRASE).is_err());
+        vault.unlock(&db, "a brand new passphrase").unwrap();
+        assert_eq!(vault.key().unwrap().decrypt_secret(&sealed).unwrap().expose_secret(), [7u8; 32]);
+    }
+
+    #[test]
+    fn test_excessive_kdf_costs_rejected() {
+        let (vault, db) = vault_and_db(Duration::from_secs(60));
+        assert!(vault.initialize(&db, PASSPHRASE, KdfParams { m_cost: u32::MAX, ..TEST_PARAMS }).is_err());
+
+        vault.initialize(&db, PASSPHRASE, TEST_PARAMS).unwrap();
+        vault.lock().unwrap();
+        let record = db.get_vault_record().unwrap().unwrap();
+        let params = KdfParams { t_cost: u32::MAX, ..record.params };
+        db.update_vault_record(&VaultRecord { params, ..record }).unwrap();
+        let err = vault.unlock(&db, PASSPHRASE).unwrap_err().to_string();
+        assert!(err.contains("exceed the limits"), "{}", err);
+    }
+
+    #[test]
+    fn test_auto_lock() {
+        let (vault, db) = vault_and_db(Duration::ZERO);
+        vault.initialize(&db, PASSPHRASE, TEST_PARAMS).unwrap();
+        assert!(vault.key().is_err());
+        assert!(!vault.status(&db).unwrap().unlocked);
+    }
+
+    #[test]
+    fn test_legacy_data_migrated() {
+        std::env::set_var("EDGE_AGENT_MASTER_KEY", "11".repeat(32));
+        let legacy = [0x11u8; 32];
+        let (vault, db) = vault_and_db(Duration::from_secs(60));
+
+        let enc = format!("enc:{}", BASE64.encode(encrypt_bytes(&legacy, &[1u8; 32], b"").unwrap()));
+        db.insert_keypair(&keypair("enc", enc)).unwrap();
+        db.insert_keypair(&keypair("raw", format!("raw:{}", hex::encode([2u8; 32])))).unwrap();
+        db.insert_keypair(&keypair("hex", hex::encode([3u8; 32]))).unwrap();
+
+        let mut sealed = LEGACY_BLOB_PREFIX.to_vec();
+        sealed.extend_from_slice(&encrypt_bytes(&legacy, b"sealed evidence", b"").unwrap());
+        // Rows written before the vault carry plaintext blobs and columns
+        db.conn.execute(
+            "INSERT INTO evidence (id, evidence_type, content_hash, extracted_text, raw_content, created_at)
+             VALUES ('ev-enc', 'lab_report', 'aa', NULL, ?1, '2025-01-01T00:00:00Z'),
+                    ('ev-plain', 'lab_report', 'aa', 'Cobalt 25%', ?2, '2025-01-01T00:00:00Z')",
+            rusqlite::params![sealed, b"plain evidence".to_vec()],
+        ).unwrap();
+        db.conn.execute_batch(
+            "INSERT INTO claims (id, claim_type, value, unit, product_id, evidence_ids, verified,
+                 metadata, created_at, updated_at)
+             VALUES ('claim-1', 'recycled_content', '25', 'percent', 'battery-001', '[]', 1,
+                 '{}', '2025-01-01T00:00:00Z', '2025-01-01T00:00:00Z');
+             INSERT INTO claim_versions (claim_id, version, change, claim_type, value, unit,
+                 product_id, evidence_ids, verified, metadata, created_at, recorded_at)
+             VALUES ('claim-1', 1, 'created', 'recycled_content', '25', 'percent', 'battery-001',
+                 '[]', 1, '{}', '2025-01-01T00:00:00Z', '2025-01-01T00:00:00Z');",
+        ).unwrap();
+
+        vault.initialize(&db, PASSPHRASE, TEST_PARAMS).unwrap();
+        let key = vault.key().unwrap();
+
+        let secrets: std::collections::HashMap<_, _> = db.list_keypair_secrets().unwrap().into_iter().collect();
+        assert_eq!(key.decrypt_secret(&secrets["enc"]).unwrap().expose_secret(), [1u8; 32]);
+        assert_eq!(key.decrypt_secret(&secrets["raw"]).unwrap().expose_secret(), [2u8; 32]);
+        assert_eq!(key.decrypt_secret(&secrets["hex"]).unwrap().expose_secret(), [3u8; 32]);
+
+        assert_eq!(db.get_evidence_content("ev-enc").unwrap().unwrap(), b"sealed evidence");
+        assert_eq!(db.get_evidence_content("ev-plain").unwrap().unwrap(), b"plain evidence");
+
+        // Evidence sealed directly with the vault key gets its own key on unlock
+        db.conn.execute(
+            "INSERT INTO evidence (id, evidence_type, content_hash, raw_content, created_at)
+             VALUES ('ev-vault', 'lab_report', 'aa', ?1, '2025-01-01T00:00:00Z')",
+            [key.seal_blob(b"vault evidence").unwrap()],
+        ).unwrap();
+        vault.lock().unwrap();
+        vault.unlock(&db, PASSPHRASE).unwrap();
+        assert!(db.list_unkeyed_evidence().unwrap().is_empty());
+        assert_eq!(db.get_evidence_content("ev-vault").unwrap().unwrap(), b"vault evidence");
+
+        let stored: String = db.conn
+            .query_row("SELECT value FROM claim_versions WHERE claim_id = 'claim-1'", [], |row| row.get(0))
+            .unwrap();
+        assert!(is_sealed_field(&stored));
+        assert_eq!(db.get_evidence("ev-plain").unwrap().unwrap().extracted_text.as_deref(), Some("Cobalt 25%"));
+        assert_eq!(db.get_claim("claim-1").unwrap().unwrap().value, serde_json::json!(25));
+        assert_eq!(db.get_claim_history("claim-1").unwrap()[0].claim.value, serde_json::json!(25));
+        // History is append-only again once sealed
+        assert!(db.conn.execute("UPDATE claim_versions SET unit = 'kg'", []).is_err());
+    }
+
+    #[test]
+    fn test_plaintext_rejected_once_sealed() {
+        let (vault, db) = vault_and_db(Duration::from_secs(60));
+        vault.initialize(&db, PASSPHRASE, TEST_PARAMS).unwrap();
+        assert!(db.get_vault_record().unwrap().unwrap().fields_sealed_at.is_some());
+
+        // Plaintext written after sealing is neither read nor sealed on unlock
+        db.conn.execute_batch(
+            "INSERT INTO claims (id, claim_type, value, unit, product_id, evidence_ids, verified,
+                 metadata, created_at, updated_at)
+             VALUES ('claim-1', 'recycled_content', '25', 'percent', 'battery-001', '[]', 1,
+                 '{}', '2025-01-01T00:00:00Z', '2025-01-01T00:00:00Z');",
+        ).unwrap();
+        vault.lock().unwrap();
+        vault.unlock(&db, PASSPHRASE).unwrap();
+
+        let stored: String = db.conn
+            .query_row("SELECT value FROM claims WHERE id = 'claim-1'", [], |row| row.get(0))
+            .unwrap();
+        assert_eq!(stored, "25");
+        let err = format!("{:#}", db.get_claim("claim-1").unwrap_err());
+        assert!(err.contains("claims.value/claim-1 is not sealed by the vault"), "{}", err);
+    }
+}
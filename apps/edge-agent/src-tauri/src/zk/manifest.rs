@@ -0,0 +1,487 @@
+//! Pinned circuit manifest and build stamps
+//!
+//! The manifest records, for every audited circuit, the hash of its Noir
+//! sources (including its local path dependencies such as `zkdpp_lib`), the
+//! hash of the compiled ACIR artifact and the Noir version used to build it.
+//! Provers and the verify gateway refuse to run against circuits that do not
+//! match the manifest committed at `circuits/noir/circuit-manifest.json`,
+//! the one verifiers trust. Maintainers update it with `edge-agent
+//! gen-manifest` after auditing a circuit change; `gen-manifest --check`
+//! confirms a local build reproduces it.
+//!
+//! A build stamp is written next to each compiled artifact so that a source
+//! or toolchain change triggers a recompile instead of reusing a stale
+//! `target/<name>.json`.
+
+use anyhow::{anyhow, Context, Result};
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, HashMap};
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::sync::{Mutex, OnceLock};
+use std::time::SystemTime;
+
+/// Manifest file name, looked up next to the predicates directory.
+pub const MANIFEST_FILE: &str = "circuit-manifest.json";
+
+const MANIFEST_VERSION: u32 = 1;
+
+/// Pinned hashes for a single circuit
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct CircuitPin {
+    pub source_hash: String,
+    /// Hash of `target/<name>.json` built with the pinned Noir version. Until
+    /// a maintainer pins it with that toolchain, the source hash and Noir
+    /// version are what is checked.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub artifact_hash: Option<String>,
+}
+
+/// Manifest of audited circuits
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct CircuitManifest {
+    pub version: u32,
+    pub noir_version: String,
+    pub circuits: BTreeMap<String, CircuitPin>,
+}
+
+/// Recorded inputs of the last successful `nargo compile`
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct BuildStamp {
+    pub source_hash: String,
+    pub noir_version: String,
+}
+
+impl CircuitManifest {
+    pub fn new(noir_version: String, circuits: BTreeMap<String, CircuitPin>) -> Self {
+        CircuitManifest { version: MANIFEST_VERSION, noir_version, circuits }
+    }
+
+    pub fn load(path: &Path) -> Result<Self> {
+        let data = fs::read_to_string(path)
+            .with_context(|| format!("Failed to read circuit manifest {}", path.display()))?;
+        let manifest: CircuitManifest = serde_json::from_str(&data)
+            .with_context(|| format!("Invalid circuit manifest {}", path.display()))?;
+        if manifest.version != MANIFEST_VERSION {
+            return Err(anyhow!(
+                "Unsupported circuit manifest version {} (expected {})",
+                manifest.version,
+                MANIFEST_VERSION
+            ));
+        }
+        Ok(manifest)
+    }
+
+    pub fn save(&self, path: &Path) -> Result<()> {
+        let data = serde_json::to_string_pretty(self)?;
+        fs::write(path, data + "\n")
+            .with_context(|| format!("Failed to write circuit manifest {}", path.display()))
+    }
+
+    pub fn pin(&self, circuit: &str) -> Result<&CircuitPin> {
+        self.circuits
+            .get(circuit)
+            .ok_or_else(|| anyhow!("Circuit {} is not pinned in the circuit manifest", circuit))
+    }
+
+    /// Checks the local sources, toolchain and artifact against the pin.
+    pub fn check(
+        &self,
+        circuit: &str,
+        source_hash: &str,
+        noir_version: &str,
+        artifact_hash: &str,
+    ) -> Result<()> {
+        let pin = self.pin(circuit)?;
+        if noir_version != self.noir_version {
+            return Err(anyhow!(
+                "Noir version mismatch for {}: manifest pins {}, local nargo is {}",
+                circuit,
+                self.noir_version,
+                noir_version
+            ));
+        }
+        if pin.source_hash != source_hash {
+            return Err(anyhow!(
+                "Circuit sources for {} do not match the manifest (expected {}, found {})",
+                circuit,
+                pin.source_hash,
+                source_hash
+            ));
+        }
+        match &pin.artifact_hash {
+            Some(pinned) if pinned != artifact_hash => Err(anyhow!(
+                "Compiled artifact for {} does not match the manifest (expected {}, found {})",
+                circuit,
+                pinned,
+                artifact_hash
+            )),
+            _ => Ok(()),
+        }
+    }
+
+    /// What a freshly built manifest changes relative to this one, one line
+    /// per difference; empty when it reproduces this manifest.
+    pub fn differences(&self, built: &CircuitManifest) -> Vec<String> {
+        let mut differences = Vec::new();
+        if self.noir_version != built.noir_version {
+            differences.push(format!("Noir version {} -> {}", self.noir_version, built.noir_version));
+        }
+        for (circuit, pin) in &self.circuits {
+            match built.circuits.get(circuit) {
+                None => differences.push(format!("{}: removed", circuit)),
+                Some(built_pin) if built_pin.source_hash != pin.source_hash => {
+                    differences.push(format!("{}: sources changed", circuit))
+                }
+                Some(built_pin) if pin.artifact_hash.is_some() && built_pin.artifact_hash != pin.artifact_hash => {
+                    differences.push(format!("{}: artifact changed", circuit))
+                }
+                Some(_) => {}
+            }
+        }
+        for circuit in built.circuits.keys().filter(|circuit| !self.circuits.contains_key(*circuit)) {
+            differences.push(format!("{}: not pinned", circuit));
+        }
+        differences
+    }
+}
+
+/// Resolves the manifest path: `NOIR_CIRCUIT_MANIFEST`, otherwise
+/// `circuit-manifest.json` in the parent of the predicates directory.
+pub fn resolve_manifest_path(circuits_dir: &Path) -> PathBuf {
+    if let Ok(path) = std::env::var("NOIR_CIRCUIT_MANIFEST") {
+        return PathBuf::from(path);
+    }
+    circuits_dir
+        .parent()
+        .unwrap_or(circuits_dir)
+        .join(MANIFEST_FILE)
+}
+
+/// Hashes a circuit's `Nargo.toml` and `.nr` sources together with those of
+/// the local path dependencies its `Nargo.toml` declares, transitively.
+pub fn hash_circuit_sources(circuit_dir: &Path) -> Result<String> {
+    let mut data = Vec::new();
+    for (name, file) in source_files(circuit_dir)? {
+        let contents = fs::read(&file)
+            .with_context(|| format!("Failed to read {}", file.display()))?;
+        data.extend_from_slice(&(name.len() as u64).to_be_bytes());
+        data.extend_from_slice(name.as_bytes());
+        data.extend_from_slice(&(contents.len() as u64).to_be_bytes());
+        data.extend_from_slice(&contents);
+    }
+
+    Ok(commitments::to_hex(&commitments::hash_bytes(&data)))
+}
+
+/// [`hash_circuit_sources`], rehashed only when a source file was added,
+/// removed or modified since the last call for `circuit_dir`.
+pub fn cached_source_hash(circuit_dir: &Path) -> Result<String> {
+    type Stamps = Vec<(PathBuf, SystemTime, u64)>;
+    static CACHE: OnceLock<Mutex<HashMap<PathBuf, (Stamps, String)>>> = OnceLock::new();
+
+    let mut stamps = Stamps::new();
+    for (_, file) in source_files(circuit_dir)? {
+        let metadata = fs::metadata(&file)
+            .with_context(|| format!("Failed to read {}", file.display()))?;
+        stamps.push((file, metadata.modified()?, metadata.len()));
+    }
+
+    let cache = CACHE.get_or_init(Default::default);
+    if let Some((cached, hash)) = cache.lock().map_err(|_| anyhow!("Source hash cache poisoned"))?.get(circuit_dir) {
+        if *cached == stamps {
+            return Ok(hash.clone());
+        }
+    }
+    let hash = hash_circuit_sources(circuit_dir)?;
+    cache
+        .lock()
+        .map_err(|_| anyhow!("Source hash cache poisoned"))?
+        .insert(circuit_dir.to_path_buf(), (stamps, hash.clone()));
+    Ok(hash)
+}
+
+/// Every file hashed for a circuit, sorted, with the name it is hashed
+/// under: `circuit/...` for the circuit itself and `<dependency>/...` for
+/// each path dependency.
+fn source_files(circuit_dir: &Path) -> Result<Vec<(String, PathBuf)>> {
+    let mut packages = vec![("circuit".to_string(), circuit_dir.to_path_buf())];
+    let mut index = 0;
+    while index < packages.len() {
+        for (name, dir) in path_dependencies(&packages[index].1)? {
+            let dir = fs::canonicalize(&dir)
+                .with_context(|| format!("Dependency {} not found at {}", name, dir.display()))?;
+            if !packages.iter().any(|(_, seen)| *seen == dir) {
+                packages.push((name, dir));
+            }
+        }
+        index += 1;
+    }
+
+    let mut named = Vec::new();
+    for (label, dir) in &packages {
+        let mut files = vec![dir.join("Nargo.toml")];
+        collect_nr_files(&dir.join("src"), &mut files)?;
+        files.sort();
+        for file in files {
+            let rel = file.strip_prefix(dir).unwrap_or(&file);
+            named.push((format!("{}/{}", label, rel.to_string_lossy().replace('\\', "/")), file));
+        }
+    }
+    Ok(named)
+}
+
+/// The `path` dependencies in a package's `Nargo.toml`, resolved against
+/// the package directory. Git dependencies are pinned by their tag and are
+/// not hashed.
+pub fn path_dependencies(package_dir: &Path) -> Result<Vec<(String, PathBuf)>> {
+    let nargo_toml = package_dir.join("Nargo.toml");
+    let data = fs::read_to_string(&nargo_toml)
+        .with_context(|| format!("Failed to read {}", nargo_toml.display()))?;
+
+    let mut dependencies = Vec::new();
+    let mut in_dependencies = false;
+    for line in data.lines() {
+        let line = line.split('#').next().unwrap_or_default().trim();
+        if line.starts_with('[') {
+            in_dependencies = line == "[dependencies]";
+            continue;
+        }
+        if !in_dependencies {
+            continue;
+        }
+        // `zkdpp_lib = { path = "../../lib" }`
+        let path = line.split_once('=').and_then(|(name, spec)| {
+            let fields = spec.trim().strip_prefix('{')?.strip_suffix('}')?;
+            fields.split(',').find_map(|field| {
+                let (key, value) = field.split_once('=')?;
+                let value = value.trim().strip_prefix('"')?.strip_suffix('"')?;
+                (key.trim() == "path").then(|| (name.trim().to_string(), package_dir.join(value)))
+            })
+        });
+        dependencies.extend(path);
+    }
+    Ok(dependencies)
+}
+
+/// Hashes a compiled ACIR artifact.
+pub fn hash_artifact(artifact: &Path) -> Result<String> {
+    let bytes = fs::read(artifact)
+        .with_context(|| format!("Failed to read artifact {}", artifact.display()))?;
+    Ok(commitments::to_hex(&commitments::hash_bytes(&bytes)))
+}
+
+pub fn stamp_path(artifact: &Path) -> PathBuf {
+    artifact.with_extension("build.json")
+}
+
+pub fn read_stamp(artifact: &Path) -> Option<BuildStamp> {
+    let data = fs::read_to_string(stamp_path(artifact)).ok()?;
+    serde_json::from_str(&data).ok()
+}
+
+pub fn write_stamp(artifact: &Path, stamp: &BuildStamp) -> Result<()> {
+    let data = serde_json::to_string_pretty(stamp)?;
+    fs::write(stamp_path(artifact), data).context("Failed to write build stamp")
+}
+
+/// Parses `nargo --version` output (`nargo version = 0.30.0` on the first line).
+pub fn parse_nargo_version(output: &str) -> Option<String> {
+    let line = output.lines().find(|l| l.trim_start().starts_with("nargo"))?;
+    let version = line.split('=').nth(1)?.trim();
+    let version = version.split_whitespace().next()?;
+    if version.is_empty() {
+        None
+    } else {
+        Some(version.to_string())
+    }
+}
+
+fn collect_nr_files(dir: &Path, out: &mut Vec<PathBuf>) -> Result<()> {
+    if !dir.exists() {
+        return Ok(());
+    }
+    for entry in fs::read_dir(dir).with_context(|| format!("Failed to read {}", dir.display()))? {
+        let path = entry?.path();
+        if path.is_dir() {
+            collect_nr_files(&path, out)?;
+        } else if path.extension().and_then(|e| e.to_str()) == Some("nr") {
+            out.push(path);
+        }
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// A circuit depending on the library at `lib_path`, relative to the
+    /// circuit directory
+    fn temp_circuit_with_lib(lib_path: &str) -> PathBuf {
+        let root = std::env::temp_dir().join(format!("zkdpp-manifest-{}", uuid::Uuid::new_v4()));
+        let circuit = root.join("predicates").join("test_circuit_v1");
+        let lib = circuit.join(lib_path);
+        fs::create_dir_all(circuit.join("src")).unwrap();
+        fs::create_dir_all(lib.join("src")).unwrap();
+        fs::write(
+            circuit.join("Nargo.toml"),
+            format!(
+                "[package]\nname = \"test_circuit_v1\"\n\n[dependencies]\nzkdpp_lib = {{ path = \"{}\" }}\n",
+                lib_path
+            ),
+        )
+        .unwrap();
+        fs::write(circuit.join("src").join("main.nr"), "fn main() {}\n").unwrap();
+        fs::write(lib.join("Nargo.toml"), "[package]\nname = \"zkdpp_lib\"\ntype = \"lib\"\n\n[dependencies]\n").unwrap();
+        fs::write(lib.join("src").join("lib.nr"), "fn helper() {}\n").unwrap();
+        circuit
+    }
+
+    fn temp_circuit() -> PathBuf {
+        temp_circuit_with_lib("../../lib")
+    }
+
+    fn manifest_for(source_hash: &str, artifact_hash: &str) -> CircuitManifest {
+        let mut circuits = BTreeMap::new();
+        circuits.insert(
+            "test_circuit_v1".to_string(),
+            CircuitPin {
+                source_hash: source_hash.to_string(),
+                artifact_hash: Some(artifact_hash.to_string()),
+            },
+        );
+        CircuitManifest::new("0.30.0".to_string(), circuits)
+    }
+
+    #[test]
+    fn test_parse_nargo_version() {
+        let output = "nargo version = 0.30.0\nnoirc version = 0.30.0+af57a6\n";
+        assert_eq!(parse_nargo_version(output), Some("0.30.0".to_string()));
+        assert_eq!(parse_nargo_version("garbage"), None);
+    }
+
+    #[test]
+    fn test_source_hash_tracks_circuit_and_lib() {
+        let circuit = temp_circuit();
+        let initial = hash_circuit_sources(&circuit).unwrap();
+        assert_eq!(initial, hash_circuit_sources(&circuit).unwrap());
+
+        fs::write(circuit.join("src").join("main.nr"), "fn main() { assert(true); }\n").unwrap();
+        let edited = hash_circuit_sources(&circuit).unwrap();
+        assert_ne!(initial, edited);
+
+        let lib = circuit.parent().unwrap().parent().unwrap().join("lib");
+        fs::write(lib.join("src").join("lib.nr"), "fn helper() { }\n").unwrap();
+        assert_ne!(edited, hash_circuit_sources(&circuit).unwrap());
+    }
+
+    #[test]
+    fn test_dependencies_read_from_nargo_toml() {
+        let circuit = temp_circuit_with_lib("../../vendor/zkdpp_lib");
+        let dependencies = path_dependencies(&circuit).unwrap();
+        assert_eq!(dependencies, vec![("zkdpp_lib".to_string(), circuit.join("../../vendor/zkdpp_lib"))]);
+
+        let initial = hash_circuit_sources(&circuit).unwrap();
+        let lib = circuit.join("../../vendor/zkdpp_lib");
+        fs::write(lib.join("src").join("lib.nr"), "fn helper() { }\n").unwrap();
+        assert_ne!(initial, hash_circuit_sources(&circuit).unwrap());
+
+        // A dependency that is declared but missing is an error, not skipped
+        fs::remove_dir_all(&lib).unwrap();
+        assert!(hash_circuit_sources(&circuit).is_err());
+    }
+
+    #[test]
+    fn test_cached_source_hash_follows_edits() {
+        let circuit = temp_circuit();
+        let initial = cached_source_hash(&circuit).unwrap();
+        assert_eq!(initial, hash_circuit_sources(&circuit).unwrap());
+        assert_eq!(initial, cached_source_hash(&circuit).unwrap());
+
+        // A new file changes the stamps even if no timestamp moves
+        fs::write(circuit.join("src").join("util.nr"), "fn util() {}\n").unwrap();
+        let edited = cached_source_hash(&circuit).unwrap();
+        assert_ne!(initial, edited);
+        assert_eq!(edited, hash_circuit_sources(&circuit).unwrap());
+    }
+
+    #[test]
+    fn test_manifest_save_load_roundtrip() {
+        let path = temp_circuit().join(MANIFEST_FILE);
+        let manifest = manifest_for("aa", "bb");
+        manifest.save(&path).unwrap();
+        let loaded = CircuitManifest::load(&path).unwrap();
+        assert_eq!(loaded.noir_version, manifest.noir_version);
+        assert_eq!(loaded.circuits, manifest.circuits);
+    }
+
+    #[test]
+    fn test_build_stamp_roundtrip() {
+        let circuit = temp_circuit();
+        let artifact = circuit.join("target").join("test_circuit_v1.json");
+        fs::create_dir_all(artifact.parent().unwrap()).unwrap();
+        assert!(read_stamp(&artifact).is_none());
+
+        let stamp = BuildStamp {
+            source_hash: hash_circuit_sources(&circuit).unwrap(),
+            noir_version: "0.30.0".to_string(),
+        };
+        write_stamp(&artifact, &stamp).unwrap();
+        assert_eq!(read_stamp(&artifact), Some(stamp));
+    }
+
+    #[test]
+    fn test_manifest_check() {
+        let manifest = manifest_for("aa", "bb");
+        assert!(manifest.check("test_circuit_v1", "aa", "0.30.0", "bb").is_ok());
+        assert!(manifest.check("test_circuit_v1", "aa", "0.31.0", "bb").is_err());
+        assert!(manifest.check("test_circuit_v1", "cc", "0.30.0", "bb").is_err());
+        assert!(manifest.check("test_circuit_v1", "aa", "0.30.0", "cc").is_err());
+        assert!(manifest.check("other_circuit_v1", "aa", "0.30.0", "bb").is_err());
+
+        // Without an artifact pin the sources and toolchain still have to match
+        let mut unpinned = manifest_for("aa", "bb");
+        unpinned.circuits.get_mut("test_circuit_v1").unwrap().artifact_hash = None;
+        assert!(unpinned.check("test_circuit_v1", "aa", "0.30.0", "cc").is_ok());
+        assert!(unpinned.check("test_circuit_v1", "cc", "0.30.0", "cc").is_err());
+    }
+
+    #[test]
+    fn test_manifest_differences() {
+        let committed = manifest_for("aa", "bb");
+        assert!(committed.differences(&committed).is_empty());
+
+        let mut built = manifest_for("cc", "bb");
+        built.noir_version = "0.31.0".to_string();
+        built.circuits.insert("new_circuit_v1".to_string(), built.circuits["test_circuit_v1"].clone());
+        assert_eq!(
+            committed.differences(&built),
+            vec![
+                "Noir version 0.30.0 -> 0.31.0".to_string(),
+                "test_circuit_v1: sources changed".to_string(),
+                "new_circuit_v1: not pinned".to_string(),
+            ]
+        );
+    }
+
+    /// The committed manifest is the trust anchor: it has to pin every
+    /// committed circuit exactly as it is in the tree.
+    #[test]
+    fn test_committed_manifest_pins_committed_circuits() {
+        let noir = Path::new(env!("CARGO_MANIFEST_DIR")).join("../../../circuits/noir");
+        let manifest = CircuitManifest::load(&noir.join(MANIFEST_FILE)).unwrap();
+        let predicates = noir.join("predicates");
+        let mut circuits = Vec::new();
+        for entry in fs::read_dir(&predicates).unwrap() {
+            let circuit_dir = entry.unwrap().path();
+            if circuit_dir.join("Nargo.toml").exists() {
+                circuits.push(circuit_dir.file_name().unwrap().to_string_lossy().into_owned());
+                let pin = manifest.pin(circuits.last().unwrap()).unwrap();
+                assert_eq!(pin.source_hash, hash_circuit_sources(&circuit_dir).unwrap(), "{}", circuit_dir.display());
+            }
+        }
+        circuits.sort();
+        assert_eq!(manifest.circuits.keys().cloned().collect::<Vec<_>>(), circuits);
+    }
+}
@@ -0,0 +1,127 @@
+//! INSECURE mock prover for development and UI testing
+//!
+//! Evaluates each predicate natively (Merkle inclusion plus the pre-flight
+//! constraint checks) and emits a deterministic fake proof: the
+//! `zkp_core::MOCK_PROOF_TAG` prefix followed by a BLAKE3 digest of the public
+//! statement. The proof carries no zero-knowledge guarantees and every real
+//! verifier refuses it. Only compiled with the `mock-prover` feature.
+
+use super::preflight;
+use super::{CircuitInputs, ProverBackend};
+use anyhow::{anyhow, Result};
+use commitments::merkle::verify_merkle_proof;
+
+/// Mock backend selected with `ZK_BACKEND=mock`
+#[derive(Debug, Clone, Copy, Default)]
+pub struct MockProver;
+
+impl ProverBackend for MockProver {
+    fn name(&self) -> &'static str {
+        "mock"
+    }
+
+    fn prove(&self, inputs: CircuitInputs) -> Result<String> {
+        let root = commitment_root(&inputs);
+        for (leaf, path, indices) in inclusion_proofs(&inputs) {
+            if !verify_merkle_proof(&leaf, path, indices, &root) {
+                return Err(anyhow!("Merkle proof verification failed"));
+            }
+        }
+
+        let check = match &inputs {
+            CircuitInputs::RecycledContentGte(i) => preflight::check_recycled_content_gte(i.actual_value, i.threshold),
+            CircuitInputs::CarbonFootprintLte(i) => preflight::check_carbon_footprint_lte(i.actual_value, i.threshold),
+            CircuitInputs::CarbonFootprintLifecycle(i) => {
+                let values: Vec<u64> = i.stages.iter().map(|s| s.value).collect();
+                preflight::check_carbon_footprint_lifecycle(&values, i.threshold)
+            }
+            CircuitInputs::EnergyDensityRange(i) => {
+                preflight::check_energy_density_range(i.actual_value, i.min_threshold, i.max_threshold)
+            }
+            CircuitInputs::CertValid(i) => preflight::check_cert_valid(i.valid_from, i.valid_until, i.check_timestamp),
+            CircuitInputs::SubstanceNotInList(i) => {
+                let names: Vec<String> = i.product_substances.iter().map(hex::encode).collect();
+                let product: Vec<([u8; 32], &str)> = i.product_substances.iter()
+                    .copied()
+                    .zip(names.iter().map(String::as_str))
+                    .collect();
+                preflight::check_substance_not_in_list(&product, &i.forbidden_substances)
+            }
+        };
+        check.map_err(|failure| anyhow!("{}", failure))?;
+
+        let mut proof = zkp_core::MOCK_PROOF_TAG.to_vec();
+        proof.extend_from_slice(&commitments::hash_bytes(&public_statement(&inputs)));
+        Ok(hex::encode(proof))
+    }
+}
+
+fn commitment_root(inputs: &CircuitInputs) -> [u8; 32] {
+    match inputs {
+        CircuitInputs::RecycledContentGte(i) => i.commitment_root,
+        CircuitInputs::CarbonFootprintLte(i) => i.commitment_root,
+        CircuitInputs::CarbonFootprintLifecycle(i) => i.commitment_root,
+        CircuitInputs::EnergyDensityRange(i) => i.commitment_root,
+        CircuitInputs::CertValid(i) => i.commitment_root,
+        CircuitInputs::SubstanceNotInList(i) => i.commitment_root,
+    }
+}
+
+/// Leaf, sibling path and directions of every claim the circuit opens.
+fn inclusion_proofs(inputs: &CircuitInputs) -> Vec<([u8; 32], &Vec<[u8; 32]>, &Vec<u8>)> {
+    match inputs {
+        CircuitInputs::RecycledContentGte(i) => vec![(i.claim_hash, &i.merkle_path, &i.merkle_indices)],
+        CircuitInputs::CarbonFootprintLte(i) => vec![(i.claim_hash, &i.merkle_path, &i.merkle_indices)],
+        CircuitInputs::CarbonFootprintLifecycle(i) => i.stages.iter()
+            .map(|s| (s.claim_hash, &s.merkle_path, &s.merkle_indices))
+            .collect(),
+        CircuitInputs::EnergyDensityRange(i) => vec![(i.claim_hash, &i.merkle_path, &i.merkle_indices)],
+        CircuitInputs::CertValid(i) => vec![(i.claim_hash, &i.merkle_path, &i.merkle_indices)],
+        CircuitInputs::SubstanceNotInList(i) => vec![(i.claim_hash, &i.merkle_path, &i.merkle_indices)],
+    }
+}
+
+/// Serializes the public inputs the real circuit would expose.
+fn public_statement(inputs: &CircuitInputs) -> Vec<u8> {
+    let mut data = Vec::new();
+    match inputs {
+        CircuitInputs::RecycledContentGte(i) => {
+            data.extend_from_slice(b"recycled_content_gte_v1");
+            data.extend_from_slice(&i.threshold.to_be_bytes());
+            push_bindings(&mut data, &i.commitment_root, &i.product_binding, &i.requester_binding);
+        }
+        CircuitInputs::CarbonFootprintLte(i) => {
+            data.extend_from_slice(b"carbon_footprint_lte_v1");
+            data.extend_from_slice(&i.threshold.to_be_bytes());
+            push_bindings(&mut data, &i.commitment_root, &i.product_binding, &i.requester_binding);
+        }
+        CircuitInputs::CarbonFootprintLifecycle(i) => {
+            data.extend_from_slice(b"carbon_footprint_lifecycle_v1");
+            data.extend_from_slice(&i.threshold.to_be_bytes());
+            push_bindings(&mut data, &i.commitment_root, &i.product_binding, &i.requester_binding);
+        }
+        CircuitInputs::EnergyDensityRange(i) => {
+            data.extend_from_slice(b"energy_density_range_v1");
+            data.extend_from_slice(&i.min_threshold.to_be_bytes());
+            data.extend_from_slice(&i.max_threshold.to_be_bytes());
+            push_bindings(&mut data, &i.commitment_root, &i.product_binding, &i.requester_binding);
+        }
+        CircuitInputs::CertValid(i) => {
+            data.extend_from_slice(b"cert_valid_v1");
+            data.extend_from_slice(&i.check_timestamp.to_be_bytes());
+            push_bindings(&mut data, &i.commitment_root, &i.product_binding, &i.requester_binding);
+        }
+        CircuitInputs::SubstanceNotInList(i) => {
+            data.extend_from_slice(b"substance_not_in_list_v1");
+            data.extend_from_slice(&i.forbidden_list_hash);
+            push_bindings(&mut data, &i.commitment_root, &i.product_binding, &i.requester_binding);
+        }
+    }
+    data
+}
+
+fn push_bindings(data: &mut Vec<u8>, root: &[u8; 32], product: &[u8; 32], requester: &[u8; 32]) {
+    data.extend_from_slice(root);
+    data.extend_from_slice(product);
+    data.extend_from_slice(requester);
+}
@@ -3,26 +3,118 @@
 //! This module uses the `nargo` CLI to compile and prove Noir circuits.
 //! It expects the Noir toolchain to be installed locally and accessible
 //! via the `NARGO_BIN` environment variable (defaults to `nargo`).
+//!
+//! Every proof is checked against the pinned circuit manifest (see
+//! [`manifest`]) before `nargo` is asked to prove, and callers evaluate the
+//! predicate natively with [`preflight`] first.
+
+pub mod manifest;
+#[cfg(feature = "mock-prover")]
+pub mod mock;
+pub mod preflight;
 
 use anyhow::{anyhow, Context, Result};
+use manifest::{BuildStamp, CircuitManifest, CircuitPin};
+use std::collections::{BTreeMap, HashMap};
 use std::fs;
 use std::path::{Path, PathBuf};
+use std::sync::{Mutex, OnceLock};
 use std::process::Command;
 
 #[derive(Debug, Clone)]
 pub struct NoirCliConfig {
     pub nargo_bin: String,
     pub circuits_dir: PathBuf,
+    /// Pinned circuit manifest; `None` only when unpinned circuits are allowed
+    pub manifest: Option<CircuitManifest>,
 }
 
 impl NoirCliConfig {
     pub fn from_env() -> Result<Self> {
-        let nargo_bin = std::env::var("NARGO_BIN").unwrap_or_else(|_| "nargo".to_string());
-        let circuits_dir = resolve_circuits_dir()
-            .context("Unable to locate Noir circuits directory. Set NOIR_CIRCUITS_DIR.")?;
+        let (nargo_bin, circuits_dir) = toolchain_from_env()?;
+
+        let manifest_path = manifest::resolve_manifest_path(&circuits_dir);
+        let manifest = if manifest_path.exists() {
+            Some(CircuitManifest::load(&manifest_path)?)
+        } else if std::env::var("ALLOW_UNPINNED_CIRCUITS").as_deref() == Ok("true") {
+            None
+        } else {
+            return Err(anyhow!(
+                "Circuit manifest not found at {}. Run `edge-agent gen-manifest`, set NOIR_CIRCUIT_MANIFEST or ALLOW_UNPINNED_CIRCUITS=true",
+                manifest_path.display()
+            ));
+        };
+
+        Ok(Self { nargo_bin, circuits_dir, manifest })
+    }
+}
+
+/// `NARGO_BIN` and the predicates directory
+fn toolchain_from_env() -> Result<(String, PathBuf)> {
+    let nargo_bin = std::env::var("NARGO_BIN").unwrap_or_else(|_| "nargo".to_string());
+    let circuits_dir = resolve_circuits_dir()
+        .context("Unable to locate Noir circuits directory. Set NOIR_CIRCUITS_DIR.")?;
+    Ok((nargo_bin, circuits_dir))
+}
+
+/// Compiles every circuit in the predicates directory with the local `nargo`
+/// and pins the result
+fn build_manifest(nargo_bin: &str, circuits_dir: &Path) -> Result<CircuitManifest> {
+    let noir_version = nargo_version(nargo_bin)?;
+
+    let mut circuits = BTreeMap::new();
+    for entry in fs::read_dir(circuits_dir)
+        .with_context(|| format!("Failed to read {}", circuits_dir.display()))?
+    {
+        let circuit_dir = entry?.path();
+        if !circuit_dir.join("Nargo.toml").exists() {
+            continue;
+        }
+        let (package_name, artifact) = circuit_artifact(&circuit_dir)?;
+        let stamp = BuildStamp {
+            source_hash: manifest::hash_circuit_sources(&circuit_dir)?,
+            noir_version: noir_version.clone(),
+        };
+        ensure_compiled(nargo_bin, &circuit_dir, &artifact, &stamp)
+            .with_context(|| format!("Failed to build {}", package_name))?;
+        let pin = CircuitPin {
+            source_hash: stamp.source_hash,
+            artifact_hash: Some(manifest::hash_artifact(&artifact)?),
+        };
+        circuits.insert(package_name.to_string(), pin);
+    }
+    if circuits.is_empty() {
+        return Err(anyhow!("No circuits found in {}", circuits_dir.display()));
+    }
+    Ok(CircuitManifest::new(noir_version, circuits))
+}
+
+/// Rebuilds the circuits and rewrites the committed manifest, for
+/// maintainers once a circuit change has been audited. Returns the manifest
+/// path and what changed.
+pub fn update_manifest() -> Result<(PathBuf, Vec<String>)> {
+    let (nargo_bin, circuits_dir) = toolchain_from_env()?;
+    let built = build_manifest(&nargo_bin, &circuits_dir)?;
+    let path = manifest::resolve_manifest_path(&circuits_dir);
+    let changes = match CircuitManifest::load(&path) {
+        Ok(committed) => committed.differences(&built),
+        Err(_) => vec!["new manifest".to_string()],
+    };
+    built.save(&path)?;
+    Ok((path, changes))
+}
 
-        Ok(Self { nargo_bin, circuits_dir })
+/// Rebuilds the circuits and fails unless the build reproduces the
+/// committed manifest
+pub fn check_manifest() -> Result<PathBuf> {
+    let (nargo_bin, circuits_dir) = toolchain_from_env()?;
+    let path = manifest::resolve_manifest_path(&circuits_dir);
+    let committed = CircuitManifest::load(&path)?;
+    let differences = committed.differences(&build_manifest(&nargo_bin, &circuits_dir)?);
+    if !differences.is_empty() {
+        return Err(anyhow!("Local build does not reproduce {}: {}", path.display(), differences.join("; ")));
     }
+    Ok(path)
 }
 
 #[derive(Debug, Clone)]
@@ -55,6 +147,47 @@ pub struct CarbonFootprintInputs {
     pub tree_depth: u32,
 }
 
+/// Number of lifecycle stages aggregated by `carbon_footprint_lifecycle_v1`
+pub const LIFECYCLE_STAGES: usize = 5;
+
+/// Witness for one committed lifecycle stage claim
+#[derive(Debug, Clone)]
+pub struct LifecycleStageWitness {
+    pub value: u64,
+    pub claim_type_hash: [u8; 32],
+    pub unit_hash: [u8; 32],
+    pub claim_hash: [u8; 32],
+    pub merkle_path: Vec<[u8; 32]>,
+    pub merkle_indices: Vec<u8>,
+    pub tree_depth: u32,
+}
+
+#[derive(Debug, Clone)]
+pub struct CarbonFootprintLifecycleInputs {
+    pub threshold: u64,
+    pub commitment_root: [u8; 32],
+    pub product_binding: [u8; 32],
+    pub requester_binding: [u8; 32],
+    /// Raw materials, manufacturing, transport, use and end-of-life, in order
+    pub stages: Vec<LifecycleStageWitness>,
+}
+
+#[derive(Debug, Clone)]
+pub struct EnergyDensityRangeInputs {
+    pub min_threshold: u32,
+    pub max_threshold: u32,
+    pub commitment_root: [u8; 32],
+    pub product_binding: [u8; 32],
+    pub requester_binding: [u8; 32],
+    pub actual_value: u32,
+    pub claim_type_hash: [u8; 32],
+    pub unit_hash: [u8; 32],
+    pub claim_hash: [u8; 32],
+    pub merkle_path: Vec<[u8; 32]>,
+    pub merkle_indices: Vec<u8>,
+    pub tree_depth: u32,
+}
+
 #[derive(Debug, Clone)]
 pub struct CertValidInputs {
     pub check_timestamp: u64,
@@ -87,9 +220,65 @@ pub struct SubstanceNotInListInputs {
     pub tree_depth: u32,
 }
 
+/// Witness for one of the supported predicate circuits
+#[derive(Debug, Clone)]
+pub enum CircuitInputs {
+    RecycledContentGte(RecycledContentInputs),
+    CarbonFootprintLte(CarbonFootprintInputs),
+    CarbonFootprintLifecycle(CarbonFootprintLifecycleInputs),
+    EnergyDensityRange(EnergyDensityRangeInputs),
+    CertValid(CertValidInputs),
+    SubstanceNotInList(SubstanceNotInListInputs),
+}
+
+/// A proving backend turning circuit inputs into a hex-encoded proof
+pub trait ProverBackend {
+    fn name(&self) -> &'static str;
+    fn prove(&self, inputs: CircuitInputs) -> Result<String>;
+}
+
+impl ProverBackend for NoirCliConfig {
+    fn name(&self) -> &'static str {
+        "noir-cli"
+    }
+
+    fn prove(&self, inputs: CircuitInputs) -> Result<String> {
+        match inputs {
+            CircuitInputs::RecycledContentGte(i) => prove_recycled_content_gte(self, i),
+            CircuitInputs::CarbonFootprintLte(i) => prove_carbon_footprint_lte(self, i),
+            CircuitInputs::CarbonFootprintLifecycle(i) => prove_carbon_footprint_lifecycle(self, i),
+            CircuitInputs::EnergyDensityRange(i) => prove_energy_density_range(self, i),
+            CircuitInputs::CertValid(i) => prove_cert_valid(self, i),
+            CircuitInputs::SubstanceNotInList(i) => prove_substance_not_in_list(self, i),
+        }
+    }
+}
+
+/// Selects the prover from `ZK_BACKEND` (`noir-cli` by default). The `mock`
+/// backend is only available in builds with the `mock-prover` feature.
+pub fn backend_from_env() -> Result<Box<dyn ProverBackend>> {
+    match std::env::var("ZK_BACKEND").as_deref() {
+        Ok("mock") => mock_backend(),
+        Ok("noir-cli") | Err(_) => Ok(Box::new(
+            NoirCliConfig::from_env().context("Noir CLI config error")?,
+        )),
+        Ok(other) => Err(anyhow!("Unsupported ZK_BACKEND {}", other)),
+    }
+}
+
+#[cfg(feature = "mock-prover")]
+fn mock_backend() -> Result<Box<dyn ProverBackend>> {
+    Ok(Box::new(mock::MockProver))
+}
+
+#[cfg(not(feature = "mock-prover"))]
+fn mock_backend() -> Result<Box<dyn ProverBackend>> {
+    Err(anyhow!("Mock prover not available: build with the mock-prover feature"))
+}
+
 pub fn prove_recycled_content_gte(config: &NoirCliConfig, inputs: RecycledContentInputs) -> Result<String> {
     let circuit_dir = config.circuits_dir.join("recycled_content_gte_v1");
-    ensure_compiled(&config.nargo_bin, &circuit_dir)?;
+    prepare_circuit(config, &circuit_dir)?;
 
     // Write Prover.toml into circuit dir (nargo default)
     let prover_toml = build_recycled_content_prover_toml(&inputs)?;
@@ -113,7 +302,7 @@ pub fn prove_recycled_content_gte(config: &NoirCliConfig, inputs: RecycledConten
 
 pub fn prove_carbon_footprint_lte(config: &NoirCliConfig, inputs: CarbonFootprintInputs) -> Result<String> {
     let circuit_dir = config.circuits_dir.join("carbon_footprint_lte_v1");
-    ensure_compiled(&config.nargo_bin, &circuit_dir)?;
+    prepare_circuit(config, &circuit_dir)?;
 
     let prover_toml = build_carbon_footprint_prover_toml(&inputs)?;
     fs::write(circuit_dir.join("Prover.toml"), prover_toml)
@@ -132,9 +321,54 @@ pub fn prove_carbon_footprint_lte(config: &NoirCliConfig, inputs: CarbonFootprin
     Ok(hex::encode(proof_bytes))
 }
 
+pub fn prove_carbon_footprint_lifecycle(
+    config: &NoirCliConfig,
+    inputs: CarbonFootprintLifecycleInputs,
+) -> Result<String> {
+    let circuit_dir = config.circuits_dir.join("carbon_footprint_lifecycle_v1");
+    prepare_circuit(config, &circuit_dir)?;
+
+    let prover_toml = build_carbon_footprint_lifecycle_prover_toml(&inputs)?;
+    fs::write(circuit_dir.join("Prover.toml"), prover_toml)
+        .context("Failed to write Prover.toml")?;
+
+    run_nargo(&config.nargo_bin, &circuit_dir, &["execute"])?;
+    run_nargo(&config.nargo_bin, &circuit_dir, &["prove"])?;
+
+    let proof_path = circuit_dir
+        .join("proofs")
+        .join("carbon_footprint_lifecycle_v1.proof");
+
+    let proof_bytes = fs::read(&proof_path)
+        .with_context(|| format!("Proof file not found at {}", proof_path.display()))?;
+
+    Ok(hex::encode(proof_bytes))
+}
+
+pub fn prove_energy_density_range(config: &NoirCliConfig, inputs: EnergyDensityRangeInputs) -> Result<String> {
+    let circuit_dir = config.circuits_dir.join("energy_density_range_v1");
+    prepare_circuit(config, &circuit_dir)?;
+
+    let prover_toml = build_energy_density_prover_toml(&inputs)?;
+    fs::write(circuit_dir.join("Prover.toml"), prover_toml)
+        .context("Failed to write Prover.toml")?;
+
+    run_nargo(&config.nargo_bin, &circuit_dir, &["execute"])?;
+    run_nargo(&config.nargo_bin, &circuit_dir, &["prove"])?;
+
+    let proof_path = circuit_dir
+        .join("proofs")
+        .join("energy_density_range_v1.proof");
+
+    let proof_bytes = fs::read(&proof_path)
+        .with_context(|| format!("Proof file not found at {}", proof_path.display()))?;
+
+    Ok(hex::encode(proof_bytes))
+}
+
 pub fn prove_cert_valid(config: &NoirCliConfig, inputs: CertValidInputs) -> Result<String> {
     let circuit_dir = config.circuits_dir.join("cert_valid_v1");
-    ensure_compiled(&config.nargo_bin, &circuit_dir)?;
+    prepare_circuit(config, &circuit_dir)?;
 
     let prover_toml = build_cert_valid_prover_toml(&inputs)?;
     fs::write(circuit_dir.join("Prover.toml"), prover_toml)
@@ -158,7 +392,7 @@ pub fn prove_substance_not_in_list(
     inputs: SubstanceNotInListInputs,
 ) -> Result<String> {
     let circuit_dir = config.circuits_dir.join("substance_not_in_list_v1");
-    ensure_compiled(&config.nargo_bin, &circuit_dir)?;
+    prepare_circuit(config, &circuit_dir)?;
 
     let prover_toml = build_substance_not_in_list_prover_toml(&inputs)?;
     fs::write(circuit_dir.join("Prover.toml"), prover_toml)
@@ -237,6 +471,85 @@ tree_depth = \"{tree_depth}\"\n",
     ))
 }
 
+fn build_carbon_footprint_lifecycle_prover_toml(inputs: &CarbonFootprintLifecycleInputs) -> Result<String> {
+    if inputs.stages.len() != LIFECYCLE_STAGES {
+        return Err(anyhow!(
+            "Lifecycle proof needs {} stages, got {}",
+            LIFECYCLE_STAGES,
+            inputs.stages.len()
+        ));
+    }
+
+    let mut paths = Vec::new();
+    let mut indices = Vec::new();
+    for stage in &inputs.stages {
+        paths.push(pad_merkle_path(&stage.merkle_path, 8)?);
+        indices.push(pad_merkle_indices(&stage.merkle_indices, 8)?);
+    }
+    let stage_values = inputs.stages.iter().map(|s| format!("\"{}\"", s.value)).collect::<Vec<_>>();
+    let tree_depths = inputs.stages.iter().map(|s| format!("\"{}\"", s.tree_depth)).collect::<Vec<_>>();
+    let claim_type_hashes = inputs.stages.iter().map(|s| s.claim_type_hash).collect::<Vec<_>>();
+    let unit_hashes = inputs.stages.iter().map(|s| s.unit_hash).collect::<Vec<_>>();
+    let claim_hashes = inputs.stages.iter().map(|s| s.claim_hash).collect::<Vec<_>>();
+
+    Ok(format!(
+        "threshold = \"{threshold}\"\n\
+commitment_root = {commitment_root}\n\
+product_binding = {product_binding}\n\
+requester_binding = {requester_binding}\n\
+stage_values = [{stage_values}]\n\
+claim_type_hashes = {claim_type_hashes}\n\
+unit_hashes = {unit_hashes}\n\
+claim_hashes = {claim_hashes}\n\
+merkle_paths = [{merkle_paths}]\n\
+merkle_indices = [{merkle_indices}]\n\
+tree_depths = [{tree_depths}]\n",
+        threshold = inputs.threshold,
+        commitment_root = bytes_to_toml_array(&inputs.commitment_root),
+        product_binding = bytes_to_toml_array(&inputs.product_binding),
+        requester_binding = bytes_to_toml_array(&inputs.requester_binding),
+        stage_values = stage_values.join(", "),
+        claim_type_hashes = nested_bytes_to_toml_array(&claim_type_hashes),
+        unit_hashes = nested_bytes_to_toml_array(&unit_hashes),
+        claim_hashes = nested_bytes_to_toml_array(&claim_hashes),
+        merkle_paths = paths.join(", "),
+        merkle_indices = indices.join(", "),
+        tree_depths = tree_depths.join(", ")
+    ))
+}
+
+fn build_energy_density_prover_toml(inputs: &EnergyDensityRangeInputs) -> Result<String> {
+    let path = pad_merkle_path(&inputs.merkle_path, 8)?;
+    let indices = pad_merkle_indices(&inputs.merkle_indices, 8)?;
+
+    Ok(format!(
+        "min_threshold = \"{min_threshold}\"\n\
+max_threshold = \"{max_threshold}\"\n\
+commitment_root = {commitment_root}\n\
+product_binding = {product_binding}\n\
+requester_binding = {requester_binding}\n\
+actual_value = \"{actual_value}\"\n\
+claim_type_hash = {claim_type_hash}\n\
+unit_hash = {unit_hash}\n\
+claim_hash = {claim_hash}\n\
+merkle_path = {merkle_path}\n\
+merkle_indices = {merkle_indices}\n\
+tree_depth = \"{tree_depth}\"\n",
+        min_threshold = inputs.min_threshold,
+        max_threshold = inputs.max_threshold,
+        commitment_root = bytes_to_toml_array(&inputs.commitment_root),
+        product_binding = bytes_to_toml_array(&inputs.product_binding),
+        requester_binding = bytes_to_toml_array(&inputs.requester_binding),
+        actual_value = inputs.actual_value,
+        claim_type_hash = bytes_to_toml_array(&inputs.claim_type_hash),
+        unit_hash = bytes_to_toml_array(&inputs.unit_hash),
+        claim_hash = bytes_to_toml_array(&inputs.claim_hash),
+        merkle_path = path,
+        merkle_indices = indices,
+        tree_depth = inputs.tree_depth
+    ))
+}
+
 fn build_cert_valid_prover_toml(inputs: &CertValidInputs) -> Result<String> {
     let path = pad_merkle_path(&inputs.merkle_path, 8)?;
     let indices = pad_merkle_indices(&inputs.merkle_indices, 8)?;
@@ -356,18 +669,65 @@ fn pad_substances(values: &Vec<[u8; 32]>, max: usize) -> Result<Vec<[u8; 32]>> {
     Ok(padded)
 }
 
-fn ensure_compiled(nargo_bin: &str, circuit_dir: &Path) -> Result<()> {
+/// Compiles the circuit if needed and checks it against the pinned manifest.
+fn prepare_circuit(config: &NoirCliConfig, circuit_dir: &Path) -> Result<()> {
+    let (package_name, artifact) = circuit_artifact(circuit_dir)?;
+
+    let stamp = BuildStamp {
+        source_hash: manifest::cached_source_hash(circuit_dir)?,
+        noir_version: nargo_version(&config.nargo_bin)?,
+    };
+    ensure_compiled(&config.nargo_bin, circuit_dir, &artifact, &stamp)?;
+
+    match &config.manifest {
+        Some(pinned) => {
+            let artifact_hash = manifest::hash_artifact(&artifact)?;
+            pinned.check(package_name, &stamp.source_hash, &stamp.noir_version, &artifact_hash)
+        }
+        None => Ok(()),
+    }
+}
+
+/// The package name and compiled artifact path of a circuit
+fn circuit_artifact(circuit_dir: &Path) -> Result<(&str, PathBuf)> {
     let package_name = circuit_dir.file_name()
         .and_then(|n| n.to_str())
         .ok_or_else(|| anyhow!("Invalid circuit directory name"))?;
-    let artifact = circuit_dir.join("target").join(format!("{}.json", package_name));
-    if artifact.exists() {
+    Ok((package_name, circuit_dir.join("target").join(format!("{}.json", package_name))))
+}
+
+/// Recompiles when the artifact is missing or was built from different
+/// sources or a different Noir version.
+fn ensure_compiled(nargo_bin: &str, circuit_dir: &Path, artifact: &Path, stamp: &BuildStamp) -> Result<()> {
+    if artifact.exists() && manifest::read_stamp(artifact).as_ref() == Some(stamp) {
         return Ok(());
     }
-    run_nargo(nargo_bin, circuit_dir, &["compile"]).context("Failed to compile Noir circuit")?;
+    run_nargo(nargo_bin, circuit_dir, &["compile", "--force"]).context("Failed to compile Noir circuit")?;
+    manifest::write_stamp(artifact, stamp)?;
     Ok(())
 }
 
+/// `nargo --version`, asked once per binary for the life of the process
+fn nargo_version(nargo_bin: &str) -> Result<String> {
+    static VERSIONS: OnceLock<Mutex<HashMap<String, String>>> = OnceLock::new();
+    let versions = VERSIONS.get_or_init(Default::default);
+    if let Some(version) = versions.lock().map_err(|_| anyhow!("nargo version cache poisoned"))?.get(nargo_bin) {
+        return Ok(version.clone());
+    }
+
+    let output = Command::new(nargo_bin)
+        .arg("--version")
+        .output()
+        .with_context(|| format!("Failed to run {} --version", nargo_bin))?;
+    let version = manifest::parse_nargo_version(&String::from_utf8_lossy(&output.stdout))
+        .ok_or_else(|| anyhow!("Unable to determine nargo version"))?;
+    versions
+        .lock()
+        .map_err(|_| anyhow!("nargo version cache poisoned"))?
+        .insert(nargo_bin.to_string(), version.clone());
+    Ok(version)
+}
+
 fn run_nargo(nargo_bin: &str, dir: &Path, args: &[&str]) -> Result<()> {
     let output = Command::new(nargo_bin)
         .current_dir(dir)
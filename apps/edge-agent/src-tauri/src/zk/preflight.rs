@@ -0,0 +1,360 @@
+//! Native pre-flight evaluation of predicate constraints
+//!
+//! Mirrors the asserts in the Noir circuits so that a claim which cannot
+//! satisfy a predicate is rejected with a structured reason before any
+//! `nargo execute`/`nargo prove` run is started.
+
+use serde::Serialize;
+use std::fmt;
+
+/// Upper bound on scaled carbon footprint values (`MAX_FOOTPRINT` in the circuit)
+pub const MAX_CARBON_FOOTPRINT: u32 = 10_000_000;
+/// Upper bound on a single lifecycle stage value in `carbon_footprint_lifecycle_v1`
+pub const MAX_LIFECYCLE_STAGE_VALUE: u64 = 1_000_000;
+/// Physical bounds on energy density in Wh/kg enforced by `energy_density_range_v1`
+pub const MIN_ENERGY_DENSITY: u32 = 50;
+pub const MAX_ENERGY_DENSITY: u32 = 500;
+/// Capacity of the product substance array in `substance_not_in_list_v1`
+pub const MAX_PRODUCT_SUBSTANCES: usize = 32;
+/// Capacity of the forbidden substance array in `substance_not_in_list_v1`
+pub const MAX_FORBIDDEN_SUBSTANCES: usize = 64;
+/// Merkle path length of every predicate circuit (`TREE_DEPTH`)
+pub const MAX_TREE_DEPTH: u32 = 8;
+/// Most claims a commitment can hold and still be proved against
+pub const MAX_COMMITTED_CLAIMS: usize = 1 << MAX_TREE_DEPTH;
+
+/// Why a claim cannot satisfy the requested predicate
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+#[serde(tag = "reason", rename_all = "snake_case")]
+pub enum PredicateFailure {
+    /// `actual >= threshold` does not hold; `margin` is the shortfall
+    BelowThreshold { actual: u64, threshold: u64, margin: u64 },
+    /// `actual <= threshold` does not hold; `margin` is the excess
+    AboveThreshold { actual: u64, threshold: u64, margin: u64 },
+    /// `min_threshold <= max_threshold` does not hold
+    InvalidThresholdRange { min_threshold: u64, max_threshold: u64 },
+    /// Value or threshold lies outside the range the circuit accepts
+    OutOfRange { field: String, value: u64, min: u64, max: u64 },
+    /// `valid_from <= check_timestamp` does not hold; `margin` in seconds
+    CertificateNotYetValid { valid_from: u64, check_timestamp: u64, margin: u64 },
+    /// `check_timestamp < valid_until` does not hold; `margin` in seconds
+    CertificateExpired { valid_until: u64, check_timestamp: u64, margin: u64 },
+    /// `valid_from < valid_until` does not hold
+    InvalidValidityWindow { valid_from: u64, valid_until: u64 },
+    /// A product substance appears in the forbidden list
+    ForbiddenSubstance { substance: String },
+    /// More list entries than the circuit has slots for
+    TooManyEntries { field: String, count: usize, max: usize },
+    /// The recomputed claim tree does not reproduce the signed root
+    CommitmentRootMismatch { expected: String, actual: String },
+    /// A committed claim no longer hashes to its frozen leaf
+    LeafMismatch { claim_id: String, position: usize, expected: String, actual: String },
+}
+
+impl fmt::Display for PredicateFailure {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            PredicateFailure::BelowThreshold { actual, threshold, margin } => write!(
+                f,
+                "Value {} is below threshold {} by {}",
+                actual, threshold, margin
+            ),
+            PredicateFailure::AboveThreshold { actual, threshold, margin } => write!(
+                f,
+                "Value {} exceeds threshold {} by {}",
+                actual, threshold, margin
+            ),
+            PredicateFailure::InvalidThresholdRange { min_threshold, max_threshold } => write!(
+                f,
+                "Invalid threshold range: min {} is above max {}",
+                min_threshold, max_threshold
+            ),
+            PredicateFailure::OutOfRange { field, value, min, max } => write!(
+                f,
+                "{} {} is outside the accepted range {}..={}",
+                field, value, min, max
+            ),
+            PredicateFailure::CertificateNotYetValid { valid_from, check_timestamp, margin } => write!(
+                f,
+                "Certificate not yet valid at {} (valid from {}, {}s early)",
+                check_timestamp, valid_from, margin
+            ),
+            PredicateFailure::CertificateExpired { valid_until, check_timestamp, margin } => write!(
+                f,
+                "Certificate expired at {} (checked at {}, {}s late)",
+                valid_until, check_timestamp, margin
+            ),
+            PredicateFailure::InvalidValidityWindow { valid_from, valid_until } => write!(
+                f,
+                "Invalid validity window: valid_from {} is not before valid_until {}",
+                valid_from, valid_until
+            ),
+            PredicateFailure::ForbiddenSubstance { substance } => {
+                write!(f, "Product contains forbidden substance {}", substance)
+            }
+            PredicateFailure::TooManyEntries { field, count, max } => write!(
+                f,
+                "{} has {} entries, the circuit supports at most {}",
+                field, count, max
+            ),
+            PredicateFailure::CommitmentRootMismatch { expected, actual } => write!(
+                f,
+                "Commitment root mismatch: signed {}, recomputed {}",
+                expected, actual
+            ),
+            PredicateFailure::LeafMismatch { claim_id, position, expected, actual } => write!(
+                f,
+                "Claim {} (leaf {}) diverges from the commitment: expected {}, found {}",
+                claim_id, position, expected, actual
+            ),
+        }
+    }
+}
+
+pub type Preflight = std::result::Result<(), PredicateFailure>;
+
+/// RECYCLED_CONTENT_GTE_V1: `actual >= threshold` and `actual <= 100`.
+pub fn check_recycled_content_gte(actual: u32, threshold: u32) -> Preflight {
+    check_range("actual_value", actual as u64, 0, 100)?;
+    check_gte(actual as u64, threshold as u64)
+}
+
+/// CARBON_FOOTPRINT_LTE_V1: `actual <= threshold`, both bounded by `MAX_FOOTPRINT`.
+pub fn check_carbon_footprint_lte(actual: u32, threshold: u32) -> Preflight {
+    check_range("threshold", threshold as u64, 0, MAX_CARBON_FOOTPRINT as u64)?;
+    check_range("actual_value", actual as u64, 0, MAX_CARBON_FOOTPRINT as u64)?;
+    check_lte(actual as u64, threshold as u64)
+}
+
+/// CARBON_FOOTPRINT_LIFECYCLE_V1: every stage within bounds and the stage
+/// total `<= threshold`.
+pub fn check_carbon_footprint_lifecycle(stage_values: &[u64], threshold: u64) -> Preflight {
+    for (i, value) in stage_values.iter().enumerate() {
+        check_range(&format!("stage_values[{}]", i), *value, 0, MAX_LIFECYCLE_STAGE_VALUE)?;
+    }
+    let total: u64 = stage_values.iter().sum();
+    check_lte(total, threshold)
+}
+
+/// ENERGY_DENSITY_RANGE_V1: `min_threshold <= actual <= max_threshold` and
+/// `actual` within the physical bounds.
+pub fn check_energy_density_range(actual: u32, min_threshold: u32, max_threshold: u32) -> Preflight {
+    if min_threshold > max_threshold {
+        return Err(PredicateFailure::InvalidThresholdRange {
+            min_threshold: min_threshold as u64,
+            max_threshold: max_threshold as u64,
+        });
+    }
+    check_gte(actual as u64, min_threshold as u64)?;
+    check_lte(actual as u64, max_threshold as u64)?;
+    check_range(
+        "actual_value",
+        actual as u64,
+        MIN_ENERGY_DENSITY as u64,
+        MAX_ENERGY_DENSITY as u64,
+    )
+}
+
+/// CERT_VALID_V1: `valid_from <= check_timestamp < valid_until`.
+pub fn check_cert_valid(valid_from: u64, valid_until: u64, check_timestamp: u64) -> Preflight {
+    if valid_from > check_timestamp {
+        return Err(PredicateFailure::CertificateNotYetValid {
+            valid_from,
+            check_timestamp,
+            margin: valid_from - check_timestamp,
+        });
+    }
+    if check_timestamp >= valid_until {
+        return Err(PredicateFailure::CertificateExpired {
+            valid_until,
+            check_timestamp,
+            margin: check_timestamp - valid_until,
+        });
+    }
+    if valid_from >= valid_until {
+        return Err(PredicateFailure::InvalidValidityWindow { valid_from, valid_until });
+    }
+    Ok(())
+}
+
+/// SUBSTANCE_NOT_IN_LIST_V1: no product substance ID appears in the forbidden list.
+///
+/// `product` pairs each substance ID with the name reported on failure.
+pub fn check_substance_not_in_list(product: &[([u8; 32], &str)], forbidden: &[[u8; 32]]) -> Preflight {
+    check_capacity("product_substances", product.len(), MAX_PRODUCT_SUBSTANCES)?;
+    check_capacity("forbidden_substances", forbidden.len(), MAX_FORBIDDEN_SUBSTANCES)?;
+
+    match product.iter().find(|(id, _)| forbidden.contains(id)) {
+        Some((_, name)) => Err(PredicateFailure::ForbiddenSubstance {
+            substance: name.to_string(),
+        }),
+        None => Ok(()),
+    }
+}
+
+/// Every circuit opens a Merkle path of at most `MAX_TREE_DEPTH` levels, so
+/// the commitment can hold at most `MAX_COMMITTED_CLAIMS` claims.
+pub fn check_tree_depth(claim_count: usize) -> Preflight {
+    check_capacity("committed_claims", claim_count, MAX_COMMITTED_CLAIMS)
+}
+
+/// Every circuit asserts Merkle inclusion against the public commitment root.
+pub fn check_commitment_root(expected: &[u8; 32], actual: &[u8; 32]) -> Preflight {
+    if expected != actual {
+        return Err(PredicateFailure::CommitmentRootMismatch {
+            expected: commitments::to_hex(expected),
+            actual: commitments::to_hex(actual),
+        });
+    }
+    Ok(())
+}
+
+fn check_gte(actual: u64, threshold: u64) -> Preflight {
+    if actual < threshold {
+        return Err(PredicateFailure::BelowThreshold {
+            actual,
+            threshold,
+            margin: threshold - actual,
+        });
+    }
+    Ok(())
+}
+
+fn check_lte(actual: u64, threshold: u64) -> Preflight {
+    if actual > threshold {
+        return Err(PredicateFailure::AboveThreshold {
+            actual,
+            threshold,
+            margin: actual - threshold,
+        });
+    }
+    Ok(())
+}
+
+fn check_range(field: &str, value: u64, min: u64, max: u64) -> Preflight {
+    if value < min || value > max {
+        return Err(PredicateFailure::OutOfRange {
+            field: field.to_string(),
+            value,
+            min,
+            max,
+        });
+    }
+    Ok(())
+}
+
+fn check_capacity(field: &str, count: usize, max: usize) -> Preflight {
+    if count > max {
+        return Err(PredicateFailure::TooManyEntries {
+            field: field.to_string(),
+            count,
+            max,
+        });
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_recycled_content_below_threshold() {
+        assert_eq!(
+            check_recycled_content_gte(18, 20),
+            Err(PredicateFailure::BelowThreshold { actual: 18, threshold: 20, margin: 2 })
+        );
+        assert!(check_recycled_content_gte(20, 20).is_ok());
+        assert!(matches!(
+            check_recycled_content_gte(150, 20),
+            Err(PredicateFailure::OutOfRange { .. })
+        ));
+    }
+
+    #[test]
+    fn test_carbon_footprint_above_threshold() {
+        assert_eq!(
+            check_carbon_footprint_lte(1250, 1200),
+            Err(PredicateFailure::AboveThreshold { actual: 1250, threshold: 1200, margin: 50 })
+        );
+        assert!(check_carbon_footprint_lte(1200, 1200).is_ok());
+    }
+
+    #[test]
+    fn test_carbon_footprint_lifecycle_total() {
+        assert!(check_carbon_footprint_lifecycle(&[8, 8, 8, 8, 8], 50).is_ok());
+        assert_eq!(
+            check_carbon_footprint_lifecycle(&[20, 10, 10, 10, 10], 50),
+            Err(PredicateFailure::AboveThreshold { actual: 60, threshold: 50, margin: 10 })
+        );
+        assert!(matches!(
+            check_carbon_footprint_lifecycle(&[0, 0, 2_000_000, 0, 0], u64::MAX),
+            Err(PredicateFailure::OutOfRange { .. })
+        ));
+    }
+
+    #[test]
+    fn test_energy_density_range() {
+        assert!(check_energy_density_range(250, 200, 300).is_ok());
+        assert!(check_energy_density_range(200, 200, 300).is_ok());
+        assert_eq!(
+            check_energy_density_range(180, 200, 300),
+            Err(PredicateFailure::BelowThreshold { actual: 180, threshold: 200, margin: 20 })
+        );
+        assert_eq!(
+            check_energy_density_range(350, 200, 300),
+            Err(PredicateFailure::AboveThreshold { actual: 350, threshold: 300, margin: 50 })
+        );
+        assert_eq!(
+            check_energy_density_range(250, 300, 200),
+            Err(PredicateFailure::InvalidThresholdRange { min_threshold: 300, max_threshold: 200 })
+        );
+        assert!(matches!(
+            check_energy_density_range(40, 0, 600),
+            Err(PredicateFailure::OutOfRange { .. })
+        ));
+    }
+
+    #[test]
+    fn test_cert_window() {
+        assert!(check_cert_valid(100, 200, 150).is_ok());
+        assert!(check_cert_valid(100, 200, 100).is_ok());
+        assert_eq!(
+            check_cert_valid(100, 200, 200),
+            Err(PredicateFailure::CertificateExpired { valid_until: 200, check_timestamp: 200, margin: 0 })
+        );
+        assert_eq!(
+            check_cert_valid(100, 200, 40),
+            Err(PredicateFailure::CertificateNotYetValid { valid_from: 100, check_timestamp: 40, margin: 60 })
+        );
+    }
+
+    #[test]
+    fn test_forbidden_substance_named() {
+        let lead = [0x58; 32];
+        let product = [([0x0A; 32], "iron"), (lead, "lead")];
+        assert_eq!(
+            check_substance_not_in_list(&product, &[lead]),
+            Err(PredicateFailure::ForbiddenSubstance { substance: "lead".to_string() })
+        );
+        assert!(check_substance_not_in_list(&product[..1], &[lead]).is_ok());
+    }
+
+    #[test]
+    fn test_tree_depth_limit() {
+        assert!(check_tree_depth(1).is_ok());
+        assert!(check_tree_depth(256).is_ok());
+        assert_eq!(
+            check_tree_depth(257),
+            Err(PredicateFailure::TooManyEntries { field: "committed_claims".to_string(), count: 257, max: 256 })
+        );
+    }
+
+    #[test]
+    fn test_failure_serializes_with_reason_tag() {
+        let failure = PredicateFailure::BelowThreshold { actual: 18, threshold: 20, margin: 2 };
+        let json = serde_json::to_value(&failure).unwrap();
+        assert_eq!(json["reason"], "below_threshold");
+        assert_eq!(json["margin"], 2);
+    }
+}
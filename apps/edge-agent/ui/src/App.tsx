@@ -1,11 +1,66 @@
+import { useCallback, useEffect, useState } from 'react';
 import { Routes, Route, NavLink } from 'react-router-dom';
+import { invoke, type VaultStatus, VAULT_LOCKED_EVENT } from './api';
 import Dashboard from './screens/Dashboard';
 import Evidence from './screens/Evidence';
 import Claims from './screens/Claims';
 import Commitments from './screens/Commitments';
 import Settings from './screens/Settings';
+import Vault from './screens/Vault';
+
+/** How often to notice that the vault locked itself while idle */
+const VAULT_POLL_MS = 30_000;
 
 function App() {
+  const [vault, setVault] = useState<VaultStatus | null>(null);
+  // Remounts the screens after an unlock so they reload their data
+  const [session, setSession] = useState(0);
+
+  const refreshVault = useCallback(async () => {
+    try {
+      const response = await invoke<{ success: boolean; data: VaultStatus }>('vault_status');
+      if (response.success) {
+        setVault(response.data);
+      }
+    } catch (error) {
+      console.error('Failed to load vault status:', error);
+    }
+  }, []);
+
+  useEffect(() => {
+    refreshVault();
+    const timer = window.setInterval(refreshVault, VAULT_POLL_MS);
+    window.addEventListener(VAULT_LOCKED_EVENT, refreshVault);
+    window.addEventListener('focus', refreshVault);
+    return () => {
+      window.clearInterval(timer);
+      window.removeEventListener(VAULT_LOCKED_EVENT, refreshVault);
+      window.removeEventListener('focus', refreshVault);
+    };
+  }, [refreshVault]);
+
+  async function lockVault() {
+    try {
+      await invoke('lock_vault');
+    } catch (error) {
+      console.error('Failed to lock vault:', error);
+    }
+    refreshVault();
+  }
+
+  function unlocked() {
+    setSession(s => s + 1);
+    refreshVault();
+  }
+
+  if (!vault) {
+    return <div className="empty-state">Loading...</div>;
+  }
+
+  if (!vault.unlocked) {
+    return <Vault initialized={vault.initialized} onUnlocked={unlocked} />;
+  }
+
   return (
     <div className="app">
       <aside className="sidebar">
@@ -31,9 +86,13 @@ function App() {
             ⚙️ Settings
           </NavLink>
         </nav>
+
+        <button className="btn btn-secondary sidebar-lock" onClick={lockVault}>
+          🔒 Lock
+        </button>
       </aside>
 
-      <main className="main-content">
+      <main className="main-content" key={session}>
         <Routes>
           <Route path="/" element={<Dashboard />} />
           <Route path="/evidence" element={<Evidence />} />
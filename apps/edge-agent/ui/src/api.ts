@@ -0,0 +1,35 @@
+import { invoke as tauriInvoke, type InvokeArgs } from '@tauri-apps/api/tauri';
+
+/** Error the Edge Agent returns while the vault is locked */
+export const VAULT_LOCKED = 'Vault is locked';
+
+/** Fired when a command fails because the vault locked (e.g. auto-lock) */
+export const VAULT_LOCKED_EVENT = 'vault-locked';
+
+export interface VaultStatus {
+  initialized: boolean;
+  unlocked: boolean;
+  auto_lock_secs: number;
+}
+
+/**
+ * Calls an Edge Agent command. A command failing because the vault is locked
+ * also fires `VAULT_LOCKED_EVENT`, so the app can ask for the passphrase.
+ */
+export async function invoke<T>(command: string, args?: InvokeArgs): Promise<T> {
+  let response: T;
+  try {
+    response = await tauriInvoke<T>(command, args);
+  } catch (error) {
+    noticeLockedVault(String(error));
+    throw error;
+  }
+  noticeLockedVault((response as { error?: string } | null)?.error);
+  return response;
+}
+
+function noticeLockedVault(error: string | undefined) {
+  if (error?.includes(VAULT_LOCKED)) {
+    window.dispatchEvent(new Event(VAULT_LOCKED_EVENT));
+  }
+}
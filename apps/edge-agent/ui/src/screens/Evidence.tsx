@@ -1,5 +1,5 @@
 import { useEffect, useState } from 'react';
-import { invoke } from '@tauri-apps/api/tauri';
+import { invoke } from '../api';
 import { open } from '@tauri-apps/api/dialog';
 
 interface EvidenceItem {
@@ -9,6 +9,7 @@ interface EvidenceItem {
   content_hash: string;
   extracted_text: string | null;
   created_at: string;
+  shredded_at: string | null;
 }
 
 function Evidence() {
@@ -144,7 +145,9 @@ function Evidence() {
                     <span className="badge badge-info">{item.evidence_type}</span>
                   </td>
                   <td>
-                    {item.extracted_text ? (
+                    {item.shredded_at ? (
+                      <span className="badge badge-danger">Shredded</span>
+                    ) : item.extracted_text ? (
                       <span className="badge badge-success">Yes</span>
                     ) : (
                       <span className="badge badge-warning">No</span>
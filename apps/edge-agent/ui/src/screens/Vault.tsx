@@ -0,0 +1,109 @@
+import { type FormEvent, useState } from 'react';
+import { invoke } from '../api';
+
+/** Same minimum the Edge Agent enforces */
+const MIN_PASSPHRASE_LEN = 12;
+
+interface VaultProps {
+  initialized: boolean;
+  onUnlocked: () => void;
+}
+
+/**
+ * First-run passphrase setup, or the unlock prompt once a vault exists.
+ * Evidence, claims and keys stay sealed until one of them succeeds.
+ */
+function Vault({ initialized, onUnlocked }: VaultProps) {
+  const [passphrase, setPassphrase] = useState('');
+  const [confirmation, setConfirmation] = useState('');
+  const [error, setError] = useState<string | null>(null);
+  const [working, setWorking] = useState(false);
+
+  async function submit(event: FormEvent) {
+    event.preventDefault();
+    setError(null);
+
+    if (!initialized) {
+      if ([...passphrase].length < MIN_PASSPHRASE_LEN) {
+        setError(`Passphrase must be at least ${MIN_PASSPHRASE_LEN} characters`);
+        return;
+      }
+      if (passphrase !== confirmation) {
+        setError('Passphrases do not match');
+        return;
+      }
+    }
+
+    setWorking(true);
+    try {
+      const response = await invoke<{ success: boolean; error?: string }>(
+        initialized ? 'unlock_vault' : 'setup_vault',
+        { passphrase }
+      );
+      if (response.success) {
+        setPassphrase('');
+        setConfirmation('');
+        onUnlocked();
+      } else {
+        setError(response.error || 'Failed to open the vault');
+      }
+    } catch (error) {
+      console.error('Vault error:', error);
+      setError('Failed to open the vault');
+    } finally {
+      setWorking(false);
+    }
+  }
+
+  return (
+    <div className="vault-screen">
+      <form className="card vault-card" onSubmit={submit}>
+        <div className="page-header">
+          <h2>{initialized ? '🔒 Vault Locked' : '🔐 Set Up Your Vault'}</h2>
+          <p>
+            {initialized
+              ? 'Enter your passphrase to unlock evidence, claims and signing keys.'
+              : 'Choose a passphrase to encrypt evidence, claims and signing keys on this device.'}
+          </p>
+        </div>
+
+        <div className="form-group">
+          <label className="form-label">Passphrase</label>
+          <input
+            type="password"
+            className="form-input"
+            value={passphrase}
+            onChange={e => setPassphrase(e.target.value)}
+            autoFocus
+          />
+        </div>
+
+        {!initialized && (
+          <>
+            <div className="form-group">
+              <label className="form-label">Confirm Passphrase</label>
+              <input
+                type="password"
+                className="form-input"
+                value={confirmation}
+                onChange={e => setConfirmation(e.target.value)}
+              />
+            </div>
+            <p style={{ color: 'var(--text-secondary)', fontSize: '0.875rem', marginBottom: '1rem' }}>
+              At least {MIN_PASSPHRASE_LEN} characters. The passphrase cannot be recovered; without it the
+              stored data cannot be decrypted.
+            </p>
+          </>
+        )}
+
+        {error && <p className="vault-error">{error}</p>}
+
+        <button type="submit" className="btn btn-primary" disabled={working || !passphrase}>
+          {working ? 'Working...' : initialized ? '🔓 Unlock' : '🔐 Create Vault'}
+        </button>
+      </form>
+    </div>
+  );
+}
+
+export default Vault;
@@ -0,0 +1,86 @@
+//! Batch signature verification
+//!
+//! Checking a compliance bundle or a supplier's commitment history means
+//! verifying many signatures at once. [`verify_batch`] checks them together,
+//! which is several times faster than one at a time, and falls back to
+//! checking each signature only when the batch fails, to name the bad ones.
+//!
+//! Batch verification uses the cofactored equation, so in theory it accepts
+//! a few maliciously crafted signatures that [`PublicKey::verify_in`] rejects;
+//! signatures produced by honest signers verify the same either way.
+
+use crate::{CryptoError, Domain, PublicKey, Result};
+use ed25519_dalek::{Signature, Verifier, VerifyingKey};
+
+/// Verifies every `(public key, payload, signature)` triple, each signed in
+/// `domain`. On failure the error lists the positions of all invalid
+/// signatures.
+pub fn verify_batch(domain: Domain, items: &[(PublicKey, &[u8], Signature)]) -> Result<()> {
+    let framed: Vec<Vec<u8>> = items.iter().map(|(_, payload, _)| domain.frame(payload)).collect();
+    let messages: Vec<&[u8]> = framed.iter().map(Vec::as_slice).collect();
+    let signatures: Vec<Signature> = items.iter().map(|(_, _, signature)| *signature).collect();
+    let keys: Vec<VerifyingKey> = items.iter().map(|(key, _, _)| key.0).collect();
+    if ed25519_dalek::verify_batch(&messages, &signatures, &keys).is_ok() {
+        return Ok(());
+    }
+
+    let invalid: Vec<usize> = items
+        .iter()
+        .zip(&messages)
+        .enumerate()
+        .filter(|(_, ((key, _, signature), message))| key.0.verify(message, signature).is_err())
+        .map(|(index, _)| index)
+        .collect();
+    if invalid.is_empty() {
+        // The batch equation can reject what single verification accepts
+        return Ok(());
+    }
+    Err(CryptoError::InvalidSignatures(invalid))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{KeyPair, Signer};
+
+    fn sign(kp: &KeyPair, message: &[u8]) -> Signature {
+        Signature::from_bytes(&kp.sign_in(Domain::COMMITMENT, message).unwrap())
+    }
+
+    fn signed(count: usize) -> (Vec<KeyPair>, Vec<Vec<u8>>) {
+        let keys: Vec<KeyPair> = (0..count).map(|_| KeyPair::generate()).collect();
+        let messages = (0..count).map(|i| format!("commitment {}", i).into_bytes()).collect();
+        (keys, messages)
+    }
+
+    #[test]
+    fn test_batch_accepts_valid_signatures() {
+        let (keys, messages) = signed(16);
+        let items: Vec<_> = keys
+            .iter()
+            .zip(&messages)
+            .map(|(kp, message)| (kp.public_key(), message.as_slice(), sign(kp, message)))
+            .collect();
+        assert!(verify_batch(Domain::COMMITMENT, &items).is_ok());
+        assert!(verify_batch(Domain::COMMITMENT, &[]).is_ok());
+        assert!(verify_batch(Domain::REVOCATION, &items).is_err());
+    }
+
+    #[test]
+    fn test_batch_names_invalid_signatures() {
+        let (keys, messages) = signed(8);
+        let mut items: Vec<_> = keys
+            .iter()
+            .zip(&messages)
+            .map(|(kp, message)| (kp.public_key(), message.as_slice(), sign(kp, message)))
+            .collect();
+        // Wrong message for 2, wrong key for 5
+        items[2].1 = b"tampered";
+        items[5].0 = keys[4].public_key();
+
+        match verify_batch(Domain::COMMITMENT, &items) {
+            Err(CryptoError::InvalidSignatures(invalid)) => assert_eq!(invalid, vec![2, 5]),
+            other => panic!("expected invalid signatures, got {:?}", other),
+        }
+    }
+}
@@ -0,0 +1,255 @@
+//! W3C Data Integrity proofs (`eddsa-jcs-2022`)
+//!
+//! Lets commitments and other statements be issued as verifiable credentials
+//! that standard tooling can check. The document and the proof options are
+//! each canonicalized with JCS (RFC 8785) and hashed with SHA-256; the
+//! signature covers the two digests and is embedded as a multibase
+//! `proofValue`. These signatures are not framed in a [`crate::Domain`]; the
+//! cryptosuite fixes the signed bytes, and 64 bytes of digests cannot collide
+//! with a domain-framed message.
+
+use crate::did::DidResolver;
+use crate::{CryptoError, PublicKey, Result, Signer};
+use serde::{Deserialize, Serialize};
+use serde_json::{Map, Value};
+use sha2::{Digest, Sha256};
+
+pub const PROOF_TYPE: &str = "DataIntegrityProof";
+pub const CRYPTOSUITE: &str = "eddsa-jcs-2022";
+
+/// Length of the signed hash data: two SHA-256 digests
+pub(crate) const HASH_DATA_LEN: usize = 64;
+
+/// The proof purpose for issuing credentials
+pub const ASSERTION_METHOD: &str = "assertionMethod";
+
+/// A `proof` entry of a secured document
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct DataIntegrityProof {
+    #[serde(rename = "@context", default, skip_serializing_if = "Option::is_none")]
+    pub context: Option<Value>,
+    #[serde(rename = "type")]
+    pub proof_type: String,
+    pub cryptosuite: String,
+    /// XML Schema date-time, e.g. `2026-01-01T00:00:00Z`
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub created: Option<String>,
+    pub verification_method: String,
+    pub proof_purpose: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub proof_value: Option<String>,
+}
+
+/// Secures `document` with a proof by `signer`, identified to verifiers as
+/// `verification_method`.
+pub fn add_proof(
+    document: &Value,
+    signer: &dyn Signer,
+    verification_method: &str,
+    proof_purpose: &str,
+    created: &str,
+) -> Result<Value> {
+    let mut secured = document
+        .as_object()
+        .ok_or_else(|| CryptoError::InvalidProof("document is not a JSON object".to_string()))?
+        .clone();
+    if secured.contains_key("proof") {
+        return Err(CryptoError::InvalidProof("document already has a proof".to_string()));
+    }
+
+    let mut proof = DataIntegrityProof {
+        context: secured.get("@context").cloned(),
+        proof_type: PROOF_TYPE.to_string(),
+        cryptosuite: CRYPTOSUITE.to_string(),
+        created: Some(created.to_string()),
+        verification_method: verification_method.to_string(),
+        proof_purpose: proof_purpose.to_string(),
+        proof_value: None,
+    };
+    let signature = crate::signer::sign_raw(signer, &hash_data(&secured, &proof)?)?;
+    proof.proof_value = Some(format!("z{}", bs58::encode(signature).into_string()));
+
+    secured.insert("proof".to_string(), serde_json::to_value(&proof)?);
+    Ok(Value::Object(secured))
+}
+
+/// Checks the `eddsa-jcs-2022` proof on `document` for `proof_purpose`,
+/// resolving its verification method with `resolver`. Returns the proof and
+/// the key that made it.
+pub fn verify_proof(
+    document: &Value,
+    resolver: &DidResolver,
+    proof_purpose: &str,
+) -> Result<(DataIntegrityProof, PublicKey)> {
+    let mut unsecured = document
+        .as_object()
+        .ok_or_else(|| CryptoError::InvalidProof("document is not a JSON object".to_string()))?
+        .clone();
+    let proof: DataIntegrityProof = match unsecured.remove("proof") {
+        Some(proof) => serde_json::from_value(proof)?,
+        None => return Err(CryptoError::InvalidProof("document has no proof".to_string())),
+    };
+    if proof.proof_type != PROOF_TYPE || proof.cryptosuite != CRYPTOSUITE {
+        return Err(CryptoError::InvalidProof(format!(
+            "unsupported proof {} / {}",
+            proof.proof_type, proof.cryptosuite
+        )));
+    }
+    if proof.proof_purpose != proof_purpose {
+        return Err(CryptoError::InvalidProof(format!(
+            "proof purpose is {}, expected {}",
+            proof.proof_purpose, proof_purpose
+        )));
+    }
+    if proof.context.is_some() && proof.context.as_ref() != unsecured.get("@context") {
+        return Err(CryptoError::InvalidProof("proof @context does not match the document".to_string()));
+    }
+
+    let signature = proof
+        .proof_value
+        .as_deref()
+        .and_then(|value| value.strip_prefix('z'))
+        .and_then(|value| bs58::decode(value).into_vec().ok())
+        .and_then(|bytes| <[u8; 64]>::try_from(bytes).ok())
+        .ok_or_else(|| CryptoError::InvalidProof("proofValue is not a base58btc signature".to_string()))?;
+    let key = resolver.resolve(&proof.verification_method)?;
+
+    let options = DataIntegrityProof { proof_value: None, ..proof.clone() };
+    if !key.verify(&hash_data(&unsecured, &options)?, &signature)? {
+        return Err(CryptoError::InvalidSignature);
+    }
+    Ok((proof, key))
+}
+
+/// `SHA-256(JCS(proof options)) || SHA-256(JCS(document))`, with the proof
+/// options taking the document's `@context`
+fn hash_data(unsecured: &Map<String, Value>, options: &DataIntegrityProof) -> Result<Vec<u8>> {
+    let options = DataIntegrityProof { context: unsecured.get("@context").cloned(), ..options.clone() };
+    let mut hash = Sha256::digest(canonicalize(&serde_json::to_value(&options)?).as_bytes()).to_vec();
+    hash.extend_from_slice(&Sha256::digest(canonicalize(&Value::Object(unsecured.clone())).as_bytes()));
+    Ok(hash)
+}
+
+/// JSON Canonicalization Scheme (RFC 8785)
+pub fn canonicalize(value: &Value) -> String {
+    let mut out = String::new();
+    write_canonical(value, &mut out);
+    out
+}
+
+fn write_canonical(value: &Value, out: &mut String) {
+    match value {
+        Value::Null | Value::Bool(_) | Value::String(_) => out.push_str(&value.to_string()),
+        Value::Number(number) => match (number.as_i64(), number.as_u64(), number.as_f64()) {
+            (Some(n), _, _) => out.push_str(&n.to_string()),
+            (_, Some(n), _) => out.push_str(&n.to_string()),
+            (_, _, Some(n)) => out.push_str(&es6_number(n)),
+            _ => out.push_str(&number.to_string()),
+        },
+        Value::Array(items) => {
+            out.push('[');
+            for (index, item) in items.iter().enumerate() {
+                if index > 0 {
+                    out.push(',');
+                }
+                write_canonical(item, out);
+            }
+            out.push(']');
+        }
+        Value::Object(fields) => {
+            // Keys sort by UTF-16 code units
+            let mut fields: Vec<_> = fields.iter().collect();
+            fields.sort_by(|(a, _), (b, _)| a.encode_utf16().cmp(b.encode_utf16()));
+            out.push('{');
+            for (index, (key, item)) in fields.into_iter().enumerate() {
+                if index > 0 {
+                    out.push(',');
+                }
+                out.push_str(&Value::String(key.clone()).to_string());
+                out.push(':');
+                write_canonical(item, out);
+            }
+            out.push('}');
+        }
+    }
+}
+
+/// Formats a double the way ECMAScript's `Number.prototype.toString` does
+fn es6_number(n: f64) -> String {
+    if n == 0.0 {
+        return "0".to_string();
+    }
+    let magnitude = n.abs();
+    if (1e-6..1e21).contains(&magnitude) {
+        // Shortest round-trip digits, no exponent, no trailing ".0"
+        return n.to_string();
+    }
+    let formatted = format!("{:e}", n);
+    match formatted.split_once('e') {
+        Some((mantissa, exponent)) if !exponent.starts_with('-') => format!("{}e+{}", mantissa, exponent),
+        _ => formatted,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::KeyPair;
+    use serde_json::json;
+
+    fn credential() -> Value {
+        json!({
+            "@context": ["https://www.w3.org/ns/credentials/v2"],
+            "type": ["VerifiableCredential"],
+            "issuer": "did:web:acme.example",
+            "credentialSubject": { "root": "ab".repeat(32), "claimCount": 3 }
+        })
+    }
+
+    #[test]
+    fn test_jcs_canonical_form() {
+        let value = json!({ "b": [1.0, 0.5, 1e21, 1e-7, -0.0], "a": "\u{20ac}\n", "\u{e9}": null, "Z": true });
+        assert_eq!(canonicalize(&value), r#"{"Z":true,"a":"€\n","b":[1,0.5,1e+21,1e-7,0],"é":null}"#);
+        // Supplementary-plane characters sort by their surrogates
+        let value = json!({ "\u{ff61}": 1, "\u{1f600}": 2 });
+        assert_eq!(canonicalize(&value), "{\"\u{1f600}\":2,\"\u{ff61}\":1}");
+    }
+
+    #[test]
+    fn test_proof_roundtrip_with_did_key() {
+        let kp = KeyPair::generate();
+        let method = kp.public_key().did_key_verification_method();
+        let secured = add_proof(&credential(), &kp, &method, ASSERTION_METHOD, "2026-01-01T00:00:00Z").unwrap();
+        assert_eq!(secured["proof"]["cryptosuite"], CRYPTOSUITE);
+        assert_eq!(secured["proof"]["@context"], credential()["@context"]);
+
+        let (proof, key) = verify_proof(&secured, &DidResolver::new(), ASSERTION_METHOD).unwrap();
+        assert_eq!(key, kp.public_key());
+        assert_eq!(proof.verification_method, method);
+        assert!(add_proof(&secured, &kp, &method, ASSERTION_METHOD, "2026-01-01T00:00:00Z").is_err());
+    }
+
+    #[test]
+    fn test_proof_rejects_tampering() {
+        let kp = KeyPair::generate();
+        let method = kp.public_key().did_key_verification_method();
+        let secured = add_proof(&credential(), &kp, &method, ASSERTION_METHOD, "2026-01-01T00:00:00Z").unwrap();
+        let resolver = DidResolver::new();
+
+        let mut altered = secured.clone();
+        altered["credentialSubject"]["claimCount"] = json!(4);
+        assert!(matches!(verify_proof(&altered, &resolver, ASSERTION_METHOD), Err(CryptoError::InvalidSignature)));
+
+        let mut backdated = secured.clone();
+        backdated["proof"]["created"] = json!("2025-01-01T00:00:00Z");
+        assert!(verify_proof(&backdated, &resolver, ASSERTION_METHOD).is_err());
+
+        let mut impersonated = secured.clone();
+        impersonated["proof"]["verificationMethod"] = json!(KeyPair::generate().public_key().did_key_verification_method());
+        assert!(verify_proof(&impersonated, &resolver, ASSERTION_METHOD).is_err());
+
+        assert!(verify_proof(&secured, &resolver, "authentication").is_err());
+        assert!(verify_proof(&credential(), &resolver, ASSERTION_METHOD).is_err());
+    }
+}
@@ -0,0 +1,328 @@
+//! Decentralized identifiers for supplier keys
+//!
+//! A supplier key on its own is `did:key:z6Mk...`: the multicodec-tagged
+//! Ed25519 key in base58btc, resolvable without any lookup. An organization
+//! that publishes a DID document on its website is `did:web:example.com`,
+//! which ties its keys to a domain brands already know. This crate never
+//! fetches documents; callers download `did.json` from [`did_web_url`] and
+//! hand it to [`DidDocument::from_json`].
+
+use crate::{CryptoError, PublicKey, Result};
+use base64::engine::general_purpose::URL_SAFE_NO_PAD;
+use base64::Engine;
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+
+/// Multicodec prefix of an Ed25519 public key (`0xed`, varint-encoded)
+const ED25519_MULTICODEC: [u8; 2] = [0xed, 0x01];
+
+const DID_CONTEXT: &str = "https://www.w3.org/ns/did/v1";
+const MULTIKEY_CONTEXT: &str = "https://w3id.org/security/multikey/v1";
+
+impl PublicKey {
+    /// The key as a base58btc multibase Multikey (`z6Mk...`)
+    pub fn to_multibase(&self) -> String {
+        let mut bytes = ED25519_MULTICODEC.to_vec();
+        bytes.extend_from_slice(self.as_bytes());
+        format!("z{}", bs58::encode(bytes).into_string())
+    }
+
+    /// Parses a base58btc multibase Multikey.
+    pub fn from_multibase(multibase: &str) -> Result<Self> {
+        let encoded = multibase
+            .strip_prefix('z')
+            .ok_or_else(|| CryptoError::InvalidDid(format!("{} is not base58btc multibase", multibase)))?;
+        let bytes = bs58::decode(encoded)
+            .into_vec()
+            .map_err(|e| CryptoError::InvalidDid(e.to_string()))?;
+        let key = bytes
+            .strip_prefix(&ED25519_MULTICODEC)
+            .ok_or_else(|| CryptoError::InvalidDid(format!("{} is not an Ed25519 key", multibase)))?;
+        let key: &[u8; 32] = key.try_into().map_err(|_| CryptoError::InvalidKeyLength {
+            expected: 32,
+            got: key.len(),
+        })?;
+        PublicKey::from_bytes(key)
+    }
+
+    /// The key's `did:key` identifier
+    pub fn to_did_key(&self) -> String {
+        format!("did:key:{}", self.to_multibase())
+    }
+
+    /// Parses a `did:key` identifier or one of its verification method URLs.
+    pub fn from_did_key(did: &str) -> Result<Self> {
+        let (did, fragment) = split_fragment(did);
+        let multibase = did
+            .strip_prefix("did:key:")
+            .ok_or_else(|| CryptoError::InvalidDid(format!("{} is not a did:key", did)))?;
+        if fragment.is_some_and(|fragment| fragment != multibase) {
+            return Err(CryptoError::InvalidDid(format!("{} has no such verification method", did)));
+        }
+        PublicKey::from_multibase(multibase)
+    }
+
+    /// The verification method URL of the key's `did:key` (`did:key:z..#z..`)
+    pub fn did_key_verification_method(&self) -> String {
+        let multibase = self.to_multibase();
+        format!("did:key:{}#{}", multibase, multibase)
+    }
+}
+
+/// Where the DID document of a `did:web` is published
+pub fn did_web_url(did: &str) -> Result<String> {
+    let (did, _) = split_fragment(did);
+    let rest = did
+        .strip_prefix("did:web:")
+        .ok_or_else(|| CryptoError::InvalidDid(format!("{} is not a did:web", did)))?;
+    let mut segments = rest.split(':');
+    let host = segments.next().unwrap_or_default().replace("%3A", ":");
+    if host.is_empty() || host.contains('/') {
+        return Err(CryptoError::InvalidDid(format!("{} has no valid domain", did)));
+    }
+    let path: Vec<&str> = segments.collect();
+    if path.iter().any(|segment| segment.is_empty()) {
+        return Err(CryptoError::InvalidDid(format!("{} has an empty path segment", did)));
+    }
+    Ok(match path.is_empty() {
+        true => format!("https://{}/.well-known/did.json", host),
+        false => format!("https://{}/{}/did.json", host, path.join("/")),
+    })
+}
+
+fn split_fragment(url: &str) -> (&str, Option<&str>) {
+    match url.split_once('#') {
+        Some((did, fragment)) => (did, Some(fragment)),
+        None => (url, None),
+    }
+}
+
+/// A public key in JWK form (`OKP`, `Ed25519`)
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Jwk {
+    pub kty: String,
+    pub crv: String,
+    pub x: String,
+}
+
+/// A key listed in a DID document
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct VerificationMethod {
+    pub id: String,
+    #[serde(rename = "type")]
+    pub method_type: String,
+    pub controller: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub public_key_multibase: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub public_key_jwk: Option<Jwk>,
+}
+
+impl VerificationMethod {
+    /// The Ed25519 key, from `publicKeyMultibase` or an `OKP` JWK
+    pub fn public_key(&self) -> Result<PublicKey> {
+        if let Some(multibase) = &self.public_key_multibase {
+            return PublicKey::from_multibase(multibase);
+        }
+        match &self.public_key_jwk {
+            Some(jwk) if jwk.kty == "OKP" && jwk.crv == "Ed25519" => {
+                let bytes = URL_SAFE_NO_PAD
+                    .decode(&jwk.x)
+                    .map_err(|e| CryptoError::InvalidDid(format!("{}: {}", self.id, e)))?;
+                let bytes: &[u8; 32] = bytes.as_slice().try_into().map_err(|_| CryptoError::InvalidKeyLength {
+                    expected: 32,
+                    got: bytes.len(),
+                })?;
+                PublicKey::from_bytes(bytes)
+            }
+            _ => Err(CryptoError::InvalidDid(format!("{} has no Ed25519 key", self.id))),
+        }
+    }
+}
+
+/// A verification relationship entry: a reference or an embedded method
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(untagged)]
+pub enum MethodReference {
+    Reference(String),
+    Embedded(VerificationMethod),
+}
+
+/// The parts of a DID document needed to check supplier signatures
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct DidDocument {
+    #[serde(rename = "@context", default, skip_serializing_if = "serde_json::Value::is_null")]
+    pub context: serde_json::Value,
+    pub id: String,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub verification_method: Vec<VerificationMethod>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub assertion_method: Vec<MethodReference>,
+}
+
+impl DidDocument {
+    /// Parses a downloaded `did.json`.
+    pub fn from_json(json: &str) -> Result<Self> {
+        let document: DidDocument = serde_json::from_str(json)?;
+        if !document.id.starts_with("did:") {
+            return Err(CryptoError::InvalidDid(format!("{} is not a DID", document.id)));
+        }
+        Ok(document)
+    }
+
+    /// A document for `did` whose only key, a Multikey, can issue
+    /// credentials; what a supplier publishes at [`did_web_url`].
+    pub fn for_key(did: &str, key: &PublicKey) -> Self {
+        let method = VerificationMethod {
+            id: format!("{}#{}", did, key.to_multibase()),
+            method_type: "Multikey".to_string(),
+            controller: did.to_string(),
+            public_key_multibase: Some(key.to_multibase()),
+            public_key_jwk: None,
+        };
+        DidDocument {
+            context: serde_json::json!([DID_CONTEXT, MULTIKEY_CONTEXT]),
+            id: did.to_string(),
+            assertion_method: vec![MethodReference::Reference(method.id.clone())],
+            verification_method: vec![method],
+        }
+    }
+
+    /// The key behind `verification_method`, which must be listed under
+    /// `assertionMethod` (the relationship used to issue credentials).
+    pub fn assertion_key(&self, verification_method: &str) -> Result<PublicKey> {
+        let absolute = |id: &str| match id.strip_prefix('#') {
+            Some(fragment) => format!("{}#{}", self.id, fragment),
+            None => id.to_string(),
+        };
+        let wanted = absolute(verification_method);
+        for reference in &self.assertion_method {
+            match reference {
+                MethodReference::Embedded(method) if absolute(&method.id) == wanted => return method.public_key(),
+                MethodReference::Reference(id) if absolute(id) == wanted => {
+                    return self
+                        .verification_method
+                        .iter()
+                        .find(|method| absolute(&method.id) == wanted)
+                        .ok_or_else(|| CryptoError::InvalidDid(format!("{} is not defined", wanted)))?
+                        .public_key();
+                }
+                _ => {}
+            }
+        }
+        Err(CryptoError::InvalidDid(format!("{} is not an assertion method of {}", wanted, self.id)))
+    }
+}
+
+/// Resolves verification methods to keys: `did:key` directly, other DIDs
+/// from documents the caller has loaded
+#[derive(Debug, Clone, Default)]
+pub struct DidResolver {
+    documents: HashMap<String, DidDocument>,
+}
+
+impl DidResolver {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Trusts `document` for its DID.
+    pub fn add_document(&mut self, document: DidDocument) {
+        self.documents.insert(document.id.clone(), document);
+    }
+
+    /// The key behind a verification method URL.
+    pub fn resolve(&self, verification_method: &str) -> Result<PublicKey> {
+        let (did, _) = split_fragment(verification_method);
+        if did.starts_with("did:key:") {
+            return PublicKey::from_did_key(verification_method);
+        }
+        self.documents
+            .get(did)
+            .ok_or_else(|| CryptoError::InvalidDid(format!("no document loaded for {}", did)))?
+            .assertion_key(verification_method)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::KeyPair;
+
+    #[test]
+    fn test_did_key_roundtrip() {
+        // Test vector from the did:key specification
+        let key = PublicKey::from_multibase("z6MkiTBz1ymuepAQ4HEHYSF1H8quG5GLVVQR3djdX3mDooWp").unwrap();
+        assert_eq!(
+            key.to_hex(),
+            "3b6a27bcceb6a42d62a3a8d02a6f0d73653215771de243a63ac048a18b59da29"
+        );
+
+        let kp = KeyPair::generate();
+        let did = kp.public_key().to_did_key();
+        assert!(did.starts_with("did:key:z6Mk"));
+        assert_eq!(PublicKey::from_did_key(&did).unwrap(), kp.public_key());
+        let method = kp.public_key().did_key_verification_method();
+        assert_eq!(PublicKey::from_did_key(&method).unwrap(), kp.public_key());
+        assert!(PublicKey::from_did_key(&format!("{}#other", did)).is_err());
+        assert!(PublicKey::from_did_key("did:web:example.com").is_err());
+    }
+
+    #[test]
+    fn test_did_web_url() {
+        assert_eq!(did_web_url("did:web:example.com").unwrap(), "https://example.com/.well-known/did.json");
+        assert_eq!(
+            did_web_url("did:web:example.com%3A8443:suppliers:acme#key-1").unwrap(),
+            "https://example.com:8443/suppliers/acme/did.json"
+        );
+        assert!(did_web_url("did:key:z6Mk").is_err());
+        assert!(did_web_url("did:web:example.com::acme").is_err());
+    }
+
+    #[test]
+    fn test_did_web_document_keys() {
+        let kp = KeyPair::generate();
+        let other = KeyPair::generate();
+        let jwk_x = URL_SAFE_NO_PAD.encode(other.public_key().as_bytes());
+        let json = serde_json::json!({
+            "@context": ["https://www.w3.org/ns/did/v1"],
+            "id": "did:web:acme.example",
+            "verificationMethod": [{
+                "id": "#key-1",
+                "type": "Multikey",
+                "controller": "did:web:acme.example",
+                "publicKeyMultibase": kp.public_key().to_multibase()
+            }, {
+                "id": "did:web:acme.example#unlisted",
+                "type": "Multikey",
+                "controller": "did:web:acme.example",
+                "publicKeyMultibase": other.public_key().to_multibase()
+            }],
+            "assertionMethod": ["did:web:acme.example#key-1", {
+                "id": "did:web:acme.example#key-2",
+                "type": "JsonWebKey2020",
+                "controller": "did:web:acme.example",
+                "publicKeyJwk": { "kty": "OKP", "crv": "Ed25519", "x": jwk_x }
+            }]
+        });
+        let document = DidDocument::from_json(&json.to_string()).unwrap();
+
+        let mut resolver = DidResolver::new();
+        resolver.add_document(document);
+        assert_eq!(resolver.resolve("did:web:acme.example#key-1").unwrap(), kp.public_key());
+        assert_eq!(resolver.resolve("did:web:acme.example#key-2").unwrap(), other.public_key());
+        assert!(resolver.resolve("did:web:acme.example#unlisted").is_err());
+        assert!(resolver.resolve("did:web:other.example#key-1").is_err());
+        assert_eq!(
+            resolver.resolve(&kp.public_key().did_key_verification_method()).unwrap(),
+            kp.public_key()
+        );
+
+        let generated = DidDocument::for_key("did:web:acme.example", &kp.public_key());
+        let reparsed = DidDocument::from_json(&serde_json::to_string(&generated).unwrap()).unwrap();
+        let method = &reparsed.verification_method[0].id;
+        assert_eq!(reparsed.assertion_key(method).unwrap(), kp.public_key());
+    }
+}
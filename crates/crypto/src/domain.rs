@@ -0,0 +1,220 @@
+//! Domain-separated signing
+//!
+//! A supplier's key signs commitments, proof packages, revocations, key
+//! rotations and audit heads. Each kind is signed under its own registered
+//! [`Domain`], a tag and a version framed ahead of the payload, so a signature
+//! over one kind can never be passed off as another. Verification always
+//! names the domain it expects. Only the registered domains exist; raw,
+//! unframed signing is private to this crate (see [`crate::signer`]).
+//!
+//! The signed bytes are `"zk-dpp-sig" || len(tag) || tag || version || payload`
+//! with a one-byte tag length and a big-endian `u32` version.
+
+use crate::{CryptoError, PublicKey, Result, Signer};
+use serde::{Deserialize, Serialize};
+use std::fmt;
+
+const FRAME_MAGIC: &[u8] = b"zk-dpp-sig";
+
+/// A registered message kind
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct Domain {
+    tag: &'static str,
+    version: u32,
+}
+
+impl Domain {
+    /// Merkle root of a supplier's claims
+    pub const COMMITMENT: Domain = Domain { tag: "commitment", version: 1 };
+    /// A ZK proof with its public inputs and context
+    pub const PROOF_PACKAGE: Domain = Domain { tag: "proof-package", version: 1 };
+    /// Withdrawal of a commitment
+    pub const REVOCATION: Domain = Domain { tag: "revocation", version: 1 };
+    /// Hand-over from one signing key to the next
+    pub const KEY_ROTATION: Domain = Domain { tag: "key-rotation", version: 1 };
+    /// Head of the Edge Agent's audit log
+    pub const AUDIT_HEAD: Domain = Domain { tag: "audit-head", version: 1 };
+
+    pub fn tag(&self) -> &'static str {
+        self.tag
+    }
+
+    pub fn version(&self) -> u32 {
+        self.version
+    }
+
+    /// The bytes actually signed for `payload` in this domain
+    pub(crate) fn frame(&self, payload: &[u8]) -> Vec<u8> {
+        let mut message = Vec::with_capacity(FRAME_MAGIC.len() + 1 + self.tag.len() + 4 + payload.len());
+        message.extend_from_slice(FRAME_MAGIC);
+        message.push(self.tag.len() as u8);
+        message.extend_from_slice(self.tag.as_bytes());
+        message.extend_from_slice(&self.version.to_be_bytes());
+        message.extend_from_slice(payload);
+        message
+    }
+}
+
+impl fmt::Display for Domain {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}/v{}", self.tag, self.version)
+    }
+}
+
+/// Every domain a supplier key signs in
+pub const REGISTERED_DOMAINS: [Domain; 5] = [
+    Domain::COMMITMENT,
+    Domain::PROOF_PACKAGE,
+    Domain::REVOCATION,
+    Domain::KEY_ROTATION,
+    Domain::AUDIT_HEAD,
+];
+
+/// The registered domain `message` is framed in, if any
+pub(crate) fn framed_domain(message: &[u8]) -> Option<Domain> {
+    REGISTERED_DOMAINS.iter().copied().find(|domain| message.starts_with(&domain.frame(b"")))
+}
+
+/// A message type with a registered domain
+pub trait Signable {
+    const DOMAIN: Domain;
+
+    /// The payload bytes; must encode every field unambiguously.
+    fn signing_payload(&self) -> Result<Vec<u8>>;
+}
+
+/// A payload with its domain, signer and signature
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct SignedMessage<T> {
+    pub domain: String,
+    pub version: u32,
+    pub payload: T,
+    pub public_key: PublicKey,
+    /// Signature over the framed payload (hex-encoded)
+    pub signature: String,
+}
+
+impl<T: Signable> SignedMessage<T> {
+    /// Signs `payload` in `T`'s domain.
+    pub fn sign(payload: T, signer: &dyn Signer) -> Result<Self> {
+        let signature = signer.sign_hex_in(T::DOMAIN, &payload.signing_payload()?)?;
+        Ok(SignedMessage {
+            domain: T::DOMAIN.tag().to_string(),
+            version: T::DOMAIN.version(),
+            payload,
+            public_key: signer.public_key(),
+            signature,
+        })
+    }
+
+    /// Checks that the envelope is in `T`'s domain and the signature is
+    /// valid for its public key.
+    pub fn verify(&self) -> Result<()> {
+        if self.domain != T::DOMAIN.tag() || self.version != T::DOMAIN.version() {
+            return Err(CryptoError::WrongDomain {
+                expected: T::DOMAIN.to_string(),
+                found: format!("{}/v{}", self.domain, self.version),
+            });
+        }
+        if !self.public_key.verify_hex_in(T::DOMAIN, &self.payload.signing_payload()?, &self.signature)? {
+            return Err(CryptoError::InvalidSignature);
+        }
+        Ok(())
+    }
+
+    /// Like [`SignedMessage::verify`], and also requires `expected` to be
+    /// the signer.
+    pub fn verify_from(&self, expected: &PublicKey) -> Result<()> {
+        if &self.public_key != expected {
+            return Err(CryptoError::InvalidSignature);
+        }
+        self.verify()
+    }
+}
+
+/// Appends `field` with a length prefix, for unambiguous payload encodings
+pub fn push_field(payload: &mut Vec<u8>, field: &[u8]) {
+    payload.extend_from_slice(&(field.len() as u64).to_be_bytes());
+    payload.extend_from_slice(field);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::KeyPair;
+
+    #[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+    struct Notice(String);
+
+    impl Signable for Notice {
+        const DOMAIN: Domain = Domain::REVOCATION;
+
+        fn signing_payload(&self) -> Result<Vec<u8>> {
+            Ok(self.0.as_bytes().to_vec())
+        }
+    }
+
+    #[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+    struct Root(String);
+
+    impl Signable for Root {
+        const DOMAIN: Domain = Domain::COMMITMENT;
+
+        fn signing_payload(&self) -> Result<Vec<u8>> {
+            Ok(self.0.as_bytes().to_vec())
+        }
+    }
+
+    #[test]
+    fn test_registered_domains_are_distinct() {
+        for (i, a) in REGISTERED_DOMAINS.iter().enumerate() {
+            for b in &REGISTERED_DOMAINS[i + 1..] {
+                assert_ne!(a.tag, b.tag);
+                assert_ne!(a.frame(b"payload"), b.frame(b"payload"));
+            }
+        }
+    }
+
+    #[test]
+    fn test_signed_message_roundtrip() {
+        let kp = KeyPair::generate();
+        let signed = SignedMessage::sign(Notice("superseded".to_string()), &kp).unwrap();
+        assert_eq!(signed.domain, "revocation");
+        signed.verify().unwrap();
+        signed.verify_from(&kp.public_key()).unwrap();
+        assert!(signed.verify_from(&KeyPair::generate().public_key()).is_err());
+
+        let json = serde_json::to_string(&signed).unwrap();
+        let parsed: SignedMessage<Notice> = serde_json::from_str(&json).unwrap();
+        parsed.verify().unwrap();
+
+        let mut altered = signed;
+        altered.payload = Notice("expired".to_string());
+        assert!(matches!(altered.verify(), Err(CryptoError::InvalidSignature)));
+    }
+
+    #[test]
+    fn test_signature_not_replayable_across_domains() {
+        let kp = KeyPair::generate();
+        let signed = SignedMessage::sign(Notice("same bytes".to_string()), &kp).unwrap();
+
+        // Relabelled as a commitment, the envelope names the wrong domain
+        let relabelled = SignedMessage {
+            domain: signed.domain.clone(),
+            version: signed.version,
+            payload: Root("same bytes".to_string()),
+            public_key: signed.public_key,
+            signature: signed.signature.clone(),
+        };
+        assert!(matches!(relabelled.verify(), Err(CryptoError::WrongDomain { .. })));
+
+        // Claiming the commitment domain does not help either
+        let forged = SignedMessage { domain: "commitment".to_string(), ..relabelled };
+        assert!(matches!(forged.verify(), Err(CryptoError::InvalidSignature)));
+        assert!(!kp
+            .public_key()
+            .verify_hex_in(Domain::COMMITMENT, b"same bytes", &signed.signature)
+            .unwrap());
+        assert!(!kp.public_key().verify_hex(b"same bytes", &signed.signature).unwrap());
+    }
+}
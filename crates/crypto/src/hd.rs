@@ -0,0 +1,209 @@
+//! Hierarchical deterministic keys
+//!
+//! A BIP-39 recovery phrase encodes a seed, and SLIP-0010 derives Ed25519
+//! keys from that seed along hardened paths such as `m/0'/3'`. One phrase can
+//! therefore stand in for any number of signing keys, e.g. one per product
+//! line or site, each rebuilt from its path.
+
+use crate::secret::{ExposeSecret, SecretBytes, SecretString};
+use crate::{CryptoError, KeyPair, Result};
+use hmac::{Hmac, Mac};
+use rand::rngs::OsRng;
+use rand::RngCore;
+use sha2::Sha512;
+use std::fmt;
+use std::str::FromStr;
+use zeroize::Zeroizing;
+
+/// Words in a generated recovery phrase (256 bits of entropy)
+pub const MNEMONIC_WORDS: usize = 24;
+
+const SLIP10_ED25519_KEY: &[u8] = b"ed25519 seed";
+const HARDENED: u32 = 0x8000_0000;
+
+/// A BIP-39 recovery phrase (English word list)
+pub struct Mnemonic(bip39::Mnemonic);
+
+impl Mnemonic {
+    /// Generates a new 24-word phrase from OS entropy.
+    pub fn generate() -> Self {
+        let mut entropy = Zeroizing::new([0u8; 32]);
+        OsRng.fill_bytes(entropy.as_mut_slice());
+        // 32 bytes is always a valid entropy length
+        Mnemonic(bip39::Mnemonic::from_entropy(entropy.as_slice()).expect("valid entropy length"))
+    }
+
+    /// Parses a phrase, checking its words and checksum.
+    pub fn from_phrase(phrase: &str) -> Result<Self> {
+        let phrase = Zeroizing::new(phrase.split_whitespace().collect::<Vec<_>>().join(" ").to_lowercase());
+        bip39::Mnemonic::parse_normalized(&phrase)
+            .map(Mnemonic)
+            .map_err(|e| CryptoError::Mnemonic(e.to_string()))
+    }
+
+    /// The words, space-separated.
+    pub fn phrase(&self) -> SecretString {
+        SecretString::new(self.0.to_string())
+    }
+
+    /// The 64-byte BIP-39 seed; `passphrase` is the optional extra word
+    /// (empty for none).
+    pub fn to_seed(&self, passphrase: &str) -> SecretBytes<64> {
+        SecretBytes::new(self.0.to_seed(passphrase))
+    }
+}
+
+/// A SLIP-0010 Ed25519 derivation path. Ed25519 only supports hardened
+/// derivation, so every component is hardened.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct DerivationPath(Vec<u32>);
+
+impl DerivationPath {
+    /// A path from unhardened indices, e.g. `[0, 3]` for `m/0'/3'`.
+    pub fn new(indices: Vec<u32>) -> Result<Self> {
+        if let Some(index) = indices.iter().find(|index| **index >= HARDENED) {
+            return Err(CryptoError::DerivationPath(format!("index {} is out of range", index)));
+        }
+        Ok(DerivationPath(indices))
+    }
+
+    pub fn indices(&self) -> &[u32] {
+        &self.0
+    }
+}
+
+impl FromStr for DerivationPath {
+    type Err = CryptoError;
+
+    fn from_str(path: &str) -> Result<Self> {
+        let invalid = |reason: &str| CryptoError::DerivationPath(format!("{}: {}", path, reason));
+        let mut parts = path.trim().split('/');
+        if parts.next() != Some("m") {
+            return Err(invalid("must start with m"));
+        }
+        let indices = parts
+            .map(|part| {
+                let index = part
+                    .strip_suffix('\'')
+                    .or_else(|| part.strip_suffix('h'))
+                    .or_else(|| part.strip_suffix('H'))
+                    .ok_or_else(|| invalid("Ed25519 paths must be fully hardened"))?;
+                index.parse::<u32>().map_err(|_| invalid("invalid index"))
+            })
+            .collect::<Result<Vec<_>>>()?;
+        DerivationPath::new(indices)
+    }
+}
+
+impl fmt::Display for DerivationPath {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "m")?;
+        for index in &self.0 {
+            write!(f, "/{}'", index)?;
+        }
+        Ok(())
+    }
+}
+
+impl KeyPair {
+    /// Derives the keypair at `path` from a BIP-39 seed (SLIP-0010).
+    pub fn derive(seed: &[u8], path: &DerivationPath) -> Result<Self> {
+        let (mut key, mut chain_code) = slip10_step(SLIP10_ED25519_KEY, &[seed])?;
+        for index in path.indices() {
+            let (child_key, child_chain_code) = slip10_step(
+                chain_code.as_slice(),
+                &[&[0u8], key.as_slice(), &(index | HARDENED).to_be_bytes()],
+            )?;
+            key = child_key;
+            chain_code = child_chain_code;
+        }
+        KeyPair::from_bytes(key.as_slice())
+    }
+
+    /// Derives the keypair at `path` from a recovery phrase with no BIP-39
+    /// passphrase.
+    pub fn derive_from_mnemonic(mnemonic: &Mnemonic, path: &DerivationPath) -> Result<Self> {
+        KeyPair::derive(mnemonic.to_seed("").expose_secret(), path)
+    }
+}
+
+/// A 32-byte half of a SLIP-0010 node, wiped on drop
+type NodeHalf = Zeroizing<[u8; 32]>;
+
+/// One HMAC-SHA512 step, split into key and chain code
+fn slip10_step(hmac_key: &[u8], data: &[&[u8]]) -> Result<(NodeHalf, NodeHalf)> {
+    let mut mac = Hmac::<Sha512>::new_from_slice(hmac_key)
+        .map_err(|e| CryptoError::DerivationPath(e.to_string()))?;
+    for part in data {
+        mac.update(part);
+    }
+    let output = Zeroizing::new(<[u8; 64]>::from(mac.finalize().into_bytes()));
+    let mut key = Zeroizing::new([0u8; 32]);
+    let mut chain_code = Zeroizing::new([0u8; 32]);
+    key.copy_from_slice(&output[..32]);
+    chain_code.copy_from_slice(&output[32..]);
+    Ok((key, chain_code))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn secret_hex(keypair: &KeyPair) -> String {
+        hex::encode(keypair.secret_bytes().expose_secret())
+    }
+
+    #[test]
+    fn test_slip10_ed25519_vector() {
+        // SLIP-0010 test vector 1 for ed25519
+        let seed = hex::decode("000102030405060708090a0b0c0d0e0f").unwrap();
+        let cases = [
+            ("m", "2b4be7f19ee27bbf30c667b642d5f4aa69fd169872f8fc3059c08ebae2eb19e7"),
+            ("m/0'", "68e0fe46dfb67e368c75379acec591dad19df3cde26e63b93a8e704f1dade7a3"),
+            (
+                "m/0'/1'/2'/2'/1000000000'",
+                "8f94d394a8e8fd6b1bc2f3f49f5c47e385281d5c17e65324b0f62483e37e8793",
+            ),
+        ];
+        for (path, secret) in cases {
+            let keypair = KeyPair::derive(&seed, &path.parse().unwrap()).unwrap();
+            assert_eq!(secret_hex(&keypair), secret, "{}", path);
+        }
+    }
+
+    #[test]
+    fn test_mnemonic_seed_vector() {
+        // BIP-39 reference vector for all-zero entropy
+        let phrase = "abandon abandon abandon abandon abandon abandon abandon abandon abandon abandon abandon about";
+        let mnemonic = Mnemonic::from_phrase(&format!("  {}  ", phrase.to_uppercase())).unwrap();
+        assert_eq!(mnemonic.phrase().expose_secret(), phrase);
+        assert_eq!(
+            hex::encode(mnemonic.to_seed("TREZOR").expose_secret()),
+            "c55257c360c07c72029aebc1b53c05ed0362ada38ead3e3e9efa3708e53495531f09a6987599d18264c1e1c92f2cf141630c7a3c4ab7c81b2f001698e7463b04"
+        );
+        assert!(Mnemonic::from_phrase(&phrase.replace("about", "abandon")).is_err());
+    }
+
+    #[test]
+    fn test_keys_rebuilt_from_phrase() {
+        let mnemonic = Mnemonic::generate();
+        assert_eq!(mnemonic.phrase().expose_secret().split(' ').count(), MNEMONIC_WORDS);
+        let path: DerivationPath = "m/0'/3'".parse().unwrap();
+        let line_key = KeyPair::derive_from_mnemonic(&mnemonic, &path).unwrap();
+
+        let recovered = Mnemonic::from_phrase(mnemonic.phrase().expose_secret()).unwrap();
+        assert_eq!(KeyPair::derive_from_mnemonic(&recovered, &path).unwrap().public_key(), line_key.public_key());
+        let other_line = KeyPair::derive_from_mnemonic(&recovered, &"m/1'/3'".parse().unwrap()).unwrap();
+        assert_ne!(other_line.public_key(), line_key.public_key());
+    }
+
+    #[test]
+    fn test_derivation_path_parsing() {
+        let path: DerivationPath = "m/44h/0'/7H".parse().unwrap();
+        assert_eq!(path.indices(), &[44, 0, 7]);
+        assert_eq!(path.to_string(), "m/44'/0'/7'");
+        assert!("m/0'/1".parse::<DerivationPath>().is_err());
+        assert!("0'/1'".parse::<DerivationPath>().is_err());
+        assert!("m/2147483648'".parse::<DerivationPath>().is_err());
+    }
+}
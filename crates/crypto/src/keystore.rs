@@ -0,0 +1,287 @@
+//! Password-protected keystore format
+//!
+//! A keystore is a versioned JSON document holding one secret key sealed
+//! with AES-256-GCM under a key derived from a password with Argon2id. The
+//! header (version, public key, KDF parameters) is bound to the ciphertext as
+//! associated data, and a BLAKE3 checksum over the whole document tells a
+//! damaged file apart from a wrong password.
+
+use crate::{CryptoError, ExposeSecret, KeyPair, Result};
+use aes_gcm::aead::{Aead, Payload};
+use aes_gcm::{Aes256Gcm, KeyInit, Nonce};
+use argon2::{Algorithm, Argon2, Params, Version};
+use rand::rngs::OsRng;
+use rand::RngCore;
+use serde::{Deserialize, Serialize};
+use zeroize::Zeroizing;
+
+/// Current keystore format version
+pub const KEYSTORE_VERSION: u32 = 1;
+
+const KDF_ARGON2ID: &str = "argon2id";
+const CIPHER_AES_256_GCM: &str = "aes-256-gcm";
+const AAD_DOMAIN: &[u8] = b"zk-dpp keystore v1";
+const CHECKSUM_DOMAIN: &[u8] = b"zk-dpp keystore checksum v1";
+const SALT_LEN: usize = 16;
+const NONCE_LEN: usize = 12;
+
+/// Argon2id cost parameters (memory in KiB)
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub struct KeystoreParams {
+    pub m_cost: u32,
+    pub t_cost: u32,
+    pub p_cost: u32,
+}
+
+impl Default for KeystoreParams {
+    fn default() -> Self {
+        KeystoreParams { m_cost: 64 * 1024, t_cost: 3, p_cost: 1 }
+    }
+}
+
+impl KeystoreParams {
+    /// Highest costs accepted: 1 GiB of memory, 16 passes, 8 lanes. An
+    /// imported keystore asking for more is rejected before any hashing, so
+    /// a crafted file cannot exhaust memory or stall the import.
+    pub const MAX: KeystoreParams = KeystoreParams { m_cost: 1024 * 1024, t_cost: 16, p_cost: 8 };
+
+    /// Fails if any cost exceeds [`KeystoreParams::MAX`].
+    pub fn check_limits(&self) -> Result<()> {
+        let max = Self::MAX;
+        if self.m_cost > max.m_cost || self.t_cost > max.t_cost || self.p_cost > max.p_cost {
+            return Err(CryptoError::Keystore(format!(
+                "KDF parameters m={} t={} p={} exceed the limits m={} t={} p={}",
+                self.m_cost, self.t_cost, self.p_cost, max.m_cost, max.t_cost, max.p_cost
+            )));
+        }
+        Ok(())
+    }
+}
+
+/// Key derivation settings stored in a keystore
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct KeystoreKdf {
+    pub algorithm: String,
+    /// Salt (hex-encoded)
+    pub salt: String,
+    #[serde(flatten)]
+    pub params: KeystoreParams,
+}
+
+/// A secret key sealed under a password
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct EncryptedKeystore {
+    pub version: u32,
+    /// Public key of the sealed keypair (hex-encoded)
+    pub public_key: String,
+    pub kdf: KeystoreKdf,
+    pub cipher: String,
+    /// AEAD nonce (hex-encoded)
+    pub nonce: String,
+    /// Sealed secret key (hex-encoded)
+    pub ciphertext: String,
+    /// BLAKE3 over every other field (hex-encoded)
+    pub checksum: String,
+}
+
+impl EncryptedKeystore {
+    /// Seals `keypair` under `password`.
+    pub fn seal(keypair: &KeyPair, password: &str, params: KeystoreParams) -> Result<Self> {
+        if password.is_empty() {
+            return Err(CryptoError::Keystore("password must not be empty".to_string()));
+        }
+        let mut salt = [0u8; SALT_LEN];
+        let mut nonce = [0u8; NONCE_LEN];
+        OsRng.fill_bytes(&mut salt);
+        OsRng.fill_bytes(&mut nonce);
+
+        let mut keystore = EncryptedKeystore {
+            version: KEYSTORE_VERSION,
+            public_key: keypair.public_key().to_hex(),
+            kdf: KeystoreKdf {
+                algorithm: KDF_ARGON2ID.to_string(),
+                salt: hex::encode(salt),
+                params,
+            },
+            cipher: CIPHER_AES_256_GCM.to_string(),
+            nonce: hex::encode(nonce),
+            ciphertext: String::new(),
+            checksum: String::new(),
+        };
+        let cipher = Aes256Gcm::new_from_slice(derive_key(password, &salt, params)?.as_slice())
+            .map_err(|e| CryptoError::Keystore(e.to_string()))?;
+        let secret = keypair.secret_bytes();
+        let ciphertext = cipher
+            .encrypt(Nonce::from_slice(&nonce), Payload { msg: secret.expose_secret(), aad: &keystore.aad() })
+            .map_err(|_| CryptoError::Keystore("encryption failed".to_string()))?;
+        keystore.ciphertext = hex::encode(ciphertext);
+        keystore.checksum = keystore.compute_checksum();
+        Ok(keystore)
+    }
+
+    /// Recovers the keypair, checking the format, checksum, password and that
+    /// the key matches the stored public key.
+    pub fn open(&self, password: &str) -> Result<KeyPair> {
+        if self.version != KEYSTORE_VERSION {
+            return Err(CryptoError::Keystore(format!("unsupported version {}", self.version)));
+        }
+        if self.kdf.algorithm != KDF_ARGON2ID || self.cipher != CIPHER_AES_256_GCM {
+            return Err(CryptoError::Keystore(format!(
+                "unsupported algorithms {} / {}",
+                self.kdf.algorithm, self.cipher
+            )));
+        }
+        if self.compute_checksum() != self.checksum.to_ascii_lowercase() {
+            return Err(CryptoError::Keystore("checksum mismatch; the keystore is damaged".to_string()));
+        }
+
+        let salt = hex::decode(&self.kdf.salt)?;
+        let nonce = hex::decode(&self.nonce)?;
+        if nonce.len() != NONCE_LEN {
+            return Err(CryptoError::Keystore("invalid nonce".to_string()));
+        }
+        let cipher = Aes256Gcm::new_from_slice(derive_key(password, &salt, self.kdf.params)?.as_slice())
+            .map_err(|e| CryptoError::Keystore(e.to_string()))?;
+        let secret = cipher
+            .decrypt(
+                Nonce::from_slice(&nonce),
+                Payload { msg: &hex::decode(&self.ciphertext)?, aad: &self.aad() },
+            )
+            .map(Zeroizing::new)
+            .map_err(|_| CryptoError::WrongPassword)?;
+
+        let keypair = KeyPair::from_bytes(&secret)?;
+        if !keypair.public_key().to_hex().eq_ignore_ascii_case(&self.public_key) {
+            return Err(CryptoError::Keystore("secret key does not match the public key".to_string()));
+        }
+        Ok(keypair)
+    }
+
+    /// Header fields bound to the ciphertext
+    fn aad(&self) -> Vec<u8> {
+        let mut aad = AAD_DOMAIN.to_vec();
+        aad.extend_from_slice(&self.version.to_be_bytes());
+        aad.extend_from_slice(self.public_key.to_ascii_lowercase().as_bytes());
+        aad.extend_from_slice(&self.kdf.params.m_cost.to_be_bytes());
+        aad.extend_from_slice(&self.kdf.params.t_cost.to_be_bytes());
+        aad.extend_from_slice(&self.kdf.params.p_cost.to_be_bytes());
+        aad
+    }
+
+    fn compute_checksum(&self) -> String {
+        let mut hasher = blake3::Hasher::new();
+        hasher.update(CHECKSUM_DOMAIN);
+        for field in [
+            self.version.to_string().as_str(),
+            &self.public_key,
+            &self.kdf.algorithm,
+            &self.kdf.salt,
+            &self.kdf.params.m_cost.to_string(),
+            &self.kdf.params.t_cost.to_string(),
+            &self.kdf.params.p_cost.to_string(),
+            &self.cipher,
+            &self.nonce,
+            &self.ciphertext,
+        ] {
+            let field = field.to_ascii_lowercase();
+            hasher.update(&(field.len() as u64).to_be_bytes());
+            hasher.update(field.as_bytes());
+        }
+        hasher.finalize().to_hex().to_string()
+    }
+}
+
+impl KeyPair {
+    /// Exports the keypair as keystore JSON sealed under `password`.
+    pub fn export_encrypted(&self, password: &str) -> Result<String> {
+        self.export_encrypted_with(password, KeystoreParams::default())
+    }
+
+    /// Like [`KeyPair::export_encrypted`], with explicit Argon2id costs.
+    pub fn export_encrypted_with(&self, password: &str, params: KeystoreParams) -> Result<String> {
+        Ok(serde_json::to_string_pretty(&EncryptedKeystore::seal(self, password, params)?)?)
+    }
+
+    /// Imports a keypair from keystore JSON.
+    pub fn import_encrypted(json: &str, password: &str) -> Result<Self> {
+        let keystore: EncryptedKeystore = serde_json::from_str(json)?;
+        keystore.open(password)
+    }
+}
+
+fn derive_key(password: &str, salt: &[u8], params: KeystoreParams) -> Result<Zeroizing<[u8; 32]>> {
+    params.check_limits()?;
+    let params = Params::new(params.m_cost, params.t_cost, params.p_cost, Some(32))
+        .map_err(|e| CryptoError::Keystore(format!("invalid KDF parameters: {}", e)))?;
+    let mut key = Zeroizing::new([0u8; 32]);
+    Argon2::new(Algorithm::Argon2id, Version::V0x13, params)
+        .hash_password_into(password.as_bytes(), salt, key.as_mut_slice())
+        .map_err(|e| CryptoError::Keystore(format!("key derivation failed: {}", e)))?;
+    Ok(key)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Minimum Argon2 costs, so tests do not spend seconds deriving keys
+    const TEST_PARAMS: KeystoreParams = KeystoreParams { m_cost: 8, t_cost: 1, p_cost: 1 };
+
+    #[test]
+    fn test_keystore_roundtrip() {
+        let kp = KeyPair::generate();
+        let json = kp.export_encrypted_with("correct horse battery staple", TEST_PARAMS).unwrap();
+        assert!(!json.contains(&hex::encode(kp.secret_bytes().expose_secret())));
+
+        let restored = KeyPair::import_encrypted(&json, "correct horse battery staple").unwrap();
+        assert_eq!(restored.public_key(), kp.public_key());
+        assert!(matches!(
+            KeyPair::import_encrypted(&json, "wrong password"),
+            Err(CryptoError::WrongPassword)
+        ));
+    }
+
+    #[test]
+    fn test_tampered_keystore_rejected() {
+        let kp = KeyPair::generate();
+        let keystore = EncryptedKeystore::seal(&kp, "correct horse battery staple", TEST_PARAMS).unwrap();
+
+        let mut damaged = keystore.clone();
+        damaged.ciphertext.replace_range(0..2, if damaged.ciphertext.starts_with("00") { "11" } else { "00" });
+        assert!(matches!(damaged.open("correct horse battery staple"), Err(CryptoError::Keystore(_))));
+
+        // A consistent checksum does not help once the header no longer
+        // matches the ciphertext
+        let mut swapped = keystore.clone();
+        swapped.public_key = KeyPair::generate().public_key().to_hex();
+        swapped.checksum = swapped.compute_checksum();
+        assert!(matches!(swapped.open("correct horse battery staple"), Err(CryptoError::WrongPassword)));
+
+        let mut future = keystore;
+        future.version = KEYSTORE_VERSION + 1;
+        assert!(future.open("correct horse battery staple").is_err());
+    }
+
+    #[test]
+    fn test_excessive_kdf_costs_rejected() {
+        let kp = KeyPair::generate();
+        let keystore = EncryptedKeystore::seal(&kp, "correct horse battery staple", TEST_PARAMS).unwrap();
+
+        // Each would take gigabytes or hours if it reached Argon2
+        for params in [
+            KeystoreParams { m_cost: u32::MAX, ..TEST_PARAMS },
+            KeystoreParams { t_cost: u32::MAX, ..TEST_PARAMS },
+            KeystoreParams { p_cost: 1 << 20, ..TEST_PARAMS },
+        ] {
+            let mut crafted = keystore.clone();
+            crafted.kdf.params = params;
+            crafted.checksum = crafted.compute_checksum();
+            match crafted.open("correct horse battery staple") {
+                Err(CryptoError::Keystore(message)) => assert!(message.contains("exceed the limits")),
+                other => panic!("expected a limit error, got {:?}", other.map(|kp| kp.public_key())),
+            }
+        }
+        assert!(EncryptedKeystore::seal(&kp, "password", KeystoreParams { m_cost: u32::MAX, ..TEST_PARAMS }).is_err());
+        KeystoreParams::default().check_limits().unwrap();
+    }
+}
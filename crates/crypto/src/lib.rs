@@ -1,11 +1,42 @@
 //! Cryptographic primitives for ZK-DPP
 //!
-//! Provides Ed25519 key generation, signing, and verification.
-
-use ed25519_dalek::{Signature, Signer, SigningKey, Verifier, VerifyingKey, SECRET_KEY_LENGTH};
+//! Provides Ed25519 key generation, signing, and verification (singly or in
+//! batches), plus deterministic derivation from a recovery phrase,
+//! certificates linking a rotated key to its successor and a
+//! password-protected keystore for backing keys up. Supplier keys sign in
+//! registered domains (see [`domain`]) so signatures cannot be replayed across
+//! message kinds. Keys are also named as DIDs ([`did`]) and can secure
+//! verifiable credentials with Data Integrity proofs ([`data_integrity`]).
+//! Secret key material is only handed out in
+//! zeroize-on-drop wrappers (see [`secret`]), and [`Signer`] lets callers sign
+//! with keys held outside the process.
+
+pub mod batch;
+pub mod data_integrity;
+pub mod did;
+pub mod domain;
+pub mod hd;
+pub mod keystore;
+pub mod rotation;
+pub mod secret;
+pub mod signer;
+
+pub use batch::verify_batch;
+pub use did::{DidDocument, DidResolver};
+pub use domain::{Domain, Signable, SignedMessage};
+pub use ed25519_dalek::Signature;
+pub use hd::{DerivationPath, Mnemonic};
+pub use keystore::{EncryptedKeystore, KeystoreParams};
+pub use rotation::{verify_key_chain, RotationCertificate};
+pub use secret::{ExposeSecret, SecretBytes, SecretString, SecretVec};
+pub use signer::Signer;
+
+use ed25519_dalek::{Signer as _, SigningKey, Verifier, VerifyingKey, SECRET_KEY_LENGTH};
 use rand::rngs::OsRng;
 use serde::{Deserialize, Serialize};
+use std::fmt;
 use thiserror::Error;
+use zeroize::Zeroizing;
 
 /// Errors that can occur in cryptographic operations
 #[derive(Error, Debug)]
@@ -24,6 +55,36 @@ pub enum CryptoError {
 
     #[error("Hex decoding error: {0}")]
     HexDecode(#[from] hex::FromHexError),
+
+    #[error("Invalid key rotation: {0}")]
+    InvalidRotation(String),
+
+    #[error("Keystore error: {0}")]
+    Keystore(String),
+
+    #[error("Incorrect keystore password")]
+    WrongPassword,
+
+    #[error("Invalid recovery phrase: {0}")]
+    Mnemonic(String),
+
+    #[error("Invalid derivation path: {0}")]
+    DerivationPath(String),
+
+    #[error("Signed in domain {found}, expected {expected}")]
+    WrongDomain { expected: String, found: String },
+
+    #[error("Invalid DID: {0}")]
+    InvalidDid(String),
+
+    #[error("Invalid Data Integrity proof: {0}")]
+    InvalidProof(String),
+
+    #[error("Invalid signatures at positions {0:?}")]
+    InvalidSignatures(Vec<usize>),
+
+    #[error("Signer error: {0}")]
+    Signer(String),
 }
 
 pub type Result<T> = std::result::Result<T, CryptoError>;
@@ -34,20 +95,26 @@ pub struct KeyPair {
     signing_key: SigningKey,
 }
 
-/// A serializable representation of a keypair
-#[derive(Debug, Clone, Serialize, Deserialize)]
+/// A serializable representation of a keypair. The secret key is in the
+/// clear once serialized; use [`KeyPair::export_encrypted`] for anything
+/// written to disk.
+#[derive(Serialize, Deserialize)]
 pub struct SerializableKeyPair {
     /// Secret key bytes (hex-encoded)
-    pub secret_key: String,
+    pub secret_key: SecretString,
     /// Public key bytes (hex-encoded)
     pub public_key: String,
 }
 
-/// A serializable public key for verification
-#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
-pub struct PublicKey {
-    /// Public key bytes (hex-encoded)
-    pub key: String,
+/// An Ed25519 public key, kept parsed so verifying does not decode it
+/// again. Serializes as `{"key": "<hex>"}`.
+#[derive(Clone, Copy, PartialEq, Eq, Hash)]
+pub struct PublicKey(VerifyingKey);
+
+/// Wire form of [`PublicKey`]
+#[derive(Serialize, Deserialize)]
+struct PublicKeyRepr {
+    key: String,
 }
 
 impl KeyPair {
@@ -66,7 +133,7 @@ impl KeyPair {
             });
         }
 
-        let mut key_bytes = [0u8; SECRET_KEY_LENGTH];
+        let mut key_bytes = Zeroizing::new([0u8; SECRET_KEY_LENGTH]);
         key_bytes.copy_from_slice(secret_bytes);
 
         let signing_key = SigningKey::from_bytes(&key_bytes);
@@ -75,80 +142,90 @@ impl KeyPair {
 
     /// Creates a keypair from a hex-encoded secret key.
     pub fn from_hex(hex_str: &str) -> Result<Self> {
-        let bytes = hex::decode(hex_str)?;
+        let bytes = Zeroizing::new(hex::decode(hex_str)?);
         Self::from_bytes(&bytes)
     }
 
     /// Returns the public key.
     pub fn public_key(&self) -> PublicKey {
-        let verifying_key = self.signing_key.verifying_key();
-        PublicKey {
-            key: hex::encode(verifying_key.as_bytes()),
-        }
+        PublicKey(self.signing_key.verifying_key())
     }
 
-    /// Returns the secret key bytes (handle with care!).
-    pub fn secret_bytes(&self) -> [u8; SECRET_KEY_LENGTH] {
-        self.signing_key.to_bytes()
+    /// Returns the secret key bytes, wiped when the wrapper is dropped.
+    pub fn secret_bytes(&self) -> SecretBytes<SECRET_KEY_LENGTH> {
+        SecretBytes::new(self.signing_key.to_bytes())
     }
 
     /// Serializes the keypair to a portable format.
     pub fn to_serializable(&self) -> SerializableKeyPair {
         SerializableKeyPair {
-            secret_key: hex::encode(self.secret_bytes()),
-            public_key: self.public_key().key,
+            secret_key: SecretString::new(hex::encode(self.secret_bytes().expose_secret())),
+            public_key: self.public_key().to_hex(),
         }
     }
 
     /// Deserializes a keypair from its portable format.
     pub fn from_serializable(s: &SerializableKeyPair) -> Result<Self> {
-        Self::from_hex(&s.secret_key)
+        Self::from_hex(s.secret_key.expose_secret())
     }
 
-    /// Signs a message and returns the signature bytes.
-    pub fn sign(&self, message: &[u8]) -> [u8; 64] {
+    /// Signs raw bytes with no domain. Supplier signatures go through
+    /// [`Signer::sign_in`] or [`SignedMessage`] instead.
+    pub(crate) fn sign(&self, message: &[u8]) -> [u8; 64] {
         let signature = self.signing_key.sign(message);
         signature.to_bytes()
     }
 
-    /// Signs a message and returns a hex-encoded signature.
-    pub fn sign_hex(&self, message: &[u8]) -> String {
+    /// Signs raw bytes with no domain, hex-encoded.
+    pub(crate) fn sign_hex(&self, message: &[u8]) -> String {
         hex::encode(self.sign(message))
     }
 }
 
 impl PublicKey {
+    /// Creates a public key from its 32-byte encoding.
+    pub fn from_bytes(bytes: &[u8; 32]) -> Result<Self> {
+        VerifyingKey::from_bytes(bytes)
+            .map(PublicKey)
+            .map_err(|e| CryptoError::KeyParsing(e.to_string()))
+    }
+
     /// Creates a public key from hex-encoded bytes.
     pub fn from_hex(hex_str: &str) -> Result<Self> {
-        // Validate the hex decodes to correct length
         let bytes = hex::decode(hex_str)?;
-        if bytes.len() != 32 {
-            return Err(CryptoError::InvalidKeyLength {
-                expected: 32,
-                got: bytes.len(),
-            });
-        }
-        Ok(PublicKey {
-            key: hex_str.to_string(),
-        })
+        let bytes: [u8; 32] = bytes.as_slice().try_into().map_err(|_| CryptoError::InvalidKeyLength {
+            expected: 32,
+            got: bytes.len(),
+        })?;
+        Self::from_bytes(&bytes)
     }
 
-    /// Verifies a signature against a message.
-    pub fn verify(&self, message: &[u8], signature: &[u8; 64]) -> Result<bool> {
-        let key_bytes = hex::decode(&self.key)?;
-        let mut arr = [0u8; 32];
-        arr.copy_from_slice(&key_bytes);
+    pub fn as_bytes(&self) -> &[u8; 32] {
+        self.0.as_bytes()
+    }
 
-        let verifying_key =
-            VerifyingKey::from_bytes(&arr).map_err(|e| CryptoError::KeyParsing(e.to_string()))?;
+    /// The key hex-encoded (lowercase).
+    pub fn to_hex(&self) -> String {
+        hex::encode(self.as_bytes())
+    }
 
-        let sig = Signature::from_bytes(signature);
+    /// Verifies a signature over raw bytes with no domain.
+    pub(crate) fn verify(&self, message: &[u8], signature: &[u8; 64]) -> Result<bool> {
+        Ok(self.0.verify(message, &Signature::from_bytes(signature)).is_ok())
+    }
+
+    /// Verifies a signature over `payload` in `domain`.
+    pub fn verify_in(&self, domain: Domain, payload: &[u8], signature: &[u8; 64]) -> Result<bool> {
+        self.verify(&domain.frame(payload), signature)
+    }
 
-        Ok(verifying_key.verify(message, &sig).is_ok())
+    /// Verifies a hex-encoded signature over `payload` in `domain`.
+    pub fn verify_hex_in(&self, domain: Domain, payload: &[u8], signature_hex: &str) -> Result<bool> {
+        self.verify_hex(&domain.frame(payload), signature_hex)
     }
 
-    /// Verifies a hex-encoded signature against a message.
-    pub fn verify_hex(&self, message: &[u8], signature_hex: &str) -> Result<bool> {
+    /// Verifies a hex-encoded signature over raw bytes with no domain.
+    pub(crate) fn verify_hex(&self, message: &[u8], signature_hex: &str) -> Result<bool> {
         let sig_bytes = hex::decode(signature_hex)?;
         if sig_bytes.len() != 64 {
             return Err(CryptoError::InvalidSignature);
@@ -159,19 +236,34 @@ impl PublicKey {
     }
 }
 
-/// Convenience function to generate a new keypair.
-pub fn generate_keypair() -> KeyPair {
-    KeyPair::generate()
+impl fmt::Debug for PublicKey {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "PublicKey({})", self.to_hex())
+    }
+}
+
+impl fmt::Display for PublicKey {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(&self.to_hex())
+    }
+}
+
+impl Serialize for PublicKey {
+    fn serialize<S: serde::Serializer>(&self, serializer: S) -> std::result::Result<S::Ok, S::Error> {
+        PublicKeyRepr { key: self.to_hex() }.serialize(serializer)
+    }
 }
 
-/// Convenience function to sign a message.
-pub fn sign(keypair: &KeyPair, message: &[u8]) -> [u8; 64] {
-    keypair.sign(message)
+impl<'de> Deserialize<'de> for PublicKey {
+    fn deserialize<D: serde::Deserializer<'de>>(deserializer: D) -> std::result::Result<Self, D::Error> {
+        let repr = PublicKeyRepr::deserialize(deserializer)?;
+        PublicKey::from_hex(&repr.key).map_err(serde::de::Error::custom)
+    }
 }
 
-/// Convenience function to verify a signature.
-pub fn verify(public_key: &PublicKey, message: &[u8], signature: &[u8; 64]) -> Result<bool> {
-    public_key.verify(message, signature)
+/// Convenience function to generate a new keypair.
+pub fn generate_keypair() -> KeyPair {
+    KeyPair::generate()
 }
 
 #[cfg(test)]
@@ -182,7 +274,7 @@ mod tests {
     fn test_generate_keypair() {
         let kp = KeyPair::generate();
         let pk = kp.public_key();
-        assert_eq!(pk.key.len(), 64); // 32 bytes = 64 hex chars
+        assert_eq!(pk.to_hex().len(), 64); // 32 bytes = 64 hex chars
     }
 
     #[test]
@@ -245,10 +337,19 @@ mod tests {
         assert_eq!(kp.sign(message), restored.sign(message));
     }
 
+    #[test]
+    fn test_serializable_keypair_json() {
+        let kp = KeyPair::generate();
+        let json = serde_json::to_string(&kp.to_serializable()).unwrap();
+
+        let parsed: SerializableKeyPair = serde_json::from_str(&json).unwrap();
+        assert_eq!(KeyPair::from_serializable(&parsed).unwrap().public_key(), kp.public_key());
+    }
+
     #[test]
     fn test_from_hex() {
         let kp = KeyPair::generate();
-        let hex_secret = hex::encode(kp.secret_bytes());
+        let hex_secret = hex::encode(kp.secret_bytes().expose_secret());
 
         let restored = KeyPair::from_hex(&hex_secret).unwrap();
         assert_eq!(kp.public_key(), restored.public_key());
@@ -0,0 +1,211 @@
+//! Key rotation certificates
+//!
+//! When a supplier replaces its signing key, the outgoing key signs a
+//! certificate naming its successor, and the successor countersigns it to
+//! show the supplier holds it. A verifier that trusts the first key can follow
+//! the certificates to every later key, so commitments signed before and after
+//! a rotation remain attributable to the same supplier.
+
+use crate::domain::push_field;
+use crate::{verify_batch, CryptoError, Domain, PublicKey, Result, Signature, Signer};
+use serde::{Deserialize, Serialize};
+
+/// A statement that `new_key` succeeds `previous_key`
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
+pub struct RotationCertificate {
+    /// Outgoing public key (hex-encoded)
+    pub previous_key: String,
+    /// Successor public key (hex-encoded)
+    pub new_key: String,
+    /// Unix time in seconds when the rotation took effect
+    pub issued_at: u64,
+    /// Signature by the outgoing key (hex-encoded)
+    pub signature: String,
+    /// Countersignature by the successor key (hex-encoded)
+    pub new_key_signature: String,
+}
+
+impl RotationCertificate {
+    /// Issues a certificate handing over from `previous` to `new`, whichever
+    /// backends hold them; fails if either cannot sign.
+    pub fn issue(previous: &dyn Signer, new: &dyn Signer, issued_at: u64) -> Result<Self> {
+        let previous_key = previous.public_key().to_hex();
+        let new_key = new.public_key().to_hex();
+        let payload = rotation_payload(&previous_key, &new_key, issued_at);
+        Ok(RotationCertificate {
+            signature: previous.sign_hex_in(Domain::KEY_ROTATION, &payload)?,
+            new_key_signature: new.sign_hex_in(Domain::KEY_ROTATION, &payload)?,
+            previous_key,
+            new_key,
+            issued_at,
+        })
+    }
+
+    /// The payload both keys sign, in [`Domain::KEY_ROTATION`]
+    pub fn payload(&self) -> Vec<u8> {
+        rotation_payload(&self.previous_key, &self.new_key, self.issued_at)
+    }
+
+    /// Checks both signatures.
+    pub fn verify(&self) -> Result<bool> {
+        let payload = self.payload();
+        let previous = PublicKey::from_hex(&self.previous_key)?;
+        let new = PublicKey::from_hex(&self.new_key)?;
+        Ok(previous.verify_hex_in(Domain::KEY_ROTATION, &payload, &self.signature)?
+            && new.verify_hex_in(Domain::KEY_ROTATION, &payload, &self.new_key_signature)?)
+    }
+
+    /// Both signatures as `(key, payload, signature)` triples for
+    /// [`verify_batch`]
+    fn signed_parts(&self, previous: PublicKey, new: PublicKey) -> Result<[(PublicKey, Vec<u8>, Signature); 2]> {
+        let payload = self.payload();
+        Ok([
+            (previous, payload.clone(), parse_signature(&self.signature)?),
+            (new, payload, parse_signature(&self.new_key_signature)?),
+        ])
+    }
+}
+
+fn parse_signature(signature_hex: &str) -> Result<Signature> {
+    Signature::from_slice(&hex::decode(signature_hex)?).map_err(|_| CryptoError::InvalidSignature)
+}
+
+fn rotation_payload(previous_key: &str, new_key: &str, issued_at: u64) -> Vec<u8> {
+    let mut payload = Vec::new();
+    push_field(&mut payload, previous_key.to_ascii_lowercase().as_bytes());
+    push_field(&mut payload, new_key.to_ascii_lowercase().as_bytes());
+    payload.extend_from_slice(&issued_at.to_be_bytes());
+    payload
+}
+
+/// Follows `certificates` from a trusted key and returns every key in the
+/// chain, starting with `trusted`. Certificates must be in rotation order,
+/// each signed by the key the previous one introduced. Signatures are checked
+/// together in one batch once the chain's structure is known to be sound.
+pub fn verify_key_chain(trusted: &PublicKey, certificates: &[RotationCertificate]) -> Result<Vec<PublicKey>> {
+    let mut keys = vec![*trusted];
+    let mut signed = Vec::with_capacity(certificates.len() * 2);
+    let mut issued_at = 0;
+    for (index, certificate) in certificates.iter().enumerate() {
+        let current = &keys[keys.len() - 1];
+        if !certificate.previous_key.eq_ignore_ascii_case(&current.to_hex()) {
+            return Err(CryptoError::InvalidRotation(format!(
+                "certificate {} does not rotate from the current key",
+                index
+            )));
+        }
+        if certificate.issued_at < issued_at {
+            return Err(CryptoError::InvalidRotation(format!(
+                "certificate {} predates the previous rotation",
+                index
+            )));
+        }
+        let new_key = PublicKey::from_hex(&certificate.new_key)?;
+        if keys.contains(&new_key) {
+            return Err(CryptoError::InvalidRotation(format!(
+                "certificate {} rotates back to an earlier key",
+                index
+            )));
+        }
+        signed.extend(certificate.signed_parts(*current, new_key)?);
+        issued_at = certificate.issued_at;
+        keys.push(new_key);
+    }
+
+    let items: Vec<(PublicKey, &[u8], Signature)> = signed
+        .iter()
+        .map(|(key, payload, signature)| (*key, payload.as_slice(), *signature))
+        .collect();
+    match verify_batch(Domain::KEY_ROTATION, &items) {
+        Ok(()) => Ok(keys),
+        Err(CryptoError::InvalidSignatures(invalid)) => Err(CryptoError::InvalidRotation(format!(
+            "certificate {} has an invalid signature",
+            invalid[0] / 2
+        ))),
+        Err(e) => Err(e),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::signer::{RawSigner, Token};
+    use crate::KeyPair;
+
+    #[test]
+    fn test_key_chain_links_rotated_keys() {
+        let first = KeyPair::generate();
+        let second = KeyPair::generate();
+        let third = KeyPair::generate();
+        let certificates = vec![
+            RotationCertificate::issue(&first, &second, 100).unwrap(),
+            RotationCertificate::issue(&second, &third, 200).unwrap(),
+        ];
+
+        let keys = verify_key_chain(&first.public_key(), &certificates).unwrap();
+        assert_eq!(keys, vec![first.public_key(), second.public_key(), third.public_key()]);
+
+        // A chain that skips a rotation does not start from the trusted key
+        assert!(verify_key_chain(&first.public_key(), &certificates[1..]).is_err());
+    }
+
+    #[test]
+    fn test_forged_rotation_rejected() {
+        let first = KeyPair::generate();
+        let second = KeyPair::generate();
+        let attacker = KeyPair::generate();
+
+        // Signed by someone other than the outgoing key
+        let mut forged = RotationCertificate::issue(&attacker, &second, 100).unwrap();
+        forged.previous_key = first.public_key().to_hex();
+        assert!(!forged.verify().unwrap());
+        assert!(verify_key_chain(&first.public_key(), &[forged]).is_err());
+
+        // Redirected to a key that never countersigned
+        let mut redirected = RotationCertificate::issue(&first, &second, 100).unwrap();
+        redirected.new_key = attacker.public_key().to_hex();
+        redirected.signature = first.sign_hex_in(Domain::KEY_ROTATION, &redirected.payload()).unwrap();
+        assert!(!redirected.verify().unwrap());
+
+        // Rotating back to an earlier key
+        let back = vec![
+            RotationCertificate::issue(&first, &second, 100).unwrap(),
+            RotationCertificate::issue(&second, &first, 200).unwrap(),
+        ];
+        assert!(verify_key_chain(&first.public_key(), &back).is_err());
+    }
+
+    /// A backend that holds a key but cannot currently sign with it
+    struct Offline(KeyPair);
+
+    impl Signer for Offline {
+        fn key_id(&self) -> String {
+            "offline".to_string()
+        }
+
+        fn public_key(&self) -> PublicKey {
+            self.0.public_key()
+        }
+    }
+
+    impl RawSigner for Offline {
+        fn sign_raw(&self, _message: &[u8], _: Token) -> Result<[u8; 64]> {
+            Err(CryptoError::Signer("backend unreachable".to_string()))
+        }
+    }
+
+    #[test]
+    fn test_issue_through_signer_backends() {
+        let first = KeyPair::generate();
+        let second = KeyPair::generate();
+        let held: &dyn Signer = &first;
+        let certificate = RotationCertificate::issue(held, &second, 100).unwrap();
+        assert!(certificate.verify().unwrap());
+
+        let offline = Offline(first);
+        assert!(matches!(
+            RotationCertificate::issue(&offline, &second, 100),
+            Err(CryptoError::Signer(_))
+        ));
+    }
+}
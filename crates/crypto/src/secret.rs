@@ -0,0 +1,152 @@
+//! Wrappers for secret key material
+//!
+//! Secrets are wiped from memory when dropped, cannot be cloned, and have no
+//! `Debug` or `Display` output, so they do not end up in logs or panic
+//! messages by accident. Reading one takes an explicit call to
+//! [`ExposeSecret::expose_secret`], which keeps every place that touches raw
+//! key material easy to find and review.
+
+use serde::{Deserialize, Deserializer, Serialize, Serializer};
+use zeroize::{Zeroize, ZeroizeOnDrop};
+
+/// Access to the value inside a secret wrapper
+pub trait ExposeSecret<T: ?Sized> {
+    fn expose_secret(&self) -> &T;
+}
+
+/// A fixed-size secret such as an Ed25519 secret key
+pub struct SecretBytes<const N: usize>([u8; N]);
+
+impl<const N: usize> SecretBytes<N> {
+    pub fn new(bytes: [u8; N]) -> Self {
+        SecretBytes(bytes)
+    }
+}
+
+impl<const N: usize> ExposeSecret<[u8; N]> for SecretBytes<N> {
+    fn expose_secret(&self) -> &[u8; N] {
+        &self.0
+    }
+}
+
+impl<const N: usize> Drop for SecretBytes<N> {
+    fn drop(&mut self) {
+        self.0.zeroize();
+    }
+}
+
+impl<const N: usize> ZeroizeOnDrop for SecretBytes<N> {}
+
+/// A variable-length secret, such as a decrypted key buffer
+pub struct SecretVec(Vec<u8>);
+
+impl SecretVec {
+    pub fn new(bytes: Vec<u8>) -> Self {
+        SecretVec(bytes)
+    }
+}
+
+impl From<Vec<u8>> for SecretVec {
+    fn from(bytes: Vec<u8>) -> Self {
+        SecretVec(bytes)
+    }
+}
+
+impl ExposeSecret<[u8]> for SecretVec {
+    fn expose_secret(&self) -> &[u8] {
+        &self.0
+    }
+}
+
+impl Drop for SecretVec {
+    fn drop(&mut self) {
+        self.0.zeroize();
+    }
+}
+
+impl ZeroizeOnDrop for SecretVec {}
+
+/// A secret in text form, such as a hex-encoded key
+pub struct SecretString(String);
+
+impl SecretString {
+    pub fn new(value: String) -> Self {
+        SecretString(value)
+    }
+}
+
+impl From<String> for SecretString {
+    fn from(value: String) -> Self {
+        SecretString(value)
+    }
+}
+
+impl ExposeSecret<str> for SecretString {
+    fn expose_secret(&self) -> &str {
+        &self.0
+    }
+}
+
+impl Drop for SecretString {
+    fn drop(&mut self) {
+        self.0.zeroize();
+    }
+}
+
+impl ZeroizeOnDrop for SecretString {}
+
+impl Serialize for SecretString {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.serialize_str(&self.0)
+    }
+}
+
+impl<'de> Deserialize<'de> for SecretString {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        String::deserialize(deserializer).map(SecretString)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::mem::ManuallyDrop;
+
+    fn assert_zeroize_on_drop<T: ZeroizeOnDrop>() {}
+
+    #[test]
+    fn test_expose_secret_roundtrip() {
+        let bytes = SecretBytes::new([7u8; 32]);
+        assert_eq!(bytes.expose_secret(), &[7u8; 32]);
+
+        assert_eq!(SecretVec::new(vec![1, 2, 3]).expose_secret(), &[1, 2, 3]);
+        assert_eq!(SecretVec::from(vec![4, 5]).expose_secret(), &[4, 5]);
+        assert!(SecretVec::new(Vec::new()).expose_secret().is_empty());
+
+        assert_eq!(SecretString::new("deadbeef".to_string()).expose_secret(), "deadbeef");
+        assert_eq!(SecretString::from("phrase".to_string()).expose_secret(), "phrase");
+    }
+
+    #[test]
+    fn test_secret_string_serde_roundtrip() {
+        let json = serde_json::to_string(&SecretString::new("abandon ability".to_string())).unwrap();
+        assert_eq!(json, "\"abandon ability\"");
+        let parsed: SecretString = serde_json::from_str(&json).unwrap();
+        assert_eq!(parsed.expose_secret(), "abandon ability");
+    }
+
+    #[test]
+    fn test_zeroize_on_drop() {
+        assert_zeroize_on_drop::<SecretBytes<32>>();
+        assert_zeroize_on_drop::<SecretVec>();
+        assert_zeroize_on_drop::<SecretString>();
+
+        // A fixed-size secret keeps its storage after the destructor runs, so
+        // the wipe can be observed; heap-backed secrets free theirs.
+        let mut secret = ManuallyDrop::new(SecretBytes::new([0xA5u8; 32]));
+        // SAFETY: `secret` is not used as a `SecretBytes` again; only its
+        // plain byte array is read back.
+        unsafe { ManuallyDrop::drop(&mut secret) };
+        assert_eq!(secret.0, [0u8; 32]);
+    }
+}
@@ -0,0 +1,107 @@
+//! Signing backends
+//!
+//! [`Signer`] hides where a supplier's secret key lives. [`KeyPair`] signs in
+//! process memory; [`remote::RemoteSigner`] asks a signing daemon on a Unix
+//! socket (a stand-in for a corporate KMS), and `pkcs11::Pkcs11Signer`
+//! (feature `pkcs11`) signs inside an HSM without the key ever leaving it.
+//! Code that signs through a `&dyn Signer` works with any of them.
+//!
+//! Outside this crate a signer only signs in a registered [`Domain`]. The raw
+//! primitive each backend provides sits in a sealed supertrait that other
+//! crates can neither call nor implement, so the backends are the ones here.
+
+#[cfg(feature = "pkcs11")]
+pub mod pkcs11;
+#[cfg(unix)]
+pub mod remote;
+
+use crate::{CryptoError, Domain, KeyPair, PublicKey, Result};
+
+mod sealed {
+    /// Only this crate can construct it, and so call [`RawSigner::sign_raw`]
+    pub struct Token(pub(crate) ());
+
+    /// The unframed signing primitive of a backend
+    pub trait RawSigner {
+        /// Signs raw `message` bytes; fails if the backend cannot be reached.
+        fn sign_raw(&self, message: &[u8], token: Token) -> crate::Result<[u8; 64]>;
+    }
+}
+
+pub(crate) use sealed::{RawSigner, Token};
+
+/// Signs raw `message` bytes with `signer`, for the messages this crate
+/// frames itself
+pub(crate) fn sign_raw(signer: &(impl Signer + ?Sized), message: &[u8]) -> Result<[u8; 64]> {
+    signer.sign_raw(message, Token(()))
+}
+
+/// An Ed25519 signing key, wherever it is held
+pub trait Signer: RawSigner + Send + Sync {
+    /// Identifies the key within its backend
+    fn key_id(&self) -> String;
+
+    fn public_key(&self) -> PublicKey;
+
+    /// Signs `payload` in `domain`; what every supplier signature should use.
+    fn sign_in(&self, domain: Domain, payload: &[u8]) -> Result<[u8; 64]> {
+        sign_raw(self, &domain.frame(payload))
+    }
+
+    /// Signs `payload` in `domain`, hex-encoded.
+    fn sign_hex_in(&self, domain: Domain, payload: &[u8]) -> Result<String> {
+        Ok(hex::encode(self.sign_in(domain, payload)?))
+    }
+}
+
+impl Signer for KeyPair {
+    /// The hex-encoded public key
+    fn key_id(&self) -> String {
+        KeyPair::public_key(self).to_hex()
+    }
+
+    fn public_key(&self) -> PublicKey {
+        KeyPair::public_key(self)
+    }
+}
+
+impl RawSigner for KeyPair {
+    fn sign_raw(&self, message: &[u8], _: Token) -> Result<[u8; 64]> {
+        Ok(KeyPair::sign(self, message))
+    }
+}
+
+/// Checks a signature returned by an external backend against the key it
+/// claims to hold, so a misconfigured key ID fails at signing time rather
+/// than at verification.
+fn checked_signature(public_key: &PublicKey, message: &[u8], signature: &[u8]) -> Result<[u8; 64]> {
+    let signature: [u8; 64] = signature
+        .try_into()
+        .map_err(|_| CryptoError::Signer(format!("signature has {} bytes, expected 64", signature.len())))?;
+    if !public_key.verify(message, &signature)? {
+        return Err(CryptoError::Signer("signature does not match the signer's public key".to_string()));
+    }
+    Ok(signature)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn sign_through(signer: &dyn Signer, message: &[u8]) -> (PublicKey, [u8; 64]) {
+        (signer.public_key(), sign_raw(signer, message).unwrap())
+    }
+
+    #[test]
+    fn test_keypair_signer() {
+        let kp = KeyPair::generate();
+        let (public_key, signature) = sign_through(&kp, b"commitment root");
+        assert_eq!(public_key, kp.public_key());
+        assert_eq!(signature, kp.sign(b"commitment root"));
+        assert_eq!(Signer::key_id(&kp), kp.public_key().to_hex());
+
+        let other = KeyPair::generate();
+        assert!(checked_signature(&other.public_key(), b"commitment root", &signature).is_err());
+        assert!(checked_signature(&public_key, b"commitment root", &signature[..32]).is_err());
+    }
+}
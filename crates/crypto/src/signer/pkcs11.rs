@@ -0,0 +1,157 @@
+//! Signing inside a PKCS#11 token
+//!
+//! The key is an Ed25519 (`CKK_EC_EDWARDS`) key pair on the token, found by
+//! its label; signing uses `CKM_EDDSA`, so the secret key never leaves the
+//! HSM. SoftHSM works for development:
+//!
+//! ```text
+//! softhsm2-util --init-token --free --label zkdpp --pin 1234 --so-pin 0000
+//! pkcs11-tool --module /usr/lib/softhsm/libsofthsm2.so --login --pin 1234 \
+//!     --keypairgen --key-type EC:edwards25519 --label supplier
+//! ```
+
+use super::{checked_signature, RawSigner, Signer, Token};
+use crate::{CryptoError, PublicKey, Result};
+use cryptoki::context::{CInitializeArgs, CInitializeFlags, Pkcs11};
+use cryptoki::error::{Error, RvError};
+use cryptoki::mechanism::eddsa::{EddsaParams, EddsaSignatureScheme};
+use cryptoki::mechanism::Mechanism;
+use cryptoki::object::{Attribute, AttributeType, KeyType, ObjectClass, ObjectHandle};
+use cryptoki::session::{Session, UserType};
+use cryptoki::types::AuthPin;
+use std::path::Path;
+use std::sync::Mutex;
+
+/// An Ed25519 key held on a PKCS#11 token
+pub struct Pkcs11Signer {
+    // Sessions must not be used from two threads at once
+    session: Mutex<Session>,
+    key: ObjectHandle,
+    key_label: String,
+    public_key: PublicKey,
+}
+
+impl Pkcs11Signer {
+    /// Loads `module`, logs in to the token labelled `token_label` with
+    /// `pin` and finds the key pair labelled `key_label`.
+    pub fn open(module: impl AsRef<Path>, token_label: &str, pin: &str, key_label: &str) -> Result<Self> {
+        let context = Pkcs11::new(module.as_ref()).map_err(pkcs11_error)?;
+        match context.initialize(CInitializeArgs::new(CInitializeFlags::OS_LOCKING_OK)) {
+            Ok(()) | Err(Error::Pkcs11(RvError::CryptokiAlreadyInitialized, _)) => {}
+            Err(e) => return Err(pkcs11_error(e)),
+        }
+
+        let mut slot = None;
+        for candidate in context.get_slots_with_token().map_err(pkcs11_error)? {
+            if context.get_token_info(candidate).map_err(pkcs11_error)?.label() == token_label {
+                slot = Some(candidate);
+                break;
+            }
+        }
+        let slot = slot.ok_or_else(|| CryptoError::Signer(format!("no token labelled {}", token_label)))?;
+        let session = context.open_ro_session(slot).map_err(pkcs11_error)?;
+        session
+            .login(UserType::User, Some(&AuthPin::from(pin)))
+            .map_err(pkcs11_error)?;
+
+        let key = find_key(&session, ObjectClass::PRIVATE_KEY, key_label)?;
+        let public = find_key(&session, ObjectClass::PUBLIC_KEY, key_label)?;
+        let ec_point = match session
+            .get_attributes(public, &[AttributeType::EcPoint])
+            .map_err(pkcs11_error)?
+            .pop()
+        {
+            Some(Attribute::EcPoint(point)) => point,
+            _ => return Err(CryptoError::Signer(format!("key {} has no public point", key_label))),
+        };
+
+        Ok(Pkcs11Signer {
+            session: Mutex::new(session),
+            key,
+            key_label: key_label.to_string(),
+            public_key: PublicKey::from_hex(&hex::encode(ed25519_point(&ec_point)?))?,
+        })
+    }
+}
+
+impl Signer for Pkcs11Signer {
+    /// The key's `CKA_LABEL`
+    fn key_id(&self) -> String {
+        self.key_label.clone()
+    }
+
+    fn public_key(&self) -> PublicKey {
+        self.public_key
+    }
+}
+
+impl RawSigner for Pkcs11Signer {
+    fn sign_raw(&self, message: &[u8], _: Token) -> Result<[u8; 64]> {
+        let session = self
+            .session
+            .lock()
+            .map_err(|_| CryptoError::Signer("PKCS#11 session poisoned".to_string()))?;
+        let mechanism = Mechanism::Eddsa(EddsaParams::new(EddsaSignatureScheme::Pure));
+        let signature = session.sign(&mechanism, self.key, message).map_err(pkcs11_error)?;
+        checked_signature(&self.public_key, message, &signature)
+    }
+}
+
+fn find_key(session: &Session, class: ObjectClass, label: &str) -> Result<ObjectHandle> {
+    let template = [
+        Attribute::Class(class),
+        Attribute::KeyType(KeyType::EC_EDWARDS),
+        Attribute::Label(label.as_bytes().to_vec()),
+    ];
+    let mut handles = session.find_objects(&template).map_err(pkcs11_error)?;
+    match handles.len() {
+        1 => Ok(handles.remove(0)),
+        0 => Err(CryptoError::Signer(format!("no Ed25519 {} labelled {}", class, label))),
+        n => Err(CryptoError::Signer(format!("{} Ed25519 {} objects labelled {}", n, class, label))),
+    }
+}
+
+/// `CKA_EC_POINT` is the raw 32-byte key, or that key DER-wrapped in an
+/// OCTET STRING depending on the token
+fn ed25519_point(point: &[u8]) -> Result<&[u8]> {
+    match point {
+        [0x04, 0x20, key @ ..] if key.len() == 32 => Ok(key),
+        key if key.len() == 32 => Ok(key),
+        _ => Err(CryptoError::Signer(format!("unexpected EC point of {} bytes", point.len()))),
+    }
+}
+
+fn pkcs11_error(error: Error) -> CryptoError {
+    CryptoError::Signer(error.to_string())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::Domain;
+
+    #[test]
+    fn test_ec_point_encodings() {
+        let key = [7u8; 32];
+        let mut wrapped = vec![0x04, 0x20];
+        wrapped.extend_from_slice(&key);
+        assert_eq!(ed25519_point(&wrapped).unwrap(), &key);
+        assert_eq!(ed25519_point(&key).unwrap(), &key);
+        assert!(ed25519_point(&key[..31]).is_err());
+    }
+
+    #[test]
+    #[ignore = "needs a SoftHSM token; see the module docs and PKCS11_TEST_* variables"]
+    fn test_softhsm_signer() {
+        let var = |name: &str| std::env::var(name).unwrap_or_else(|_| panic!("{} is not set", name));
+        let signer = Pkcs11Signer::open(
+            var("PKCS11_TEST_MODULE"),
+            &var("PKCS11_TEST_TOKEN"),
+            &var("PKCS11_TEST_PIN"),
+            &var("PKCS11_TEST_KEY"),
+        )
+        .unwrap();
+        let signature = signer.sign_in(Domain::COMMITMENT, b"commitment root").unwrap();
+        assert!(signer.public_key().verify_in(Domain::COMMITMENT, b"commitment root", &signature).unwrap());
+    }
+}
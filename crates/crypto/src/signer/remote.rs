@@ -0,0 +1,205 @@
+//! Signing through a local daemon
+//!
+//! The daemon holds the keys and answers one newline-terminated JSON request
+//! per connection on a Unix socket:
+//!
+//! - `{"op":"public_key","key_id":"..."}` returns `{"public_key":"<hex>"}`
+//! - `{"op":"sign","key_id":"...","message":"<hex>"}` returns
+//!   `{"signature":"<hex>"}`
+//!
+//! and reports failures as `{"error":"..."}`. [`serve`] is a minimal daemon
+//! for development and tests; production deployments put a KMS client
+//! behind the same protocol. A daemon signs only what a [`Signer`] sends:
+//! messages framed in a registered [`crate::Domain`], or the hash data of a
+//! Data Integrity proof. Anything else is refused, so access to the socket
+//! does not amount to an unframed signing oracle.
+
+use super::{checked_signature, RawSigner, Signer, Token};
+use crate::data_integrity::HASH_DATA_LEN;
+use crate::domain::framed_domain;
+use crate::{CryptoError, KeyPair, PublicKey, Result};
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::io::{BufRead, BufReader, Write};
+use std::os::unix::net::{UnixListener, UnixStream};
+use std::path::{Path, PathBuf};
+use std::time::Duration;
+
+/// How long to wait for the daemon before giving up
+pub const DEFAULT_TIMEOUT: Duration = Duration::from_secs(30);
+
+/// A request to the signing daemon
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(tag = "op", rename_all = "snake_case")]
+pub enum SignRequest {
+    PublicKey { key_id: String },
+    Sign { key_id: String, message: String },
+}
+
+/// The daemon's answer; exactly one field is set
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct SignResponse {
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub public_key: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub signature: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub error: Option<String>,
+}
+
+/// A key held by a signing daemon
+#[derive(Debug, Clone)]
+pub struct RemoteSigner {
+    socket: PathBuf,
+    key_id: String,
+    public_key: PublicKey,
+    timeout: Duration,
+}
+
+impl RemoteSigner {
+    /// Connects to the daemon at `socket` and fetches the public key of
+    /// `key_id`.
+    pub fn connect(socket: impl AsRef<Path>, key_id: &str) -> Result<Self> {
+        Self::connect_with_timeout(socket, key_id, DEFAULT_TIMEOUT)
+    }
+
+    pub fn connect_with_timeout(socket: impl AsRef<Path>, key_id: &str, timeout: Duration) -> Result<Self> {
+        let socket = socket.as_ref().to_path_buf();
+        let response = request(&socket, timeout, &SignRequest::PublicKey { key_id: key_id.to_string() })?;
+        let public_key = response
+            .public_key
+            .ok_or_else(|| CryptoError::Signer("daemon returned no public key".to_string()))?;
+        Ok(RemoteSigner {
+            socket,
+            key_id: key_id.to_string(),
+            public_key: PublicKey::from_hex(&public_key)?,
+            timeout,
+        })
+    }
+}
+
+impl Signer for RemoteSigner {
+    fn key_id(&self) -> String {
+        self.key_id.clone()
+    }
+
+    fn public_key(&self) -> PublicKey {
+        self.public_key
+    }
+}
+
+impl RawSigner for RemoteSigner {
+    fn sign_raw(&self, message: &[u8], _: Token) -> Result<[u8; 64]> {
+        let response = request(
+            &self.socket,
+            self.timeout,
+            &SignRequest::Sign { key_id: self.key_id.clone(), message: hex::encode(message) },
+        )?;
+        let signature = response
+            .signature
+            .ok_or_else(|| CryptoError::Signer("daemon returned no signature".to_string()))?;
+        checked_signature(&self.public_key, message, &hex::decode(signature)?)
+    }
+}
+
+fn request(socket: &Path, timeout: Duration, request: &SignRequest) -> Result<SignResponse> {
+    let io_error = |e: std::io::Error| CryptoError::Signer(format!("{}: {}", socket.display(), e));
+    let mut stream = UnixStream::connect(socket).map_err(io_error)?;
+    stream.set_read_timeout(Some(timeout)).map_err(io_error)?;
+    stream.set_write_timeout(Some(timeout)).map_err(io_error)?;
+
+    let mut line = serde_json::to_vec(request)?;
+    line.push(b'\n');
+    stream.write_all(&line).map_err(io_error)?;
+
+    let mut reply = String::new();
+    BufReader::new(stream).read_line(&mut reply).map_err(io_error)?;
+    let response: SignResponse = serde_json::from_str(&reply)?;
+    match response.error {
+        Some(error) => Err(CryptoError::Signer(error)),
+        None => Ok(response),
+    }
+}
+
+/// Answers requests on `listener` with `keys`, one connection at a time,
+/// until accepting fails.
+pub fn serve(listener: UnixListener, keys: HashMap<String, KeyPair>) -> std::io::Result<()> {
+    for stream in listener.incoming() {
+        let mut stream = stream?;
+        let mut line = String::new();
+        if BufReader::new(&stream).read_line(&mut line).is_err() {
+            continue;
+        }
+        let response = answer(&keys, &line);
+        let mut reply = serde_json::to_vec(&response).unwrap_or_default();
+        reply.push(b'\n');
+        // A client that hung up does not stop the daemon
+        let _ = stream.write_all(&reply);
+    }
+    Ok(())
+}
+
+fn answer(keys: &HashMap<String, KeyPair>, line: &str) -> SignResponse {
+    let error = |error: String| SignResponse { error: Some(error), ..Default::default() };
+    let request: SignRequest = match serde_json::from_str(line) {
+        Ok(request) => request,
+        Err(e) => return error(format!("invalid request: {}", e)),
+    };
+    let key_id = match &request {
+        SignRequest::PublicKey { key_id } | SignRequest::Sign { key_id, .. } => key_id,
+    };
+    let keypair = match keys.get(key_id) {
+        Some(keypair) => keypair,
+        None => return error(format!("unknown key {}", key_id)),
+    };
+    match request {
+        SignRequest::PublicKey { .. } => SignResponse {
+            public_key: Some(keypair.public_key().to_hex()),
+            ..Default::default()
+        },
+        SignRequest::Sign { message, .. } => match hex::decode(&message) {
+            Ok(message) if framed_domain(&message).is_some() || message.len() == HASH_DATA_LEN => SignResponse {
+                signature: Some(keypair.sign_hex(&message)),
+                ..Default::default()
+            },
+            Ok(_) => error("refusing to sign a message outside a registered domain".to_string()),
+            Err(e) => error(format!("invalid message: {}", e)),
+        },
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::Domain;
+    use rand::RngCore;
+
+    fn start_daemon(keys: HashMap<String, KeyPair>) -> PathBuf {
+        let socket = std::env::temp_dir().join(format!("zkdpp-signer-{}.sock", rand::rngs::OsRng.next_u64()));
+        let listener = UnixListener::bind(&socket).unwrap();
+        std::thread::spawn(move || serve(listener, keys));
+        socket
+    }
+
+    #[test]
+    fn test_remote_signer_roundtrip() {
+        let kp = KeyPair::generate();
+        let expected = kp.public_key();
+        let socket = start_daemon(HashMap::from([("supplier-1".to_string(), kp)]));
+
+        let signer = RemoteSigner::connect(&socket, "supplier-1").unwrap();
+        assert_eq!(signer.public_key(), expected);
+        assert_eq!(signer.key_id(), "supplier-1");
+        let signature = signer.sign_in(Domain::COMMITMENT, b"commitment root").unwrap();
+        assert!(expected.verify_in(Domain::COMMITMENT, b"commitment root", &signature).unwrap());
+
+        // The daemon is not an oracle for unframed messages
+        let raw = SignRequest::Sign { key_id: "supplier-1".to_string(), message: hex::encode(b"commitment root") };
+        let refused = request(&socket, DEFAULT_TIMEOUT, &raw).unwrap_err().to_string();
+        assert!(refused.contains("outside a registered domain"), "{}", refused);
+
+        assert!(matches!(RemoteSigner::connect(&socket, "supplier-2"), Err(CryptoError::Signer(_))));
+        let _ = std::fs::remove_file(&socket);
+        assert!(signer.sign_in(Domain::COMMITMENT, b"after shutdown").is_err());
+    }
+}
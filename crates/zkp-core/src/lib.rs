@@ -29,10 +29,22 @@ pub enum ZkpError {
 
     #[error("Hex decoding error: {0}")]
     HexDecode(#[from] hex::FromHexError),
+
+    #[error("Mock proofs are not accepted by the verifier")]
+    MockProof,
 }
 
 pub type Result<T> = std::result::Result<T, ZkpError>;
 
+/// Prefix of the insecure, deterministic proofs emitted by the edge agent's
+/// development-only mock prover. Real verification always refuses them.
+pub const MOCK_PROOF_TAG: &[u8] = b"ZKDPP_INSECURE_MOCK_PROOF_V1";
+
+/// Returns true if the proof bytes carry the mock prover tag.
+pub fn is_mock_proof(proof_bytes: &[u8]) -> bool {
+    proof_bytes.starts_with(MOCK_PROOF_TAG)
+}
+
 /// A predicate identifier with version
 #[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq, Hash)]
 pub struct PredicateId {
@@ -58,6 +70,12 @@ impl PredicateId {
 pub struct PublicInputs {
     /// The predicate-specific threshold or comparison value
     pub threshold: Option<u64>,
+    /// Lower bound for range predicates (`min_threshold <= value`)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub min_threshold: Option<u64>,
+    /// Upper bound for range predicates (`value <= max_threshold`)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub max_threshold: Option<u64>,
     /// Merkle root of the supplier's commitment
     pub commitment_root: String,
     /// Binding to product identifier (hash)
@@ -127,7 +145,10 @@ pub fn verify_proof(package: &ProofPackage, vkey: &VerificationKey) -> Result<Ve
     }
 
     // Decode proof bytes
-    let _proof_bytes = hex::decode(&package.proof)?;
+    let proof_bytes = hex::decode(&package.proof)?;
+    if is_mock_proof(&proof_bytes) {
+        return Err(ZkpError::MockProof);
+    }
 
     // Decode verification key
     let _vkey_bytes = hex::decode(&vkey.key)?;
@@ -148,8 +169,11 @@ pub fn verify_proof(package: &ProofPackage, vkey: &VerificationKey) -> Result<Ve
 ///
 /// Use this for quick validation before expensive proof verification.
 pub fn validate_proof_package(package: &ProofPackage) -> Result<()> {
-    // Check proof is valid hex
-    hex::decode(&package.proof).map_err(|_| ZkpError::InvalidProofFormat)?;
+    // Check proof is valid hex and not a mock proof
+    let proof_bytes = hex::decode(&package.proof).map_err(|_| ZkpError::InvalidProofFormat)?;
+    if is_mock_proof(&proof_bytes) {
+        return Err(ZkpError::MockProof);
+    }
 
     // Check commitment root is valid hex
     let root_bytes = hex::decode(&package.public_inputs.commitment_root)
@@ -172,6 +196,13 @@ pub fn validate_proof_package(package: &ProofPackage) -> Result<()> {
         return Err(ZkpError::InvalidPublicInputs);
     }
 
+    // Range predicates carry both bounds, in order
+    match (package.public_inputs.min_threshold, package.public_inputs.max_threshold) {
+        (None, None) => {}
+        (Some(min), Some(max)) if min <= max => {}
+        _ => return Err(ZkpError::InvalidPublicInputs),
+    }
+
     // Check nonce is valid hex
     hex::decode(&package.nonce).map_err(|_| ZkpError::InvalidProofFormat)?;
 
@@ -217,6 +248,8 @@ mod tests {
             proof: hex::encode([0u8; 64]), // Placeholder proof
             public_inputs: PublicInputs {
                 threshold: Some(20),
+                min_threshold: None,
+                max_threshold: None,
                 commitment_root: hex::encode([1u8; 32]),
                 product_binding: hex::encode([2u8; 32]),
                 requester_binding: hex::encode([3u8; 32]),
@@ -272,6 +305,28 @@ mod tests {
         ));
     }
 
+    #[test]
+    fn test_validate_range_thresholds() {
+        let mut package = make_test_package();
+        package.predicate_id = PredicateId::new("ENERGY_DENSITY_RANGE", "V1");
+        package.public_inputs.threshold = None;
+        package.public_inputs.min_threshold = Some(200);
+        package.public_inputs.max_threshold = Some(300);
+        assert!(validate_proof_package(&package).is_ok());
+
+        package.public_inputs.max_threshold = Some(150);
+        assert!(matches!(
+            validate_proof_package(&package),
+            Err(ZkpError::InvalidPublicInputs)
+        ));
+
+        package.public_inputs.max_threshold = None;
+        assert!(matches!(
+            validate_proof_package(&package),
+            Err(ZkpError::InvalidPublicInputs)
+        ));
+    }
+
     #[test]
     fn test_verify_proof_structure() {
         let package = make_test_package();
@@ -295,6 +350,23 @@ mod tests {
         ));
     }
 
+    #[test]
+    fn test_mock_proof_rejected() {
+        let mut package = make_test_package();
+        let mut proof = MOCK_PROOF_TAG.to_vec();
+        proof.extend_from_slice(&[0u8; 32]);
+        package.proof = hex::encode(proof);
+
+        assert!(matches!(
+            validate_proof_package(&package),
+            Err(ZkpError::MockProof)
+        ));
+        assert!(matches!(
+            verify_proof(&package, &make_test_vkey()),
+            Err(ZkpError::MockProof)
+        ));
+    }
+
     #[test]
     fn test_proof_package_serialization() {
         let package = make_test_package();
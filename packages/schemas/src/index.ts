@@ -93,6 +93,8 @@ export interface Commitment {
 
 export interface PublicInputs {
   threshold?: number;
+  minThreshold?: number;
+  maxThreshold?: number;
   commitmentRoot: string;
   productBinding: string;
   requesterBinding: string;
@@ -111,6 +113,7 @@ export interface ProofPackage {
     requesterId?: string;
     productId?: string;
   };
+  supplierPublicKey?: string;
   supplierSignature?: string;
   usageTerms?: {
     allowedPurposes?: string[];
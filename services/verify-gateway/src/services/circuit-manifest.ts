@@ -0,0 +1,190 @@
+import fs from 'fs';
+import path from 'path';
+import { execFileSync } from 'child_process';
+import { blake3 } from '@noble/hashes/blake3';
+import { bytesToHex } from '@noble/hashes/utils';
+
+/**
+ * Pinned circuit manifest (`circuits/noir/circuit-manifest.json`), the same
+ * file the edge agent proves against. Hashes are computed exactly as the
+ * edge agent's `zk::manifest` does, so both sides trust the same circuits.
+ */
+export const MANIFEST_FILE = 'circuit-manifest.json';
+
+const MANIFEST_VERSION = 1;
+
+export interface CircuitPin {
+  source_hash: string;
+  artifact_hash?: string;
+}
+
+export interface CircuitManifest {
+  version: number;
+  noir_version: string;
+  circuits: Record<string, CircuitPin>;
+}
+
+const manifests = new Map<string, CircuitManifest>();
+const nargoVersions = new Map<string, string>();
+
+/**
+ * Checks a compiled circuit against the manifest next to its predicates
+ * directory (or `NOIR_CIRCUIT_MANIFEST`). Throws on any mismatch, or when no
+ * manifest exists unless `ALLOW_UNPINNED_CIRCUITS=true`.
+ */
+export function checkCircuitPin(nargoBin: string, circuitDir: string, packageName: string): void {
+  const manifestPath = resolveManifestPath(path.dirname(circuitDir));
+  if (!fs.existsSync(manifestPath)) {
+    if (process.env.ALLOW_UNPINNED_CIRCUITS === 'true') return;
+    throw new Error(`Circuit manifest not found at ${manifestPath}`);
+  }
+  const manifest = loadManifest(manifestPath);
+
+  const pin = manifest.circuits[packageName];
+  if (!pin) {
+    throw new Error(`Circuit ${packageName} is not pinned in the circuit manifest`);
+  }
+  const noirVersion = nargoVersion(nargoBin);
+  if (noirVersion !== manifest.noir_version) {
+    throw new Error(
+      `Noir version mismatch for ${packageName}: manifest pins ${manifest.noir_version}, local nargo is ${noirVersion}`
+    );
+  }
+  const sourceHash = hashCircuitSources(circuitDir);
+  if (sourceHash !== pin.source_hash) {
+    throw new Error(
+      `Circuit sources for ${packageName} do not match the manifest (expected ${pin.source_hash}, found ${sourceHash})`
+    );
+  }
+  if (pin.artifact_hash !== undefined) {
+    const artifact = path.join(circuitDir, 'target', `${packageName}.json`);
+    const artifactHash = bytesToHex(blake3(fs.readFileSync(artifact)));
+    if (artifactHash !== pin.artifact_hash) {
+      throw new Error(
+        `Compiled artifact for ${packageName} does not match the manifest (expected ${pin.artifact_hash}, found ${artifactHash})`
+      );
+    }
+  }
+}
+
+function resolveManifestPath(predicatesDir: string): string {
+  return process.env.NOIR_CIRCUIT_MANIFEST || path.join(path.dirname(predicatesDir), MANIFEST_FILE);
+}
+
+function loadManifest(manifestPath: string): CircuitManifest {
+  const cached = manifests.get(manifestPath);
+  if (cached) return cached;
+
+  const manifest = JSON.parse(fs.readFileSync(manifestPath, 'utf8')) as CircuitManifest;
+  if (manifest.version !== MANIFEST_VERSION) {
+    throw new Error(`Unsupported circuit manifest version ${manifest.version} (expected ${MANIFEST_VERSION})`);
+  }
+  manifests.set(manifestPath, manifest);
+  return manifest;
+}
+
+/** `nargo --version`, asked once per binary */
+function nargoVersion(nargoBin: string): string {
+  const cached = nargoVersions.get(nargoBin);
+  if (cached) return cached;
+
+  const output = execFileSync(nargoBin, ['--version'], { stdio: 'pipe' }).toString();
+  const line = output.split('\n').find((l) => l.trimStart().startsWith('nargo'));
+  const version = line?.split('=')[1]?.trim().split(/\s+/)[0];
+  if (!version) {
+    throw new Error('Unable to determine nargo version');
+  }
+  nargoVersions.set(nargoBin, version);
+  return version;
+}
+
+/**
+ * BLAKE3 over a circuit's `Nargo.toml` and `.nr` sources and those of its
+ * path dependencies, each file framed as its length-prefixed name and
+ * length-prefixed contents
+ */
+export function hashCircuitSources(circuitDir: string): string {
+  const chunks: Buffer[] = [];
+  for (const [name, file] of sourceFiles(circuitDir)) {
+    const contents = fs.readFileSync(file);
+    const nameBytes = Buffer.from(name, 'utf8');
+    chunks.push(u64(nameBytes.length), nameBytes, u64(contents.length), contents);
+  }
+  return bytesToHex(blake3(Buffer.concat(chunks)));
+}
+
+function sourceFiles(circuitDir: string): Array<[string, string]> {
+  const packages: Array<[string, string]> = [['circuit', circuitDir]];
+  for (let index = 0; index < packages.length; index += 1) {
+    for (const [name, dir] of pathDependencies(packages[index][1])) {
+      const resolved = fs.realpathSync(dir);
+      if (!packages.some(([, seen]) => seen === resolved)) {
+        packages.push([name, resolved]);
+      }
+    }
+  }
+
+  const named: Array<[string, string]> = [];
+  for (const [label, dir] of packages) {
+    const files = [path.join(dir, 'Nargo.toml')];
+    collectNrFiles(path.join(dir, 'src'), files);
+    const relative = files.map((file) => path.relative(dir, file).split(path.sep));
+    relative.sort(compareComponents);
+    for (const parts of relative) {
+      named.push([`${label}/${parts.join('/')}`, path.join(dir, ...parts)]);
+    }
+  }
+  return named;
+}
+
+/** `path` dependencies in a package's `Nargo.toml` */
+function pathDependencies(packageDir: string): Array<[string, string]> {
+  const data = fs.readFileSync(path.join(packageDir, 'Nargo.toml'), 'utf8');
+  const dependencies: Array<[string, string]> = [];
+  let inDependencies = false;
+  for (const raw of data.split('\n')) {
+    const line = raw.split('#')[0].trim();
+    if (line.startsWith('[')) {
+      inDependencies = line === '[dependencies]';
+      continue;
+    }
+    if (!inDependencies) continue;
+    // `zkdpp_lib = { path = "../../lib" }`
+    const match = line.match(/^([^=]+)=\s*\{(.*)\}$/);
+    if (!match) continue;
+    for (const field of match[2].split(',')) {
+      const [key, value] = field.split('=').map((part) => part.trim());
+      if (key === 'path' && value?.startsWith('"') && value.endsWith('"')) {
+        dependencies.push([match[1].trim(), path.join(packageDir, value.slice(1, -1))]);
+      }
+    }
+  }
+  return dependencies;
+}
+
+function collectNrFiles(dir: string, out: string[]): void {
+  if (!fs.existsSync(dir)) return;
+  for (const entry of fs.readdirSync(dir, { withFileTypes: true })) {
+    const file = path.join(dir, entry.name);
+    if (entry.isDirectory()) {
+      collectNrFiles(file, out);
+    } else if (entry.name.endsWith('.nr')) {
+      out.push(file);
+    }
+  }
+}
+
+/** Path order as Rust sorts paths: component by component, bytewise */
+function compareComponents(a: string[], b: string[]): number {
+  for (let i = 0; i < Math.min(a.length, b.length); i += 1) {
+    const order = Buffer.compare(Buffer.from(a[i]), Buffer.from(b[i]));
+    if (order !== 0) return order;
+  }
+  return a.length - b.length;
+}
+
+function u64(value: number): Buffer {
+  const buffer = Buffer.alloc(8);
+  buffer.writeBigUInt64BE(BigInt(value));
+  return buffer;
+}
@@ -4,6 +4,7 @@ import { execFile, execFileSync } from 'child_process';
 import { promisify } from 'util';
 import type { ProofPackage } from '@zkdpp/schemas';
 import type { PredicateDefinition } from '@zkdpp/predicate-lib';
+import { checkCircuitPin } from './circuit-manifest.js';
 
 const execFileAsync = promisify(execFile);
 
@@ -20,6 +21,7 @@ export async function verifyWithNoirCli(config: NoirVerifyConfig): Promise<{ val
     const packageName = config.predicate.circuitPath;
 
     ensureCompiled(config.nargoBin, circuitDir, packageName);
+    checkCircuitPin(config.nargoBin, circuitDir, packageName);
 
     const proofsDir = path.join(circuitDir, 'proofs');
     fs.mkdirSync(proofsDir, { recursive: true });
@@ -74,6 +76,14 @@ function buildVerifierToml(predicate: PredicateDefinition, proofPackage: ProofPa
     lines.push(`threshold = "${publicInputs.threshold}"`);
   }
 
+  if (publicInputs.minThreshold !== undefined || publicInputs.maxThreshold !== undefined) {
+    if (publicInputs.minThreshold === undefined || publicInputs.maxThreshold === undefined) {
+      throw new Error(`Both minThreshold and maxThreshold are required for ${predicate.name}`);
+    }
+    lines.push(`min_threshold = "${publicInputs.minThreshold}"`);
+    lines.push(`max_threshold = "${publicInputs.maxThreshold}"`);
+  }
+
   if (predicate.name === 'CERT_VALID') {
     const checkTs = publicInputs.timestamp ?? (publicInputs.extra as any)?.checkTimestamp;
     if (checkTs === undefined) {
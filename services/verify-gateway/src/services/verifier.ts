@@ -8,6 +8,9 @@ import { verifyWithNoirCli } from './noir-cli.js';
 
 const logger = pino({ name: 'verifier' });
 
+/** Hex of the tag the edge agent's insecure mock prover puts in front of every proof */
+const MOCK_PROOF_TAG_HEX = Buffer.from('ZKDPP_INSECURE_MOCK_PROOF_V1').toString('hex');
+
 export interface VerifierConfig {
   signingKeyId: string;
   signingKeyPrivate: string;
@@ -167,8 +170,14 @@ export class Verifier {
       return { valid: false, error: 'Invalid proof data length' };
     }
 
+    // Hex is case-insensitive, so the tag is matched on the lowercased proof
+    const mockAllowed = this.config.zkBackend === 'mock' && process.env.ALLOW_MOCK_PROOFS === 'true';
+    if (proofData.toLowerCase().startsWith(MOCK_PROOF_TAG_HEX) && !mockAllowed) {
+      return { valid: false, error: 'Mock proofs are not accepted by the verifier' };
+    }
+
     if (this.config.zkBackend === 'mock') {
-      if (process.env.ALLOW_MOCK_PROOFS !== 'true') {
+      if (!mockAllowed) {
         return { valid: false, error: 'Mock backend disabled. Set ALLOW_MOCK_PROOFS=true to enable.' };
       }
       logger.warn('ZK backend is set to mock - not suitable for production');